@@ -1,6 +1,10 @@
 package dev.shblock.tileglobemc
 
 import dev.shblock.tileglobemc.datagen.BlockDefDatagen
+import dev.shblock.tileglobemc.datagen.BlockTagResolver
+import dev.shblock.tileglobemc.datagen.ItemDefDatagen
+import dev.shblock.tileglobemc.datagen.RegistryIdsDatagen
+import net.minecraft.server.packs.PackType
 import net.neoforged.bus.api.SubscribeEvent
 import net.neoforged.fml.common.EventBusSubscriber
 import net.neoforged.fml.common.Mod
@@ -23,5 +27,8 @@ object TileGlobeMC {
     @SubscribeEvent
     fun onGatherDataServer(event: GatherDataEvent.Server) {
         event.createProvider(::BlockDefDatagen)
+        val blockTags = BlockTagResolver(event.getResourceManager(PackType.SERVER_DATA))
+        event.createProvider { output, registries -> ItemDefDatagen(output, registries, blockTags) }
+        event.createProvider(::RegistryIdsDatagen)
     }
 }
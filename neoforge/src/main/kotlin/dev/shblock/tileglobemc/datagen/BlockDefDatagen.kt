@@ -41,6 +41,11 @@ class BlockDefDatagen(
                 val idBase = possibleStates.first().id.also { blockData.addProperty("id_base", it) }
                 blockData.addProperty("total_states", possibleStates.size)
                 blockData.addProperty("default_state", block.defaultBlockState().id - idBase)
+                blockData.addProperty("hardness", block.defaultDestroyTime())
+                blockData.addProperty(
+                    "requires_correct_tool_for_drops",
+                    block.defaultBlockState().requiresCorrectToolForDrops()
+                )
 
                 val blockstatePropertiesData = JsonArray().also { blockData.add("blockstate_properties", it) }
                 for (property in block.stateDefinition.properties) {
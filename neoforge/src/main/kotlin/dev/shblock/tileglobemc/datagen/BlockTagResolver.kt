@@ -0,0 +1,36 @@
+package dev.shblock.tileglobemc.datagen
+
+import com.google.gson.JsonParser
+import net.minecraft.resources.ResourceLocation
+import net.minecraft.server.packs.resources.ResourceManager
+
+/**
+ * Reads block tags straight from the data packs, as tags aren't bound to the registries during datagen.
+ */
+class BlockTagResolver(val resourceManager: ResourceManager) {
+    private val resolved = mutableMapOf<ResourceLocation, Set<ResourceLocation>>()
+
+    /**
+     * The blocks in the tag, with nested tags expanded.
+     * Optional entries are included as well, so the blocks might not all exist.
+     */
+    fun resolve(tag: ResourceLocation): Set<ResourceLocation> = resolved[tag] ?: run {
+        val blocks = mutableSetOf<ResourceLocation>()
+        val file = ResourceLocation.fromNamespaceAndPath(tag.namespace, "tags/block/${tag.path}.json")
+        for (resource in resourceManager.getResourceStack(file)) {
+            val tagData = resource.openAsReader().use { JsonParser.parseReader(it).asJsonObject }
+            if (tagData.get("replace")?.asBoolean == true) {
+                blocks.clear()
+            }
+            for (entry in tagData.getAsJsonArray("values")) {
+                val id = if (entry.isJsonPrimitive) entry.asString else entry.asJsonObject.get("id").asString
+                if (id.startsWith("#")) {
+                    blocks += resolve(ResourceLocation.parse(id.substring(1)))
+                } else {
+                    blocks += ResourceLocation.parse(id)
+                }
+            }
+        }
+        blocks.also { resolved[tag] = it }
+    }
+}
@@ -0,0 +1,71 @@
+package dev.shblock.tileglobemc.datagen
+
+import com.google.gson.JsonArray
+import com.google.gson.JsonObject
+import net.minecraft.core.HolderLookup
+import net.minecraft.core.component.DataComponents
+import net.minecraft.core.registries.BuiltInRegistries
+import net.minecraft.core.registries.Registries
+import net.minecraft.data.CachedOutput
+import net.minecraft.data.DataProvider
+import net.minecraft.data.PackOutput
+import net.minecraft.world.item.BlockItem
+import net.minecraft.world.item.Item
+import net.minecraft.world.item.component.Tool
+import java.util.concurrent.CompletableFuture
+
+class ItemDefDatagen(
+    val packOutput: PackOutput,
+    val registries: CompletableFuture<HolderLookup.Provider>,
+    val blockTags: BlockTagResolver
+) : DataProvider {
+    override fun run(cachedOutput: CachedOutput) = registries.thenCompose { registries ->
+        CompletableFuture.allOf(
+            *registries.lookupOrThrow(Registries.ITEM).listElements().map { entry ->
+                val resLoc = entry.key().location()
+                val item = entry.value()
+
+                val itemData = JsonObject()
+
+                itemData.addProperty("resource_location", resLoc.toString())
+                itemData.addProperty("id", Item.getId(item))
+                itemData.addProperty("max_stack_size", item.defaultMaxStackSize)
+                item.components().get(DataComponents.TOOL)?.let { itemData.add("tool", toolData(it)) }
+                if (item is BlockItem) {
+                    itemData.addProperty("block", BuiltInRegistries.BLOCK.getKey(item.block).toString())
+                }
+
+                DataProvider.saveStable(
+                    cachedOutput,
+                    itemData,
+                    packOutput.outputFolder
+                        .resolve("item_def")
+                        .resolve(resLoc.namespace)
+                        .resolve("${resLoc.path}.json")
+                )
+            }.toArray { n -> arrayOfNulls<CompletableFuture<*>>(n) }
+        )
+    }
+
+    /** The tool's rules, with the blocks they apply to listed rather than referred to by tag. */
+    private fun toolData(tool: Tool) = JsonObject().also { toolData ->
+        toolData.addProperty("default_mining_speed", tool.defaultMiningSpeed())
+        toolData.add("rules", JsonArray().also { rulesData ->
+            for (rule in tool.rules()) {
+                val ruleData = JsonObject().also { rulesData.add(it) }
+                val blocks = rule.blocks().unwrapKey()
+                    .map { tag -> blockTags.resolve(tag.location()) }
+                    .orElseGet { rule.blocks().map { it.unwrapKey().orElseThrow().location() }.toSet() }
+                ruleData.add("blocks", JsonArray().also { blocksData ->
+                    blocks.filter { BuiltInRegistries.BLOCK.containsKey(it) }
+                        .sorted()
+                        .forEach { blocksData.add(it.toString()) }
+                })
+                rule.speed().ifPresent { ruleData.addProperty("speed", it) }
+                rule.correctForDrops().ifPresent { ruleData.addProperty("correct_for_drops", it) }
+            }
+        })
+    }
+
+    override fun getName() = "TileGlobeMC: ItemDef"
+}
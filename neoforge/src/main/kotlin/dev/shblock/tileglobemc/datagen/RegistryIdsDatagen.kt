@@ -0,0 +1,64 @@
+package dev.shblock.tileglobemc.datagen
+
+import com.google.gson.JsonObject
+import net.minecraft.core.HolderLookup
+import net.minecraft.core.Registry
+import net.minecraft.core.registries.BuiltInRegistries
+import net.minecraft.core.registries.Registries
+import net.minecraft.data.CachedOutput
+import net.minecraft.data.DataProvider
+import net.minecraft.data.PackOutput
+import net.minecraft.resources.ResourceKey
+import net.minecraft.resources.ResourceLocation
+import java.util.concurrent.CompletableFuture
+
+/**
+ * The network ids of the entries of registries that aren't covered by the block and item defs.
+ */
+class RegistryIdsDatagen(
+    val packOutput: PackOutput,
+    val registries: CompletableFuture<HolderLookup.Provider>
+) : DataProvider {
+    override fun run(cachedOutput: CachedOutput) = registries.thenCompose { registries ->
+        CompletableFuture.allOf(
+            save(cachedOutput, Registries.ENTITY_TYPE, builtInIds(BuiltInRegistries.ENTITY_TYPE)),
+            save(cachedOutput, Registries.BLOCK_ENTITY_TYPE, builtInIds(BuiltInRegistries.BLOCK_ENTITY_TYPE)),
+            save(cachedOutput, Registries.DATA_COMPONENT_TYPE, builtInIds(BuiltInRegistries.DATA_COMPONENT_TYPE)),
+            // data pack registries are loaded sorted by name, which is the order the ids are given in
+            save(
+                cachedOutput,
+                Registries.ENCHANTMENT,
+                registries.lookupOrThrow(Registries.ENCHANTMENT).listElementIds()
+                    .map { it.location() }
+                    .sorted()
+                    .toList()
+                    .withIndex()
+                    .associate { (id, resLoc) -> resLoc to id }
+            ),
+        )
+    }
+
+    private fun <T : Any> builtInIds(registry: Registry<T>) =
+        registry.entrySet().associate { (key, value) -> key.location() to registry.getId(value) }
+
+    private fun save(
+        cachedOutput: CachedOutput,
+        registry: ResourceKey<out Registry<*>>,
+        ids: Map<ResourceLocation, Int>
+    ): CompletableFuture<*> {
+        val registryData = JsonObject()
+        ids.entries.sortedBy { it.value }.forEach { (resLoc, id) -> registryData.addProperty(resLoc.toString(), id) }
+
+        val resLoc = registry.location()
+        return DataProvider.saveStable(
+            cachedOutput,
+            registryData,
+            packOutput.outputFolder
+                .resolve("registry_ids")
+                .resolve(resLoc.namespace)
+                .resolve("${resLoc.path}.json")
+        )
+    }
+
+    override fun getName() = "TileGlobeMC: RegistryIds"
+}
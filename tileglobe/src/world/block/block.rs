@@ -1,14 +1,64 @@
 use crate::world::block::BlockState;
 use crate::world::block::blocks::HorizontalDirection;
 use crate::world::block::misc::MapColor;
+use crate::world::block_entity::BlockEntity;
+use crate::world::item::ItemStack;
 use crate::world::world::{_World, LocalWorld, World};
+use alloc::boxed::Box;
 use core::fmt::Debug;
-use glam::Vec3;
+use glam::{Vec2, Vec3};
+use ordered_float::OrderedFloat;
 use tileglobe_utils::color::RGBA8;
 use tileglobe_utils::direction::Direction;
+use tileglobe_utils::indexed_enum::IndexedEnum;
 use tileglobe_utils::pos::BlockPos;
 use tileglobe_utils::resloc::ResLoc;
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InteractionHand {
+    MainHand,
+    OffHand,
+}
+
+/// How a block reacts to being pushed or pulled by a piston, see [`Block::push_reaction`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PushReaction {
+    Normal,
+    /// Broken and dropped instead of being moved, e.g. redstone wire.
+    Destroy,
+    /// Stops the piston, e.g. piston heads.
+    Block,
+    /// Can be pushed, but not pulled by sticky pistons.
+    PushOnly,
+}
+
+/// A player clicking a block, see [`Block::on_use`].
+#[derive(Debug, Clone)]
+pub struct UseContext {
+    /// The stack held in `hand`, possibly empty.
+    pub item: ItemStack,
+    pub hand: InteractionHand,
+    pub sneaking: bool,
+    pub face: Direction,
+    pub cursor_pos: Vec3,
+    /// (yaw, pitch) of the player in degrees.
+    pub rotation: Vec2,
+}
+
+impl UseContext {
+    /// The direction closest to where the player looks, like vanilla's `getNearestLookingDirection`.
+    pub fn nearest_looking_direction(&self) -> Direction {
+        // (cos, sin) of the angles
+        let yaw = Vec2::from_angle(self.rotation.x.to_radians());
+        let pitch = Vec2::from_angle(self.rotation.y.to_radians());
+        let look = Vec3::new(-yaw.y * pitch.x, -pitch.y, yaw.x * pitch.x);
+        *Direction::variants()
+            .iter()
+            .max_by_key(|it| OrderedFloat(it.normal_i16().as_vec3().dot(look)))
+            .unwrap()
+    }
+}
+
 #[allow(async_fn_in_trait)]
 pub trait Block: Debug + 'static {
     fn resloc(&self) -> &'static ResLoc<'static>;
@@ -31,13 +81,22 @@ pub trait Block: Debug + 'static {
         MapColor::new(RGBA8::new(0xFF, 0xFF, 0xFF, 0))
     }
 
-    async fn get_state_for_placement(
-        &self,
-        world: &_World,
-        pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> BlockState {
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Normal
+    }
+
+    /// The block entity of the block, if it has one. Created whenever the block is set.
+    fn create_block_entity(&self, blockstate: BlockState) -> Option<Box<dyn BlockEntity>> {
+        None
+    }
+
+    /// Whether [`Self::create_block_entity`] creates one, without allocating it.
+    fn has_block_entity(&self, blockstate: BlockState) -> bool {
+        false
+    }
+
+    /// The state to place for the player, see [`UseContext`] for the clicked face and where the player looks.
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
         self.default_state()
     }
 
@@ -47,19 +106,59 @@ pub trait Block: Debug + 'static {
         world.update_neighbors(pos).await;
     }
 
-    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    /// Called after the block was broken by a player. The caller drops the block itself if `drops`,
+    /// which is `false` e.g. in creative mode, blocks broken along with it drop only if `drops` as well.
+    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState, drops: bool) {
         world.update_neighbors_shape(pos).await;
         world.update_neighbors(pos).await;
     }
 
-    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {}
+    /// Called when a player clicks the block, before the held item gets used (e.g. placed against it).
+    /// Returns whether the click was consumed, in which case the item isn't used.
+    /// By default the block is used without item if clicked with the main hand, like vanilla.
+    async fn on_use(&self, world: &_World, pos: BlockPos, blockstate: BlockState, ctx: &UseContext) -> bool {
+        ctx.hand == InteractionHand::MainHand
+            && Block::on_use_without_item(self, world, pos, blockstate).await
+    }
+
+    /// Returns whether the click was consumed.
+    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) -> bool {
+        false
+    }
 
     async fn tick(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {}
 
+    /// Called every tick for blocks with a block entity.
+    async fn tick_block_entity(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {}
+
+    /// Called when the block entity was removed along with the block, e.g. to drop the contents of a container.
+    /// `blockstate` is the removed block.
+    async fn on_block_entity_removed(
+        &self,
+        world: &_World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        block_entity: Box<dyn BlockEntity>,
+    ) {
+    }
+
     async fn update(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {}
 
     async fn update_shape(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {}
 
+    /// Runs a block event queued with [`World::block_event`], like vanilla's `triggerEvent`.
+    /// Returns whether the event happened, in which case it is sent to the clients to run it as well.
+    async fn trigger_event(
+        &self,
+        world: &_World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        event_type: u8,
+        event_param: u8,
+    ) -> bool {
+        false
+    }
+
     async fn get_signal(
         &self,
         world: &_World,
@@ -89,16 +188,22 @@ pub trait DynifiedBlock: Debug + 'static {
 
     fn is_attract_redstone_wire_connection(&self, blockstate: BlockState, direction: HorizontalDirection) -> bool;
 
-    fn get_state_for_placement<'this, 'world, 'dynify>(
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction;
+
+    fn create_block_entity(&self, blockstate: BlockState) -> Option<Box<dyn BlockEntity>>;
+
+    fn has_block_entity(&self, blockstate: BlockState) -> bool;
+
+    fn get_state_for_placement<'this, 'world, 'ctx, 'dynify>(
         &'this self,
         world: &'world _World,
         pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, Direction, Vec3 => dyn 'dynify + Future<Output = BlockState>)
+        ctx: &'ctx UseContext,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, &'ctx UseContext => dyn 'dynify + Future<Output = BlockState>)
     where
         'this: 'dynify,
-        'world: 'dynify;
+        'world: 'dynify,
+        'ctx: 'dynify;
 
     fn on_placed<'this, 'world, 'dynify>(
         &'this self,
@@ -115,17 +220,30 @@ pub trait DynifiedBlock: Debug + 'static {
         world: &'world _World,
         pos: BlockPos,
         blockstate: BlockState,
-    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = ()>)
+        drops: bool,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, bool => dyn 'dynify + Future<Output = ()>)
     where
         'this: 'dynify,
         'world: 'dynify;
 
+    fn on_use<'this, 'world, 'ctx, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        ctx: &'ctx UseContext,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, &'ctx UseContext => dyn 'dynify + Future<Output = bool>)
+    where
+        'this: 'dynify,
+        'world: 'dynify,
+        'ctx: 'dynify;
+
     fn on_use_without_item<'this, 'world, 'dynify>(
         &'this self,
         world: &'world _World,
         pos: BlockPos,
         blockstate: BlockState,
-    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = ()>)
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = bool>)
     where
         'this: 'dynify,
         'world: 'dynify;
@@ -140,6 +258,27 @@ pub trait DynifiedBlock: Debug + 'static {
         'this: 'dynify,
         'world: 'dynify;
 
+    fn tick_block_entity<'this, 'world, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = ()>)
+    where
+        'this: 'dynify,
+        'world: 'dynify;
+
+    fn on_block_entity_removed<'this, 'world, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        block_entity: Box<dyn BlockEntity>,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, Box<dyn BlockEntity> => dyn 'dynify + Future<Output = ()>)
+    where
+        'this: 'dynify,
+        'world: 'dynify;
+
     fn update<'this, 'world, 'dynify>(
         &'this self,
         world: &'world _World,
@@ -160,6 +299,18 @@ pub trait DynifiedBlock: Debug + 'static {
         'this: 'dynify,
         'world: 'dynify;
 
+    fn trigger_event<'this, 'world, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        event_type: u8,
+        event_param: u8,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, u8, u8 => dyn 'dynify + Future<Output = bool>)
+    where
+        'this: 'dynify,
+        'world: 'dynify;
+
     fn get_signal<'this, 'world, 'dynify>(
         &'this self,
         world: &'world _World,
@@ -200,24 +351,35 @@ impl<BlockImplementor: Block> DynifiedBlock for BlockImplementor {
         BlockImplementor::is_attract_redstone_wire_connection(self, blockstate, direction)
     }
 
-    fn get_state_for_placement<'this, 'world, 'dynify>(
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        BlockImplementor::push_reaction(self, blockstate)
+    }
+
+    fn create_block_entity(&self, blockstate: BlockState) -> Option<Box<dyn BlockEntity>> {
+        BlockImplementor::create_block_entity(self, blockstate)
+    }
+
+    fn has_block_entity(&self, blockstate: BlockState) -> bool {
+        BlockImplementor::has_block_entity(self, blockstate)
+    }
+
+    fn get_state_for_placement<'this, 'world, 'ctx, 'dynify>(
         &'this self,
         world: &'world _World,
         pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, Direction, Vec3 => dyn 'dynify + Future<Output = BlockState>)
+        ctx: &'ctx UseContext,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, &'ctx UseContext => dyn 'dynify + Future<Output = BlockState>)
     where
         'this: 'dynify,
         'world: 'dynify,
+        'ctx: 'dynify,
     {
         dynify::from_fn!(
             BlockImplementor::get_state_for_placement,
             self,
             world,
             pos,
-            face,
-            cursor_pos
+            ctx
         )
     }
 
@@ -245,7 +407,8 @@ impl<BlockImplementor: Block> DynifiedBlock for BlockImplementor {
         world: &'world _World,
         pos: BlockPos,
         blockstate: BlockState,
-    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = ()>)
+        drops: bool,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, bool => dyn 'dynify + Future<Output = ()>)
     where
         'this: 'dynify,
         'world: 'dynify,
@@ -255,7 +418,30 @@ impl<BlockImplementor: Block> DynifiedBlock for BlockImplementor {
             self,
             world,
             pos,
-            blockstate
+            blockstate,
+            drops
+        )
+    }
+
+    fn on_use<'this, 'world, 'ctx, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        ctx: &'ctx UseContext,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, &'ctx UseContext => dyn 'dynify + Future<Output = bool>)
+    where
+        'this: 'dynify,
+        'world: 'dynify,
+        'ctx: 'dynify,
+    {
+        dynify::from_fn!(
+            BlockImplementor::on_use,
+            self,
+            world,
+            pos,
+            blockstate,
+            ctx
         )
     }
 
@@ -264,7 +450,7 @@ impl<BlockImplementor: Block> DynifiedBlock for BlockImplementor {
         world: &'world _World,
         pos: BlockPos,
         blockstate: BlockState,
-    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = ()>)
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = bool>)
     where
         'this: 'dynify,
         'world: 'dynify,
@@ -291,6 +477,40 @@ impl<BlockImplementor: Block> DynifiedBlock for BlockImplementor {
         dynify::from_fn!(BlockImplementor::tick, self, world, pos, blockstate)
     }
 
+    fn tick_block_entity<'this, 'world, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState => dyn 'dynify + Future<Output = ()>)
+    where
+        'this: 'dynify,
+        'world: 'dynify,
+    {
+        dynify::from_fn!(BlockImplementor::tick_block_entity, self, world, pos, blockstate)
+    }
+
+    fn on_block_entity_removed<'this, 'world, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        block_entity: Box<dyn BlockEntity>,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, Box<dyn BlockEntity> => dyn 'dynify + Future<Output = ()>)
+    where
+        'this: 'dynify,
+        'world: 'dynify,
+    {
+        dynify::from_fn!(
+            BlockImplementor::on_block_entity_removed,
+            self,
+            world,
+            pos,
+            blockstate,
+            block_entity
+        )
+    }
+
     fn update<'this, 'world, 'dynify>(
         &'this self,
         world: &'world _World,
@@ -316,6 +536,29 @@ impl<BlockImplementor: Block> DynifiedBlock for BlockImplementor {
         dynify::from_fn!(BlockImplementor::update_shape, self, world, pos, blockstate)
     }
 
+    fn trigger_event<'this, 'world, 'dynify>(
+        &'this self,
+        world: &'world _World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        event_type: u8,
+        event_param: u8,
+    ) -> dynify::Fn!(&'this Self, &'world _World, BlockPos, BlockState, u8, u8 => dyn 'dynify + Future<Output = bool>)
+    where
+        'this: 'dynify,
+        'world: 'dynify,
+    {
+        dynify::from_fn!(
+            BlockImplementor::trigger_event,
+            self,
+            world,
+            pos,
+            blockstate,
+            event_type,
+            event_param
+        )
+    }
+
     fn get_signal<'this, 'world, 'dynify>(
         &'this self,
         world: &'world _World,
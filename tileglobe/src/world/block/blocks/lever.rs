@@ -1,10 +1,9 @@
 use crate::world::block::blocks::{AttachFace, HorizontalDirection};
 use crate::world::block::{
     Block, BlockResLocs, BlockState, BlockStateImpl, BoolProperty, EnumProperty, Property,
-    SimpleBlockState, StateId, StateIdType,
+    PushReaction, SimpleBlockState, StateId, StateIdType, UseContext,
 };
 use crate::world::world::{_World, World};
-use glam::Vec3;
 use tileglobe_proc_macro::mc_block_id_base;
 use tileglobe_utils::direction::Direction;
 use tileglobe_utils::pos::BlockPos;
@@ -35,27 +34,25 @@ impl Block for LeverBlock {
         BlockState(mc_block_id_base!("lever") + 9)
     }
 
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Destroy
+    }
+
     fn is_attract_redstone_wire_connection(&self, blockstate: BlockState, direction: HorizontalDirection) -> bool {
         true
     }
 
 
-    async fn get_state_for_placement(
-        &self,
-        world: &_World,
-        pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> BlockState {
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
         let mut state = LeverState::from(self.default_state());
-        state.set_facing(match face {
+        state.set_facing(match ctx.face {
             Direction::NORTH => HorizontalDirection::NORTH,
             Direction::SOUTH => HorizontalDirection::SOUTH,
             Direction::WEST => HorizontalDirection::WEST,
             Direction::EAST => HorizontalDirection::EAST,
             _ => HorizontalDirection::NORTH,
         });
-        state.set_face(match face {
+        state.set_face(match ctx.face {
             Direction::DOWN => AttachFace::CEILING,
             Direction::UP => AttachFace::FLOOR,
             _ => AttachFace::WALL,
@@ -63,13 +60,14 @@ impl Block for LeverBlock {
         state.into()
     }
 
-    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) -> bool {
         let mut state = LeverState::from(blockstate);
         state.set_powered(!state.powered());
         let _ = world.set_block_state(pos, state.into()).await;
         world.update_neighbors(pos).await;
         let direction = Self::facing_direction(state);
         world.update_neighbors_except_for_direction(pos.offset_dir(direction), direction.opposite()).await;
+        true
     }
 
     async fn get_signal(
@@ -1,5 +1,6 @@
 mod generic;
 pub mod lever;
+pub mod piston;
 pub mod redstone_wire;
 pub mod redstone_repeater;
 pub mod redstone_comparator;
@@ -102,4 +103,61 @@ impl HorizontalDirection {
             HorizontalDirection::EAST => HorizontalDirection::NORTH,
         }
     }
-}
\ No newline at end of file
+}
+
+/// The `facing` property of blocks that can face all directions, in vanilla's order of its values.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+#[repr(u8)]
+enum Facing {
+    NORTH,
+    EAST,
+    SOUTH,
+    WEST,
+    UP,
+    DOWN,
+}
+impl IndexedEnum for Facing {
+    type Index = u8;
+    const VARIANTS: &'static [Self] = &[
+        Self::NORTH,
+        Self::EAST,
+        Self::SOUTH,
+        Self::WEST,
+        Self::UP,
+        Self::DOWN,
+    ];
+}
+impl From<u8> for Facing {
+    fn from(value: u8) -> Self {
+        Self::variants()[value as usize]
+    }
+}
+impl From<Facing> for u8 {
+    fn from(value: Facing) -> Self {
+        value as Self
+    }
+}
+impl From<Direction> for Facing {
+    fn from(value: Direction) -> Self {
+        match value {
+            Direction::NORTH => Self::NORTH,
+            Direction::EAST => Self::EAST,
+            Direction::SOUTH => Self::SOUTH,
+            Direction::WEST => Self::WEST,
+            Direction::UP => Self::UP,
+            Direction::DOWN => Self::DOWN,
+        }
+    }
+}
+impl Facing {
+    fn direction(self) -> Direction {
+        match self {
+            Facing::NORTH => Direction::NORTH,
+            Facing::EAST => Direction::EAST,
+            Facing::SOUTH => Direction::SOUTH,
+            Facing::WEST => Direction::WEST,
+            Facing::UP => Direction::UP,
+            Facing::DOWN => Direction::DOWN,
+        }
+    }
+}
@@ -0,0 +1,970 @@
+use crate::world::block::blocks::Facing;
+use crate::world::block::{
+    Block, BlockResLocs, BlockState, BlockStateImpl, BoolProperty, EnumProperty, Property, PushReaction,
+    SimpleBlockState, StateId, UseContext,
+};
+use crate::world::block_entity::{BlockEntity, BlockEntityType};
+use crate::world::entity::Entity;
+use crate::world::item::ItemStack;
+use crate::world::world::{_World, World};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use dynify::Dynify;
+use smallvec::SmallVec;
+use tileglobe_proc_macro::mc_block_id_base;
+use tileglobe_utils::direction::Direction;
+use tileglobe_utils::indexed_enum::IndexedEnum;
+use tileglobe_utils::nbt::{Compound, Tag};
+use tileglobe_utils::pos::BlockPos;
+use tileglobe_utils::resloc::ResLoc;
+
+/// Most blocks a piston moves at once.
+const PUSH_LIMIT: usize = 12;
+
+/// Block event types of pistons, the param is the direction the piston faces.
+const EVENT_EXTEND: u8 = 0;
+const EVENT_RETRACT: u8 = 1;
+/// Retracting before the head finished extending, sticky pistons leave the block in front behind.
+const EVENT_RETRACT_INSTANTLY: u8 = 2;
+
+/// Blocks that pistons never move, besides unbreakable ones.
+const IMMOVABLE_BLOCKS: &[&ResLoc] = &[
+    BlockResLocs::OBSIDIAN,
+    BlockResLocs::CRYING_OBSIDIAN,
+    BlockResLocs::RESPAWN_ANCHOR,
+    BlockResLocs::REINFORCED_DEEPSLATE,
+];
+
+/// A block being moved by a piston, placed with a `moving_piston` where the block ends up.
+/// The moving piston turns into the moved block once done, like vanilla's `PistonMovingBlockEntity`.
+#[derive(Debug)]
+pub struct PistonMovingBlockEntity {
+    pub moved_state: BlockState,
+    /// The direction the piston faces, the block moves the other way when pulled.
+    pub facing: Direction,
+    /// From 0 to 1 in steps of 0.5 per tick, the block is placed the tick after reaching 1.
+    pub progress: f32,
+    /// `progress` before the last tick, what vanilla's `getProgress(0)` returns.
+    pub progress_o: f32,
+    pub extending: bool,
+    /// Whether the moved block is the piston head or the retracting piston itself.
+    pub source: bool,
+    /// [`World::current_tick`] of the last tick, `None` before the first.
+    pub last_ticked: Option<u32>,
+}
+impl PistonMovingBlockEntity {
+    pub fn new(moved_state: BlockState, facing: Direction, extending: bool, source: bool) -> Self {
+        Self {
+            moved_state,
+            facing,
+            progress: 0.0,
+            progress_o: 0.0,
+            extending,
+            source,
+            last_ticked: None,
+        }
+    }
+}
+impl Default for PistonMovingBlockEntity {
+    fn default() -> Self {
+        Self::new(BlockState::default(), Direction::NORTH, false, false)
+    }
+}
+impl BlockEntity for PistonMovingBlockEntity {
+    fn block_entity_type(&self) -> &'static BlockEntityType {
+        &BlockEntityType::PISTON
+    }
+
+    /// The moved state by name and properties, and the progress before the last tick, like vanilla.
+    fn client_data(&self) -> Option<Compound> {
+        let mut nbt = Compound::new();
+        nbt.insert("blockState", Tag::Compound(self.moved_state.to_nbt()));
+        nbt.insert("facing", Tag::Int(u8::from(self.facing) as i32));
+        nbt.insert("progress", Tag::Float(self.progress_o));
+        nbt.insert("extending", Tag::Byte(self.extending as i8));
+        nbt.insert("source", Tag::Byte(self.source as i8));
+        Some(nbt)
+    }
+}
+
+/// `piston` and `sticky_piston`.
+#[derive(Debug)]
+pub struct PistonBlock {
+    pub(crate) sticky: bool,
+}
+impl PistonBlock {
+    pub fn is_piston(blockstate: BlockState) -> bool {
+        (mc_block_id_base!("piston")..(mc_block_id_base!("piston") + 12)).contains(&blockstate.0)
+            || Self::is_sticky_piston(blockstate)
+    }
+
+    fn is_sticky_piston(blockstate: BlockState) -> bool {
+        (mc_block_id_base!("sticky_piston")..(mc_block_id_base!("sticky_piston") + 12)).contains(&blockstate.0)
+    }
+
+    fn piston_type(&self) -> PistonType {
+        if self.sticky { PistonType::STICKY } else { PistonType::NORMAL }
+    }
+
+    /// Whether the piston is powered, like vanilla's `getNeighborSignal`. Besides the neighbors except the one
+    /// in front, this includes the neighbors of the block above, which is known as quasi-connectivity.
+    async fn has_neighbor_signal(world: &_World, pos: BlockPos, facing: Direction) -> bool {
+        for &direction in Direction::variants() {
+            if direction != facing && world.get_signal(pos.offset_dir(direction), direction.opposite()).await > 0 {
+                return true;
+            }
+        }
+        let above = pos.offset_dir(Direction::UP);
+        for &direction in Direction::variants() {
+            if direction != Direction::DOWN
+                && world.get_signal(above.offset_dir(direction), direction.opposite()).await > 0
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Queues extending or retracting if the piston isn't in the state its power asks for, like vanilla's
+    /// `checkIfExtend`. The blocks move when the block event runs.
+    async fn check_if_extend(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+        let state = PistonBaseState::from(blockstate);
+        let powered = Self::has_neighbor_signal(world, pos, state.facing).await;
+        if powered && !state.extended {
+            if PistonStructureResolver::new(world, pos, state.facing, true).resolve().await {
+                world.block_event(pos, self.resloc(), EVENT_EXTEND, state.facing.into()).await;
+            }
+        } else if !powered && state.extended {
+            // a block still being pushed in front stays where it is if it moved less than half a block,
+            // it already ticked this tick, or the scheduled ticks run
+            let front_pos = offset(pos, state.facing, 2);
+            let current_tick = world.current_tick().await;
+            let handling_scheduled_ticks = world.is_handling_scheduled_ticks().await;
+            let mut event_type = EVENT_RETRACT;
+            if let Ok(front) = world.get_block_state(front_pos).await
+                && MovingPistonBlock::is_moving_piston(front)
+                && MovingPistonState::from(front).facing() == state.facing
+                && world
+                    .with_block_entity(front_pos, |it: &mut PistonMovingBlockEntity| {
+                        it.extending
+                            && (it.progress_o < 0.5 || it.last_ticked == Some(current_tick) || handling_scheduled_ticks)
+                    })
+                    .await
+                    == Some(true)
+            {
+                event_type = EVENT_RETRACT_INSTANTLY;
+            }
+            world.block_event(pos, self.resloc(), event_type, state.facing.into()).await;
+        }
+    }
+
+    /// Moves the blocks in front of the piston one block, like vanilla's `moveBlocks`.
+    /// Returns `false` if they can't be moved.
+    async fn move_blocks(&self, world: &_World, pos: BlockPos, facing: Direction, extending: bool) -> bool {
+        let head_pos = pos.offset_dir(facing);
+        if !extending && world.get_block_state(head_pos).await.is_ok_and(PistonHeadBlock::is_piston_head) {
+            let _ = world.set_block_state(head_pos, BlockState(0)).await;
+        }
+
+        let mut resolver = PistonStructureResolver::new(world, pos, facing, extending);
+        if !resolver.resolve().await {
+            return false;
+        }
+
+        // the drops of the blocks in the way are added first, if the world is full of entities nothing moves
+        let mut drops = Vec::new();
+        for &destroyed_pos in &resolver.to_destroy {
+            let destroyed = world.get_block_state(destroyed_pos).await.unwrap_or_default();
+            drops.extend(resource_entity(world, destroyed_pos, destroyed));
+        }
+        if world.add_entities(drops).await.is_err() {
+            return false;
+        }
+
+        let mut moved_states = Vec::with_capacity(resolver.to_push.len());
+        for &moved_pos in &resolver.to_push {
+            moved_states.push(world.get_block_state(moved_pos).await.unwrap_or_default());
+        }
+        // positions left behind, turned into air unless a block moves in
+        let mut vacated = resolver.to_push.clone();
+        let direction = if extending { facing } else { facing.opposite() };
+
+        for &destroyed_pos in resolver.to_destroy.iter().rev() {
+            let _ = world.set_block_state(destroyed_pos, BlockState(0)).await;
+        }
+        for (&moved_pos, &moved_state) in resolver.to_push.iter().zip(&moved_states).rev() {
+            let target_pos = moved_pos.offset_dir(direction);
+            vacated.retain(|&it| it != target_pos);
+            let moving = MovingPistonState::new(facing, PistonType::NORMAL);
+            let block_entity = PistonMovingBlockEntity::new(moved_state, facing, extending, false);
+            MovingPistonBlock::place(world, target_pos, moving, block_entity).await;
+        }
+        if extending {
+            vacated.retain(|&it| it != head_pos);
+            let head = PistonHeadState::new(facing, self.piston_type());
+            let moving = MovingPistonState::new(facing, self.piston_type());
+            let block_entity = PistonMovingBlockEntity::new(head.block_state(), facing, true, true);
+            MovingPistonBlock::place(world, head_pos, moving, block_entity).await;
+        }
+
+        for &vacated_pos in &vacated {
+            let _ = world.set_block_state(vacated_pos, BlockState(0)).await;
+        }
+        for &vacated_pos in &vacated {
+            world.update_neighbors_shape(vacated_pos).await;
+        }
+        for &destroyed_pos in resolver.to_destroy.iter().rev() {
+            world.update_neighbors(destroyed_pos).await;
+        }
+        for &moved_pos in resolver.to_push.iter().rev() {
+            world.update_neighbors(moved_pos).await;
+        }
+        if extending {
+            world.update_neighbors(head_pos).await;
+        }
+        true
+    }
+}
+impl Block for PistonBlock {
+    fn resloc(&self) -> &'static ResLoc<'static> {
+        if self.sticky {
+            BlockResLocs::STICKY_PISTON
+        } else {
+            BlockResLocs::PISTON
+        }
+    }
+
+    fn default_state(&self) -> BlockState {
+        PistonBaseState {
+            sticky: self.sticky,
+            extended: false,
+            facing: Direction::NORTH,
+        }
+        .block_state()
+    }
+
+    /// Faces the player, i.e. against the direction they look in the most.
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
+        PistonBaseState {
+            sticky: self.sticky,
+            extended: false,
+            facing: ctx.nearest_looking_direction().opposite(),
+        }
+        .block_state()
+    }
+
+    async fn on_placed(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+        world.update_neighbors_shape(pos).await;
+        world.update_neighbors(pos).await;
+        self.check_if_extend(world, pos, blockstate).await;
+    }
+
+    async fn update(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+        self.check_if_extend(world, pos, blockstate).await;
+    }
+
+    async fn trigger_event(
+        &self,
+        world: &_World,
+        pos: BlockPos,
+        blockstate: BlockState,
+        event_type: u8,
+        event_param: u8,
+    ) -> bool {
+        let mut state = PistonBaseState::from(blockstate);
+        let facing = state.facing;
+        // the power may have changed back since the event was queued
+        let powered = Self::has_neighbor_signal(world, pos, facing).await;
+        if powered && (event_type == EVENT_RETRACT || event_type == EVENT_RETRACT_INSTANTLY) {
+            state.extended = true;
+            let _ = world.set_block_state(pos, state.block_state()).await;
+            return false;
+        }
+        if !powered && event_type == EVENT_EXTEND {
+            return false;
+        }
+
+        if event_type == EVENT_EXTEND {
+            if !self.move_blocks(world, pos, facing, true).await {
+                return false;
+            }
+            state.extended = true;
+            if let Ok(_) = world.set_block_state(pos, state.block_state()).await {
+                world.update_neighbors(pos).await;
+                world.update_neighbors_shape(pos).await;
+            }
+            return true;
+        }
+
+        let head_pos = pos.offset_dir(facing);
+        MovingPistonBlock::finish_moving(world, head_pos, true).await;
+
+        let retracted = PistonBaseState {
+            sticky: self.sticky,
+            extended: false,
+            facing: Direction::from(event_param % Direction::variants().len() as u8),
+        };
+        let moving = MovingPistonState::new(facing, self.piston_type());
+        let block_entity = PistonMovingBlockEntity::new(retracted.block_state(), facing, false, true);
+        if !MovingPistonBlock::place(world, pos, moving, block_entity).await {
+            return false;
+        }
+        world.update_neighbors(pos).await;
+        world.update_neighbors_shape(pos).await;
+
+        if self.sticky {
+            let pulled_pos = head_pos.offset_dir(facing);
+            let pulled = world.get_block_state(pulled_pos).await.unwrap_or_default();
+            if MovingPistonBlock::is_moving_piston(pulled)
+                && world
+                    .with_block_entity(pulled_pos, |it: &mut PistonMovingBlockEntity| {
+                        it.facing == facing && it.extending
+                    })
+                    .await
+                    == Some(true)
+            {
+                // the block is still being pushed, it stays where it was pushed to
+                MovingPistonBlock::finish_moving(world, pulled_pos, true).await;
+            } else if event_type == EVENT_RETRACT
+                && !pulled.is_air()
+                && is_pushable(world, pulled_pos, facing.opposite(), false, facing).await
+                && (pulled.get_block().push_reaction(pulled) == PushReaction::Normal || Self::is_piston(pulled))
+            {
+                self.move_blocks(world, pos, facing, false).await;
+            } else {
+                remove_block(world, head_pos).await;
+            }
+        } else {
+            remove_block(world, head_pos).await;
+        }
+        true
+    }
+}
+
+/// The arm of an extended piston.
+#[derive(Debug)]
+pub struct PistonHeadBlock;
+impl PistonHeadBlock {
+    pub fn is_piston_head(blockstate: BlockState) -> bool {
+        (mc_block_id_base!("piston_head")..(mc_block_id_base!("piston_head") + 24)).contains(&blockstate.0)
+    }
+
+    /// Whether the block behind the head is the extended piston it belongs to.
+    async fn is_fitting_base(world: &_World, pos: BlockPos, state: PistonHeadState) -> bool {
+        let facing = state.facing();
+        let Ok(base) = world.get_block_state(pos.offset_dir(facing.opposite())).await else {
+            return false;
+        };
+        if !PistonBlock::is_piston(base) {
+            return false;
+        }
+        let base = PistonBaseState::from(base);
+        base.extended && base.facing == facing && base.sticky == (state.piston_type() == PistonType::STICKY)
+    }
+
+    /// Whether the head still has its piston, which may be moving as well.
+    async fn can_survive(world: &_World, pos: BlockPos, state: PistonHeadState) -> bool {
+        if Self::is_fitting_base(world, pos, state).await {
+            return true;
+        }
+        world
+            .get_block_state(pos.offset_dir(state.facing().opposite()))
+            .await
+            .is_ok_and(|it| {
+                MovingPistonBlock::is_moving_piston(it) && MovingPistonState::from(it).facing() == state.facing()
+            })
+    }
+}
+impl Block for PistonHeadBlock {
+    fn resloc(&self) -> &'static ResLoc<'static> {
+        BlockResLocs::PISTON_HEAD
+    }
+
+    fn default_state(&self) -> BlockState {
+        PistonHeadState::new(Direction::NORTH, PistonType::NORMAL).block_state()
+    }
+
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Block
+    }
+
+    /// Breaking the head breaks its piston as well, which drops like it was broken itself.
+    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState, drops: bool) {
+        let state = PistonHeadState::from(blockstate);
+        let base_pos = pos.offset_dir(state.facing().opposite());
+        // the piston stays if its drop doesn't fit in the world
+        if Self::is_fitting_base(world, pos, state).await
+            && let Ok(base) = world.get_block_state(base_pos).await
+            && (!drops
+                || match resource_entity(world, base_pos, base) {
+                    Some(entity) => world.add_entity(entity).await.is_ok(),
+                    None => true,
+                })
+            && let Ok(_) = world.set_block_state(base_pos, BlockState(0)).await
+        {
+            world.update_neighbors_shape(base_pos).await;
+            world.update_neighbors(base_pos).await;
+        }
+        world.update_neighbors_shape(pos).await;
+        world.update_neighbors(pos).await;
+    }
+
+    async fn update(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+        let state = PistonHeadState::from(blockstate);
+        if Self::can_survive(world, pos, state).await {
+            world.update_block(pos.offset_dir(state.facing().opposite())).await;
+        }
+    }
+
+    async fn update_shape(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+        if !Self::can_survive(world, pos, blockstate.into()).await
+            && let Ok(_) = world.set_block_state(pos, BlockState(0)).await
+        {
+            world.update_neighbors_shape(pos).await;
+        }
+    }
+}
+
+/// Placeholder of a block moved by a piston, see [`PistonMovingBlockEntity`].
+#[derive(Debug)]
+pub struct MovingPistonBlock;
+impl MovingPistonBlock {
+    pub fn is_moving_piston(blockstate: BlockState) -> bool {
+        (mc_block_id_base!("moving_piston")..(mc_block_id_base!("moving_piston") + 12)).contains(&blockstate.0)
+    }
+
+    /// Places a moving piston with the block entity. `false` if the position is outside of the world.
+    async fn place(
+        world: &_World,
+        pos: BlockPos,
+        state: MovingPistonState,
+        block_entity: PistonMovingBlockEntity,
+    ) -> bool {
+        if world.set_block_state(pos, state.into()).await.is_err() {
+            return false;
+        }
+        world
+            .with_block_entity(pos, |it: &mut PistonMovingBlockEntity| *it = block_entity)
+            .await;
+        true
+    }
+
+    /// Places the moved block, if the block at the position is still moving. When `cut_short`, i.e. the piston
+    /// retracts again meanwhile, a moving piston head is removed instead, like vanilla's `finalTick`.
+    async fn finish_moving(world: &_World, pos: BlockPos, cut_short: bool) {
+        if !world.get_block_state(pos).await.is_ok_and(Self::is_moving_piston) {
+            return;
+        }
+        let Some(Some((moved_state, source))) = world
+            .with_block_entity(pos, |it: &mut PistonMovingBlockEntity| {
+                // like vanilla's finalTick, a block that finished moving was placed by its tick already
+                if cut_short && it.progress_o >= 1.0 {
+                    return None;
+                }
+                it.progress = 1.0;
+                it.progress_o = 1.0;
+                Some((it.moved_state, it.source))
+            })
+            .await
+        else {
+            return;
+        };
+        let placed = if cut_short && source { BlockState(0) } else { moved_state };
+        if let Ok(_) = world.set_block_state(pos, placed).await {
+            let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+            placed.get_block().on_placed(world, pos, placed).init(&mut c).await;
+        }
+    }
+}
+impl Block for MovingPistonBlock {
+    fn resloc(&self) -> &'static ResLoc<'static> {
+        BlockResLocs::MOVING_PISTON
+    }
+
+    fn default_state(&self) -> BlockState {
+        MovingPistonState::new(Direction::NORTH, PistonType::NORMAL).block_state()
+    }
+
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Block
+    }
+
+    fn create_block_entity(&self, blockstate: BlockState) -> Option<Box<dyn BlockEntity>> {
+        Some(Box::new(PistonMovingBlockEntity::default()))
+    }
+
+    fn has_block_entity(&self, blockstate: BlockState) -> bool {
+        true
+    }
+
+    async fn tick_block_entity(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+        let current_tick = world.current_tick().await;
+        let done = world
+            .with_block_entity(pos, |it: &mut PistonMovingBlockEntity| {
+                it.last_ticked = Some(current_tick);
+                it.progress_o = it.progress;
+                if it.progress_o >= 1.0 {
+                    return true;
+                }
+                it.progress = (it.progress + 0.5).min(1.0);
+                false
+            })
+            .await;
+        if done == Some(true) {
+            Self::finish_moving(world, pos, false).await;
+        }
+    }
+}
+
+/// Finds the blocks a piston moves, like vanilla's `PistonStructureResolver`.
+/// Slime and honey blocks pull the blocks stuck to them along, except each other.
+struct PistonStructureResolver<'a> {
+    world: &'a _World,
+    piston_pos: BlockPos,
+    piston_facing: Direction,
+    push_direction: Direction,
+    start_pos: BlockPos,
+    extending: bool,
+    /// The blocks to move, those in front of the others come last.
+    to_push: Vec<BlockPos>,
+    /// Blocks in the way that break, e.g. redstone wire.
+    to_destroy: Vec<BlockPos>,
+}
+impl<'a> PistonStructureResolver<'a> {
+    fn new(world: &'a _World, piston_pos: BlockPos, piston_facing: Direction, extending: bool) -> Self {
+        let (push_direction, start_pos) = if extending {
+            (piston_facing, piston_pos.offset_dir(piston_facing))
+        } else {
+            (piston_facing.opposite(), offset(piston_pos, piston_facing, 2))
+        };
+        Self {
+            world,
+            piston_pos,
+            piston_facing,
+            push_direction,
+            start_pos,
+            extending,
+            to_push: Vec::new(),
+            to_destroy: Vec::new(),
+        }
+    }
+
+    /// Air outside of the world.
+    async fn block_state(&self, pos: BlockPos) -> BlockState {
+        self.world.get_block_state(pos).await.unwrap_or_default()
+    }
+
+    /// Returns whether the blocks can be moved.
+    async fn resolve(&mut self) -> bool {
+        self.to_push.clear();
+        self.to_destroy.clear();
+
+        let start = self.block_state(self.start_pos).await;
+        if !is_pushable(self.world, self.start_pos, self.push_direction, false, self.piston_facing).await {
+            if self.extending && start.get_block().push_reaction(start) == PushReaction::Destroy {
+                self.to_destroy.push(self.start_pos);
+                return true;
+            }
+            return false;
+        }
+        if !self.add_block_line(self.start_pos, self.push_direction).await {
+            return false;
+        }
+        // grows while the blocks stuck to the sides are added
+        let mut i = 0;
+        while let Some(&pos) = self.to_push.get(i) {
+            if is_sticky(self.block_state(pos).await) && !self.add_branching_blocks(pos).await {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Adds the block and the ones in front of it, and behind it if they stick to it.
+    /// `direction` is the side of the block it was reached from.
+    async fn add_block_line(&mut self, origin: BlockPos, direction: Direction) -> bool {
+        let mut state = self.block_state(origin).await;
+        if state.is_air()
+            || !is_pushable(self.world, origin, self.push_direction, false, direction).await
+            || origin == self.piston_pos
+            || self.to_push.contains(&origin)
+        {
+            return true;
+        }
+
+        let mut line_length = 1;
+        if line_length + self.to_push.len() > PUSH_LIMIT {
+            return false;
+        }
+        while is_sticky(state) {
+            let pos = offset(origin, self.push_direction.opposite(), line_length);
+            let previous = state;
+            state = self.block_state(pos).await;
+            if state.is_air()
+                || !can_stick_to_each_other(previous, state)
+                || !is_pushable(self.world, pos, self.push_direction, false, self.push_direction.opposite()).await
+                || pos == self.piston_pos
+            {
+                break;
+            }
+            line_length += 1;
+            if line_length + self.to_push.len() > PUSH_LIMIT {
+                return false;
+            }
+        }
+
+        let mut added = 0;
+        for distance in (0..line_length).rev() {
+            self.to_push.push(offset(origin, self.push_direction.opposite(), distance));
+            added += 1;
+        }
+
+        let mut distance = 1;
+        loop {
+            let pos = offset(origin, self.push_direction, distance);
+            if let Some(index) = self.to_push.iter().position(|&it| it == pos) {
+                // the line runs into blocks added before, which have to move after it
+                self.to_push[index..].rotate_right(added);
+                for i in 0..=index + added {
+                    let pos = self.to_push[i];
+                    if is_sticky(self.block_state(pos).await) && !self.add_branching_blocks(pos).await {
+                        return false;
+                    }
+                }
+                return true;
+            }
+
+            let state = self.block_state(pos).await;
+            if state.is_air() {
+                return true;
+            }
+            if !is_pushable(self.world, pos, self.push_direction, true, self.push_direction).await
+                || pos == self.piston_pos
+            {
+                return false;
+            }
+            if state.get_block().push_reaction(state) == PushReaction::Destroy {
+                self.to_destroy.push(pos);
+                return true;
+            }
+            if self.to_push.len() >= PUSH_LIMIT {
+                return false;
+            }
+            self.to_push.push(pos);
+            added += 1;
+            distance += 1;
+        }
+    }
+
+    /// Adds the blocks stuck to the sides of the sticky block.
+    async fn add_branching_blocks(&mut self, from: BlockPos) -> bool {
+        let state = self.block_state(from).await;
+        for &direction in Direction::variants() {
+            if direction == self.push_direction || direction == self.push_direction.opposite() {
+                continue;
+            }
+            let pos = from.offset_dir(direction);
+            if can_stick_to_each_other(self.block_state(pos).await, state)
+                && !Box::pin(self.add_block_line(pos, direction)).await
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Whether the block can be moved in the direction, like vanilla's `PistonBaseBlock.isPushable`.
+/// Blocks breaking instead only count if `allow_destroy`.
+async fn is_pushable(
+    world: &_World,
+    pos: BlockPos,
+    direction: Direction,
+    allow_destroy: bool,
+    piston_facing: Direction,
+) -> bool {
+    let Ok(blockstate) = world.get_block_state(pos).await else {
+        return false;
+    };
+    if blockstate.is_air() {
+        return true;
+    }
+    if IMMOVABLE_BLOCKS.contains(&blockstate.get_block().resloc()) {
+        return false;
+    }
+    // blocks aren't moved out of the world
+    if world.get_block_state(pos.offset_dir(direction)).await.is_err() {
+        return false;
+    }
+    if PistonBlock::is_piston(blockstate) {
+        if PistonBaseState::from(blockstate).extended {
+            return false;
+        }
+    } else {
+        if blockstate.properties().hardness < 0.0 {
+            return false;
+        }
+        match blockstate.get_block().push_reaction(blockstate) {
+            PushReaction::Normal => {}
+            PushReaction::Destroy => return allow_destroy,
+            PushReaction::Block => return false,
+            PushReaction::PushOnly => return direction == piston_facing,
+        }
+    }
+    !blockstate.get_block().has_block_entity(blockstate)
+}
+
+fn is_slime(blockstate: BlockState) -> bool {
+    blockstate.get_block().resloc() == BlockResLocs::SLIME_BLOCK
+}
+
+fn is_honey(blockstate: BlockState) -> bool {
+    blockstate.get_block().resloc() == BlockResLocs::HONEY_BLOCK
+}
+
+fn is_sticky(blockstate: BlockState) -> bool {
+    is_slime(blockstate) || is_honey(blockstate)
+}
+
+fn can_stick_to_each_other(a: BlockState, b: BlockState) -> bool {
+    if (is_honey(a) && is_slime(b)) || (is_slime(a) && is_honey(b)) {
+        return false;
+    }
+    is_sticky(a) || is_sticky(b)
+}
+
+fn offset(pos: BlockPos, direction: Direction, distance: usize) -> BlockPos {
+    BlockPos::from(*pos + direction.normal_i16() * distance as i16)
+}
+
+/// Removes the block, updating its neighbors.
+async fn remove_block(world: &_World, pos: BlockPos) {
+    if let Ok(_) = world.set_block_state(pos, BlockState(0)).await {
+        world.update_neighbors(pos).await;
+        world.update_neighbors_shape(pos).await;
+    }
+}
+
+/// The item entity a block broken without tool drops, `None` if it drops nothing.
+fn resource_entity(world: &_World, pos: BlockPos, blockstate: BlockState) -> Option<Entity> {
+    if blockstate.properties().requires_correct_tool_for_drops {
+        return None;
+    }
+    let item = blockstate.as_item()?;
+    Some(Entity::dropped_item(world.allocate_entity_id(), pos, ItemStack::new(item.id, 1)))
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+#[repr(u8)]
+enum PistonType {
+    NORMAL,
+    STICKY,
+}
+impl IndexedEnum for PistonType {
+    type Index = u8;
+    const VARIANTS: &'static [Self] = &[Self::NORMAL, Self::STICKY];
+}
+impl From<u8> for PistonType {
+    fn from(value: u8) -> Self {
+        Self::variants()[value as usize]
+    }
+}
+impl From<PistonType> for u8 {
+    fn from(value: PistonType) -> Self {
+        value as Self
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct PistonBaseState {
+    sticky: bool,
+    extended: bool,
+    facing: Direction,
+}
+impl PistonBaseState {
+    fn block_state(self) -> BlockState {
+        if self.sticky {
+            let mut state = StickyPistonState(StateId(0));
+            BoolProperty::set(&mut state, self.extended);
+            EnumProperty::set(&mut state, Facing::from(self.facing));
+            state.block_state()
+        } else {
+            let mut state = PistonState(StateId(0));
+            BoolProperty::set(&mut state, self.extended);
+            EnumProperty::set(&mut state, Facing::from(self.facing));
+            state.block_state()
+        }
+    }
+}
+impl From<BlockState> for PistonBaseState {
+    fn from(value: BlockState) -> Self {
+        if PistonBlock::is_sticky_piston(value) {
+            let state = StickyPistonState::from(value);
+            Self {
+                sticky: true,
+                extended: BoolProperty::get(state),
+                facing: EnumProperty::<Facing, 1>::get(state).direction(),
+            }
+        } else {
+            let state = PistonState::from(value);
+            Self {
+                sticky: false,
+                extended: BoolProperty::get(state),
+                facing: EnumProperty::<Facing, 1>::get(state).direction(),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct PistonState(StateId);
+impl BoolProperty<6> for PistonState {} // extended
+impl Property<bool, 2, 6> for PistonState {}
+impl EnumProperty<Facing, 1> for PistonState {} // facing
+impl Property<Facing, 6, 1> for PistonState {}
+impl BlockStateImpl for PistonState {
+    fn block_state(self) -> BlockState {
+        BlockState(mc_block_id_base!("piston") + self.state_id().0)
+    }
+}
+impl SimpleBlockState for PistonState {
+    fn from_state_id(id: StateId) -> Self {
+        Self(id)
+    }
+
+    fn state_id(self) -> StateId {
+        self.0
+    }
+
+    fn set_state_id(&mut self, id: StateId) {
+        self.0 = id;
+    }
+}
+impl From<BlockState> for PistonState {
+    fn from(value: BlockState) -> Self {
+        Self(StateId(value.0 - mc_block_id_base!("piston")))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct StickyPistonState(StateId);
+impl BoolProperty<6> for StickyPistonState {} // extended
+impl Property<bool, 2, 6> for StickyPistonState {}
+impl EnumProperty<Facing, 1> for StickyPistonState {} // facing
+impl Property<Facing, 6, 1> for StickyPistonState {}
+impl BlockStateImpl for StickyPistonState {
+    fn block_state(self) -> BlockState {
+        BlockState(mc_block_id_base!("sticky_piston") + self.state_id().0)
+    }
+}
+impl SimpleBlockState for StickyPistonState {
+    fn from_state_id(id: StateId) -> Self {
+        Self(id)
+    }
+
+    fn state_id(self) -> StateId {
+        self.0
+    }
+
+    fn set_state_id(&mut self, id: StateId) {
+        self.0 = id;
+    }
+}
+impl From<BlockState> for StickyPistonState {
+    fn from(value: BlockState) -> Self {
+        Self(StateId(value.0 - mc_block_id_base!("sticky_piston")))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct PistonHeadState(StateId);
+impl EnumProperty<Facing, 4> for PistonHeadState {} // facing
+impl Property<Facing, 6, 4> for PistonHeadState {}
+impl BoolProperty<2> for PistonHeadState {} // short
+impl Property<bool, 2, 2> for PistonHeadState {}
+impl EnumProperty<PistonType, 1> for PistonHeadState {} // type
+impl Property<PistonType, 2, 1> for PistonHeadState {}
+impl PistonHeadState {
+    fn new(facing: Direction, piston_type: PistonType) -> Self {
+        let mut state = Self(StateId(0));
+        <Self as EnumProperty<Facing, 4>>::set(&mut state, facing.into());
+        <Self as BoolProperty<2>>::set(&mut state, false);
+        <Self as EnumProperty<PistonType, 1>>::set(&mut state, piston_type);
+        state
+    }
+
+    fn facing(self) -> Direction {
+        <Self as EnumProperty<Facing, 4>>::get(self).direction()
+    }
+
+    fn piston_type(self) -> PistonType {
+        <Self as EnumProperty<PistonType, 1>>::get(self)
+    }
+}
+impl BlockStateImpl for PistonHeadState {
+    fn block_state(self) -> BlockState {
+        BlockState(mc_block_id_base!("piston_head") + self.state_id().0)
+    }
+}
+impl SimpleBlockState for PistonHeadState {
+    fn from_state_id(id: StateId) -> Self {
+        Self(id)
+    }
+
+    fn state_id(self) -> StateId {
+        self.0
+    }
+
+    fn set_state_id(&mut self, id: StateId) {
+        self.0 = id;
+    }
+}
+impl From<BlockState> for PistonHeadState {
+    fn from(value: BlockState) -> Self {
+        Self(StateId(value.0 - mc_block_id_base!("piston_head")))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct MovingPistonState(StateId);
+impl EnumProperty<Facing, 2> for MovingPistonState {} // facing
+impl Property<Facing, 6, 2> for MovingPistonState {}
+impl EnumProperty<PistonType, 1> for MovingPistonState {} // type
+impl Property<PistonType, 2, 1> for MovingPistonState {}
+impl MovingPistonState {
+    fn new(facing: Direction, piston_type: PistonType) -> Self {
+        let mut state = Self(StateId(0));
+        <Self as EnumProperty<Facing, 2>>::set(&mut state, facing.into());
+        <Self as EnumProperty<PistonType, 1>>::set(&mut state, piston_type);
+        state
+    }
+
+    fn facing(self) -> Direction {
+        <Self as EnumProperty<Facing, 2>>::get(self).direction()
+    }
+}
+impl BlockStateImpl for MovingPistonState {
+    fn block_state(self) -> BlockState {
+        BlockState(mc_block_id_base!("moving_piston") + self.state_id().0)
+    }
+}
+impl SimpleBlockState for MovingPistonState {
+    fn from_state_id(id: StateId) -> Self {
+        Self(id)
+    }
+
+    fn state_id(self) -> StateId {
+        self.0
+    }
+
+    fn set_state_id(&mut self, id: StateId) {
+        self.0 = id;
+    }
+}
+impl From<BlockState> for MovingPistonState {
+    fn from(value: BlockState) -> Self {
+        Self(StateId(value.0 - mc_block_id_base!("moving_piston")))
+    }
+}
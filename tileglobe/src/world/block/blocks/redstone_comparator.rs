@@ -3,14 +3,13 @@ use crate::world::block::blocks::redstone_repeater::RedstoneRepeaterBlock;
 use crate::world::block::blocks::redstone_wire::RedstoneWireBlock;
 use crate::world::block::{
     Block, BlockResLocs, BlockState, BlockStateImpl, BoolProperty, EnumProperty, Property,
-    SimpleBlockState, StateId,
+    PushReaction, SimpleBlockState, StateId, UseContext,
 };
+use crate::world::block_entity::{BlockEntity, BlockEntityType};
 use crate::world::world::{_World, World};
-use alloc::collections::BTreeMap;
+use alloc::boxed::Box;
 use core::cmp::max;
-use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
-use embassy_sync::mutex::Mutex;
-use glam::{Vec2, Vec3, Vec3Swizzles};
+use glam::{Vec2, Vec3Swizzles};
 use ordered_float::OrderedFloat;
 use tileglobe_proc_macro::mc_block_id_base;
 use tileglobe_utils::direction::Direction;
@@ -18,8 +17,15 @@ use tileglobe_utils::indexed_enum::IndexedEnum;
 use tileglobe_utils::pos::BlockPos;
 use tileglobe_utils::resloc::ResLoc;
 
-static SIGNALS: Mutex<CriticalSectionRawMutex, BTreeMap<BlockPos, u8>> =
-    Mutex::new(BTreeMap::new()); // hack! should actually be implemented with blockentity
+#[derive(Debug, Default)]
+pub struct ComparatorBlockEntity {
+    pub output_signal: u8,
+}
+impl BlockEntity for ComparatorBlockEntity {
+    fn block_entity_type(&self) -> &'static BlockEntityType {
+        &BlockEntityType::COMPARATOR
+    }
+}
 
 #[derive(Debug)]
 pub struct RedstoneComparatorBlock;
@@ -64,6 +70,13 @@ impl RedstoneComparatorBlock {
         }
     }
 
+    async fn get_output(world: &_World, pos: BlockPos) -> u8 {
+        world
+            .with_block_entity(pos, |it: &mut ComparatorBlockEntity| it.output_signal)
+            .await
+            .unwrap_or(0)
+    }
+
     async fn calculate_output(world: &_World, pos: BlockPos, state: RedstoneComparatorState) -> u8 {
         let input = Self::get_input(world, pos, state.facing()).await;
         let side_input = max(
@@ -91,6 +104,10 @@ impl Block for RedstoneComparatorBlock {
         BlockState(mc_block_id_base!("comparator") + 1)
     }
 
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Destroy
+    }
+
     fn is_attract_redstone_wire_connection(
         &self,
         blockstate: BlockState,
@@ -99,13 +116,15 @@ impl Block for RedstoneComparatorBlock {
         true
     }
 
-    async fn get_state_for_placement(
-        &self,
-        world: &_World,
-        pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> BlockState {
+    fn create_block_entity(&self, blockstate: BlockState) -> Option<Box<dyn BlockEntity>> {
+        Some(Box::new(ComparatorBlockEntity::default()))
+    }
+
+    fn has_block_entity(&self, blockstate: BlockState) -> bool {
+        true
+    }
+
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
         let mut state = RedstoneComparatorState::from(self.default_state());
 
         state.set_facing(
@@ -117,7 +136,7 @@ impl Block for RedstoneComparatorBlock {
                             .normal_i16()
                             .xz()
                             .as_vec2()
-                            .dot(cursor_pos.xz() - Vec2::new(0.5, 0.5)),
+                            .dot(ctx.cursor_pos.xz() - Vec2::new(0.5, 0.5)),
                     )
                 })
                 .map(|it| HorizontalDirection::try_from(it.direction().opposite()).unwrap())
@@ -126,11 +145,7 @@ impl Block for RedstoneComparatorBlock {
         state.block_state()
     }
 
-    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
-        SIGNALS.lock().await.remove(&pos);
-    }
-
-    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) -> bool {
         let mut state = RedstoneComparatorState::from(blockstate);
         state.set_mode(if state.mode() == Mode::COMPARE {
             Mode::SUBTRACT
@@ -138,9 +153,10 @@ impl Block for RedstoneComparatorBlock {
             Mode::COMPARE
         });
         if let Ok(_) = world.set_block_state(pos, state.into()).await {
-            world.schedule_tick(pos, 2, 0).await;
+            world.schedule_tick(pos, blockstate, 2, 0).await;
             world.update_neighbors(pos).await;
         }
+        true
     }
 
     async fn tick(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
@@ -152,7 +168,11 @@ impl Block for RedstoneComparatorBlock {
             let _ = world.set_block_state(pos, state.into()).await;
         }
 
-        let old_output = SIGNALS.lock().await.insert(pos, output);
+        let old_output = world
+            .with_block_entity(pos, |it: &mut ComparatorBlockEntity| {
+                core::mem::replace(&mut it.output_signal, output)
+            })
+            .await;
         if old_output != Some(output) {
             world.update_neighbors(pos).await;
             world
@@ -165,10 +185,10 @@ impl Block for RedstoneComparatorBlock {
     }
 
     async fn update(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
-        if Self::calculate_output(world, pos, blockstate.into()).await
-            != SIGNALS.lock().await.get(&pos).cloned().unwrap_or(0)
+        if !world.will_tick_this_tick(pos, blockstate).await
+            && Self::calculate_output(world, pos, blockstate.into()).await != Self::get_output(world, pos).await
         {
-            world.schedule_tick(pos, 2, 0).await;
+            world.schedule_tick(pos, blockstate, 2, 0).await;
         }
     }
 
@@ -181,7 +201,7 @@ impl Block for RedstoneComparatorBlock {
     ) -> u8 {
         let state = RedstoneComparatorState::from(blockstate);
         if state.facing().direction().opposite() == direction {
-            SIGNALS.lock().await.get(&pos).cloned().unwrap_or(0)
+            Self::get_output(world, pos).await
         } else {
             0
         }
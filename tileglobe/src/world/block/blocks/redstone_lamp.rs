@@ -37,7 +37,7 @@ impl Block for RedstoneLampBlock {
             }
         } else {
             if !has_signal {
-                world.schedule_tick(pos, 4, 0).await;
+                world.schedule_tick(pos, blockstate, 4, 0).await;
             }
         }
     }
@@ -1,10 +1,10 @@
 use crate::world::block::blocks::HorizontalDirection;
 use crate::world::block::{
     Block, BlockResLocs, BlockState, BlockStateImpl, BoolProperty, EnumProperty, IntProperty,
-    Property, SimpleBlockState, StateId,
+    Property, PushReaction, SimpleBlockState, StateId, UseContext,
 };
 use crate::world::world::{_World, World};
-use glam::{Vec2, Vec3, Vec3Swizzles};
+use glam::{Vec2, Vec3Swizzles};
 use ordered_float::OrderedFloat;
 use tileglobe_proc_macro::mc_block_id_base;
 use tileglobe_utils::direction::Direction;
@@ -38,6 +38,10 @@ impl Block for RedstoneRepeaterBlock {
         BlockState(mc_block_id_base!("repeater") + 3)
     }
 
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Destroy
+    }
+
     fn is_attract_redstone_wire_connection(
         &self,
         blockstate: BlockState,
@@ -51,13 +55,7 @@ impl Block for RedstoneRepeaterBlock {
                 == direction.direction()
     }
 
-    async fn get_state_for_placement(
-        &self,
-        world: &_World,
-        pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> BlockState {
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
         let mut state = RedstoneRepeaterState::from(self.default_state());
 
         state.set_facing(
@@ -69,7 +67,7 @@ impl Block for RedstoneRepeaterBlock {
                             .normal_i16()
                             .xz()
                             .as_vec2()
-                            .dot(cursor_pos.xz() - Vec2::new(0.5, 0.5)),
+                            .dot(ctx.cursor_pos.xz() - Vec2::new(0.5, 0.5)),
                     )
                 })
                 .map(|it| HorizontalDirection::try_from(it.direction().opposite()).unwrap())
@@ -78,7 +76,7 @@ impl Block for RedstoneRepeaterBlock {
         state.block_state()
     }
 
-    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) -> bool {
         let mut state = RedstoneRepeaterState::from(blockstate);
         state.set_delay(if state.delay() == 4 {
             1
@@ -88,6 +86,7 @@ impl Block for RedstoneRepeaterBlock {
         if let Ok(_) = world.set_block_state(pos, state.into()).await {
             world.update_block(pos).await;
         }
+        true
     }
 
     async fn tick(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
@@ -96,7 +95,7 @@ impl Block for RedstoneRepeaterBlock {
 
         if !state.powered() {
             if !has_input {
-                world.schedule_tick(pos, (state.delay() * 2) as u8, 0).await;
+                world.schedule_tick(pos, blockstate, (state.delay() * 2) as u8, 0).await;
             }
             state.set_powered(true);
         } else {
@@ -121,8 +120,8 @@ impl Block for RedstoneRepeaterBlock {
     async fn update(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
         let state = RedstoneRepeaterState::from(blockstate);
         let has_input = Self::get_input_signal(world, pos, blockstate).await > 0;
-        if has_input != state.powered() {
-            world.schedule_tick(pos, (state.delay() * 2) as u8, 0).await;
+        if has_input != state.powered() && !world.will_tick_this_tick(pos, blockstate).await {
+            world.schedule_tick(pos, blockstate, (state.delay() * 2) as u8, 0).await;
         }
     }
 
@@ -1,10 +1,9 @@
 use crate::world::block::blocks::HorizontalDirection;
 use crate::world::block::{
     Block, BlockResLocs, BlockState, BlockStateImpl, BoolProperty, EnumProperty, Property,
-    SimpleBlockState, StateId,
+    PushReaction, SimpleBlockState, StateId, UseContext,
 };
 use crate::world::world::{_World, World};
-use glam::Vec3;
 use tileglobe_proc_macro::mc_block_id_base;
 use tileglobe_utils::direction::Direction;
 use tileglobe_utils::pos::BlockPos;
@@ -42,6 +41,10 @@ impl Block for RedstoneTorchBlock {
         }
     }
 
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Destroy
+    }
+
     fn is_attract_redstone_wire_connection(
         &self,
         blockstate: BlockState,
@@ -50,14 +53,8 @@ impl Block for RedstoneTorchBlock {
         true
     }
 
-    async fn get_state_for_placement(
-        &self,
-        world: &_World,
-        pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> BlockState {
-        let block_face = match face {
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
+        let block_face = match ctx.face {
             Direction::DOWN | Direction::UP => Direction::DOWN,
             dir => dir.opposite(),
         };
@@ -73,7 +70,7 @@ impl Block for RedstoneTorchBlock {
         Self::update_neighbors(world, pos).await;
     }
 
-    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState, drops: bool) {
         world.update_neighbors_shape(pos).await;
         Self::update_neighbors(world, pos).await;
     }
@@ -91,8 +88,10 @@ impl Block for RedstoneTorchBlock {
 
     async fn update(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
         let state = RedstoneTorchState::from(blockstate);
-        if state.lit != Self::should_output(world, pos, state).await {
-            world.schedule_tick(pos, 2, 0).await;
+        if state.lit != Self::should_output(world, pos, state).await
+            && !world.will_tick_this_tick(pos, blockstate).await
+        {
+            world.schedule_tick(pos, blockstate, 2, 0).await;
         }
     }
 
@@ -1,14 +1,14 @@
 use crate::world::block::blocks::HorizontalDirection;
 use crate::world::block::{
     Block, BlockResLocs, BlockState, BlockStateImpl, EnumProperty, IntProperty, Property,
-    SimpleBlockState, StateId,
+    PushReaction, SimpleBlockState, StateId, UseContext,
 };
 use crate::world::world::{_World, World};
 use core::cmp::max;
 use core::mem::MaybeUninit;
 use defmt_or_log::{Debug2Format, info};
 use dynify::Dynify;
-use glam::{I16Vec3, Vec3};
+use glam::I16Vec3;
 use itertools::iproduct;
 use smallvec::SmallVec;
 use tileglobe_proc_macro::mc_block_id_base;
@@ -251,6 +251,10 @@ impl Block for RedstoneWireBlock {
         RedstoneWireState::default().block_state()
     }
 
+    fn push_reaction(&self, blockstate: BlockState) -> PushReaction {
+        PushReaction::Destroy
+    }
+
     fn is_attract_redstone_wire_connection(
         &self,
         blockstate: BlockState,
@@ -259,13 +263,7 @@ impl Block for RedstoneWireBlock {
         true
     }
 
-    async fn get_state_for_placement(
-        &self,
-        world: &_World,
-        pos: BlockPos,
-        face: Direction,
-        cursor_pos: Vec3,
-    ) -> BlockState {
+    async fn get_state_for_placement(&self, world: &_World, pos: BlockPos, ctx: &UseContext) -> BlockState {
         Self::update_state_shape(world, pos, RedstoneWireState::default())
             .await
             .block_state()
@@ -277,12 +275,12 @@ impl Block for RedstoneWireBlock {
         Self::update_neighbors(world, pos).await;
     }
 
-    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    async fn on_destroyed(&self, world: &_World, pos: BlockPos, blockstate: BlockState, drops: bool) {
         Self::update_neighbors_shape(world, pos).await;
         Self::update_neighbors(world, pos).await;
     }
 
-    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
+    async fn on_use_without_item(&self, world: &_World, pos: BlockPos, blockstate: BlockState) -> bool {
         let mut state = RedstoneWireState::from(blockstate);
         let is_dot = state.is_dot();
         let is_star = state.is_star();
@@ -300,7 +298,9 @@ impl Block for RedstoneWireBlock {
             if let Ok(_) = world.set_block_state(pos, state.block_state()).await {
                 Self::update_neighbors(world, pos).await;
             }
+            return true;
         }
+        false
     }
 
     async fn tick(&self, world: &_World, pos: BlockPos, blockstate: BlockState) {
@@ -318,7 +318,7 @@ impl Block for RedstoneWireBlock {
                 Self::update_neighbors(world, pos).await;
                 // for update_pos in Self::update_positions(pos) {
                 //     world.update_block(update_pos).await;
-                // world.schedule_tick(update_pos, 1, 0).await;
+                // world.schedule_tick(update_pos, self.resloc(), 1, 0).await;
                 // }
             }
         }
@@ -9,8 +9,11 @@ use core::mem::MaybeUninit;
 use defmt_or_log::maybe_derive_format;
 use smallvec::SmallVec;
 pub use registry::*;
+use alloc::string::ToString;
 use tileglobe_utils::indexed_enum::IndexedEnum;
+use tileglobe_utils::nbt::{Compound, Tag};
 use tileglobe_utils::pos::BlockPos;
+use crate::world::item::{Item, Items};
 use crate::world::world::_World;
 
 pub type BlockStateType = u16;
@@ -24,10 +27,45 @@ impl BlockState {
     pub fn get_block(self) -> &'static dyn DynifiedBlock {
         Blocks.get_block(self)
     }
+
+    /// Network id of the block, not of the state.
+    pub fn block_id(self) -> u32 {
+        Blocks.get_block_id(self)
+    }
+
+    pub fn properties(self) -> &'static BlockProperties {
+        Blocks.get_properties(self)
+    }
+
+    /// The name and the properties of the state, like vanilla's `NbtUtils.writeBlockState`.
+    pub fn to_nbt(self) -> Compound {
+        let state_id = self.0 - Blocks.get_id_base(self);
+        let mut properties = Compound::new();
+        for property in self.properties().state_properties {
+            let value = property.values[(state_id / property.id_group_size) as usize % property.values.len()];
+            properties.insert(property.name, Tag::String(value.into()));
+        }
+        let mut nbt = Compound::new();
+        nbt.insert("Name", Tag::String(self.get_block().resloc().to_string()));
+        if !properties.is_empty() {
+            nbt.insert("Properties", Tag::Compound(properties));
+        }
+        nbt
+    }
     
     pub fn is_air(self) -> bool {
         self.0 == 0 // TODO: include cave_air & void_air
     }
+
+    /// The item of the block, `None` for blocks without one like air or fire.
+    pub fn as_item(self) -> Option<&'static Item> {
+        Items.get_item_for_block(self)
+    }
+
+    /// Whether placing a block here replaces this one instead of going next to it.
+    pub fn is_replaceable(self) -> bool {
+        self.is_air() // TODO: include fluids, short grass, snow layers...
+    }
 }
 
 impl Default for BlockState {
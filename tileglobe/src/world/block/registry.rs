@@ -1,5 +1,6 @@
 use crate::world::block::blocks::GenericBlock;
 use crate::world::block::blocks::lever::LeverBlock;
+use crate::world::block::blocks::piston::{MovingPistonBlock, PistonBlock, PistonHeadBlock};
 use crate::world::block::blocks::redstone_block::RedstoneBlock;
 use crate::world::block::blocks::redstone_comparator::RedstoneComparatorBlock;
 use crate::world::block::blocks::redstone_repeater::RedstoneRepeaterBlock;
@@ -25,6 +26,10 @@ impl Blocks {
             "redstone_torch" => &RedstoneTorchBlock { torch_type: RedstoneTorchType::Floor },
             "redstone_wall_torch" => &RedstoneTorchBlock { torch_type: RedstoneTorchType::Wall },
             "redstone_lamp" => &RedstoneLampBlock,
+            "piston" => &PistonBlock { sticky: false },
+            "sticky_piston" => &PistonBlock { sticky: true },
+            "piston_head" => &PistonHeadBlock,
+            "moving_piston" => &MovingPistonBlock,
             "iron_block" => &_TmpBlock {
                 resloc: BlockResLocs::IRON_BLOCK,
                 id_base: mc_block_id_base!("iron_block"),
@@ -91,13 +96,51 @@ impl Blocks {
         }
     };
 
-    pub(super) fn get_block(&self, bs: BlockState) -> &'static dyn DynifiedBlock {
+    const _PROPERTIES_SORTED: &[BlockProperties] = &tileglobe_proc_macro::mc_blocks_properties!();
+
+    fn index(&self, bs: BlockState) -> usize {
         match Self::_ID_BASE_TO_BLOCK_SORTED.binary_search_by_key(&bs.0, |&(id, _)| id) {
-            Ok(idx) => Self::_ID_BASE_TO_BLOCK_SORTED[idx].1,
+            Ok(idx) => idx,
             Err(0) => unreachable!(),
-            Err(idx) => Self::_ID_BASE_TO_BLOCK_SORTED[idx - 1].1,
+            Err(idx) => idx - 1,
         }
     }
+
+    pub(super) fn get_block(&self, bs: BlockState) -> &'static dyn DynifiedBlock {
+        Self::_ID_BASE_TO_BLOCK_SORTED[self.index(bs)].1
+    }
+
+    /// Index in the `minecraft:block` registry, the blocks are sorted by their first state.
+    pub(super) fn get_block_id(&self, bs: BlockState) -> u32 {
+        self.index(bs) as u32
+    }
+
+    pub(super) fn get_properties(&self, bs: BlockState) -> &'static BlockProperties {
+        &Self::_PROPERTIES_SORTED[self.index(bs)]
+    }
+
+    /// The first state of the block.
+    pub(super) fn get_id_base(&self, bs: BlockState) -> BlockStateType {
+        Self::_ID_BASE_TO_BLOCK_SORTED[self.index(bs)].0
+    }
+}
+
+#[derive(Debug)]
+pub struct BlockProperties {
+    /// Also called destroy time, -1 for unbreakable blocks.
+    pub hardness: f32,
+    pub requires_correct_tool_for_drops: bool,
+    pub state_properties: &'static [StateProperty],
+}
+
+/// A property of the block states of a block, e.g. `facing`.
+#[derive(Debug)]
+pub struct StateProperty {
+    pub name: &'static str,
+    /// As vanilla names them, in the order of the state ids.
+    pub values: &'static [&'static str],
+    /// How many consecutive states share the same value.
+    pub id_group_size: StateIdType,
 }
 
 pub struct BlockResLocs;
@@ -0,0 +1,47 @@
+use core::any::Any;
+use core::fmt::Debug;
+use tileglobe_proc_macro::mc_registry_id;
+use tileglobe_utils::nbt::Compound;
+use tileglobe_utils::resloc::ResLoc;
+
+/// An entry of the `minecraft:block_entity_type` registry.
+#[derive(Debug)]
+pub struct BlockEntityType {
+    pub resloc: &'static ResLoc<'static>,
+    /// Network id
+    pub id: u32,
+}
+
+impl BlockEntityType {
+    pub const COMPARATOR: Self = Self {
+        resloc: &ResLoc::new("minecraft", "comparator"),
+        id: mc_registry_id!("block_entity_type", "comparator"),
+    };
+
+    pub const PISTON: Self = Self {
+        resloc: &ResLoc::new("minecraft", "piston"),
+        id: mc_registry_id!("block_entity_type", "piston"),
+    };
+}
+
+/// Extra state of a block that doesn't fit in its block state, e.g. the output signal of a comparator.
+/// Created by [`crate::world::block::Block::create_block_entity`] when the block is set, and removed with the block.
+pub trait BlockEntity: Any + Debug {
+    fn block_entity_type(&self) -> &'static BlockEntityType;
+
+    /// Data clients need to render the block, e.g. the text of a sign.
+    /// `None` for block entities whose data is server-side only.
+    fn client_data(&self) -> Option<Compound> {
+        None
+    }
+}
+
+impl dyn BlockEntity {
+    pub fn downcast_ref<T: BlockEntity>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: BlockEntity>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Any).downcast_mut()
+    }
+}
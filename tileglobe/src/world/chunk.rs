@@ -1,14 +1,21 @@
 use crate::world::block::BlockState;
-use alloc::collections::BTreeSet;
+use crate::world::block_entity::BlockEntity;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
 use alloc::vec::Vec;
 use core::ops::RangeInclusive;
-use tileglobe_utils::network::{EIOError, MCPacketBuffer, WriteNumPrimitive, WriteVarInt};
-use tileglobe_utils::pos::{ChunkLocalPos, ChunkPos};
+use embedded_io_async::Write;
+use tileglobe_utils::network::{BufferPool, EIOError, MCPacketBuffer, WriteBlockPos, WriteNumPrimitive, WriteVarInt};
+use tileglobe_utils::pos::{BlockPos, ChunkLocalPos, ChunkPos};
 
 pub struct Chunk {
     sections: Vec<ChunkSection>,
     bottom_section: i8,
-    //TODO: entities
+    block_entities: BTreeMap<ChunkLocalPos, Box<dyn BlockEntity>>,
+    /// Block entities whose client data has to be sent again.
+    block_entity_changes: BTreeSet<ChunkLocalPos>,
+    /// Ids of the entities inside the chunk, the entities themselves are kept by the world.
+    entities: BTreeSet<i32>,
 }
 
 impl Chunk {
@@ -17,6 +24,9 @@ impl Chunk {
         Self {
             sections: sections.map(|y| ChunkSection::new(y)).collect(),
             bottom_section: bottom,
+            block_entities: BTreeMap::new(),
+            block_entity_changes: BTreeSet::new(),
+            entities: BTreeSet::new(),
         }
     }
 
@@ -48,20 +58,157 @@ impl Chunk {
             .set_block_state(pos.section_block_index(), bs))
     }
 
+    pub fn get_block_entity(&self, pos: ChunkLocalPos) -> Option<&dyn BlockEntity> {
+        self.block_entities.get(&pos).map(|it| it.as_ref())
+    }
+
+    pub fn get_block_entity_mut(&mut self, pos: ChunkLocalPos) -> Option<&mut dyn BlockEntity> {
+        self.block_entities.get_mut(&pos).map(|it| it.as_mut())
+    }
+
+    /// Returns the replaced block entity, if any.
+    pub fn set_block_entity(
+        &mut self,
+        pos: ChunkLocalPos,
+        block_entity: Box<dyn BlockEntity>,
+    ) -> Option<Box<dyn BlockEntity>> {
+        self.block_entity_changes.insert(pos);
+        self.block_entities.insert(pos, block_entity)
+    }
+
+    pub fn remove_block_entity(&mut self, pos: ChunkLocalPos) -> Option<Box<dyn BlockEntity>> {
+        self.block_entity_changes.remove(&pos);
+        self.block_entities.remove(&pos)
+    }
+
+    /// Sends the client data of the block entity again with the next block updates.
+    pub fn mark_block_entity_changed(&mut self, pos: ChunkLocalPos) {
+        if self.block_entities.contains_key(&pos) {
+            self.block_entity_changes.insert(pos);
+        }
+    }
+
+    pub fn block_entity_positions(&self) -> impl Iterator<Item = ChunkLocalPos> + '_ {
+        self.block_entities.keys().copied()
+    }
+
+    pub fn add_entity(&mut self, id: i32) {
+        self.entities.insert(id);
+    }
+
+    pub fn remove_entity(&mut self, id: i32) -> bool {
+        self.entities.remove(&id)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = i32> + '_ {
+        self.entities.iter().copied()
+    }
+
+    /// Writes the block entity list of the chunk data packet.
+    pub async fn serialize_block_entities_into<W: embedded_io_async::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), EIOError<W::Error>> {
+        writer.write_varint(self.block_entities.len() as u32).await?;
+        let mut data = Vec::new();
+        for (pos, block_entity) in &self.block_entities {
+            writer.write_be(pos.x() << 4 | pos.z()).await?;
+            writer.write_be(pos.y()).await?;
+            writer.write_varint(block_entity.block_entity_type().id).await?;
+            data.clear();
+            block_entity.client_data().unwrap_or_default().encode(&mut data);
+            writer.write_all(&data).await?;
+        }
+        Ok(())
+    }
+
+    /// Upper bound of the size written by [`Self::serialize_block_entities_into`].
+    pub fn block_entities_serialized_size(&self) -> usize {
+        let mut size = 5;
+        for block_entity in self.block_entities.values() {
+            size += 1 + 2 + 5 + block_entity.client_data().unwrap_or_default().encoded_size();
+        }
+        size
+    }
+
+    fn block_pos(chunk_pos: ChunkPos, pos: ChunkLocalPos) -> BlockPos {
+        BlockPos::new(
+            chunk_pos.x * 16 + pos.x() as i16,
+            pos.y(),
+            chunk_pos.y * 16 + pos.z() as i16,
+        )
+    }
+
     pub async fn gen_blocks_update_packets_and_clear_changes(
         &mut self,
         chunk_pos: ChunkPos,
-    ) -> Vec<MCPacketBuffer> {
-        let mut vec = Vec::<MCPacketBuffer>::with_capacity(self.sections.len());
+        packets: &mut Vec<MCPacketBuffer>,
+    ) {
         for section in self.sections.iter_mut() {
             if let Some(pkt) = section
                 .gen_blocks_update_packet_and_clear_changes(chunk_pos)
                 .await
             {
-                vec.push(pkt);
+                packets.push(pkt);
+            }
+        }
+        // after the blocks, the client only keeps block entities of the right block
+        for pos in core::mem::take(&mut self.block_entity_changes) {
+            let Some(block_entity) = self.block_entities.get(&pos) else {
+                continue;
+            };
+            if let Some(data) = block_entity.client_data() {
+                let mut encoded = Vec::with_capacity(data.encoded_size());
+                data.encode(&mut encoded);
+                let mut pkt = MCPacketBuffer::with_capacity(6, 8 + 5 + encoded.len()).await; // block_entity_data
+                pkt.write_block_pos(Self::block_pos(chunk_pos, pos)).await.unwrap();
+                pkt.write_varint(block_entity.block_entity_type().id).await.unwrap();
+                pkt.write_all(&encoded).await.unwrap();
+                packets.push(pkt);
             }
         }
-        vec
+    }
+
+    /// Size of the chunk data as written by [`crate::world::world::World::write_net_chunk`],
+    /// excluding the heightmaps, block entities and light data.
+    pub fn serialized_size(&self) -> usize {
+        self.sections
+            .iter()
+            .map(|section| section.serialized_size() + 1 + 1)
+            .sum()
+    }
+}
+
+/// Most sections of a world keeping their network encoding cached at once.
+const MAX_CACHED_SECTIONS: usize = 64;
+
+/// The sections of a world that may keep their network encoding, least recently sent first.
+/// Sections whose encoding was cleared by a block change stay in here until evicted.
+pub(crate) struct CachedSections(VecDeque<(ChunkPos, i8)>);
+
+impl CachedSections {
+    pub fn new() -> Self {
+        Self(VecDeque::new())
+    }
+
+    /// Marks the section as the most recently sent one, `false` if there's no room left for it.
+    pub fn touch(&mut self, section: (ChunkPos, i8)) -> bool {
+        if let Some(index) = self.0.iter().position(|&it| it == section) {
+            self.0.remove(index);
+        } else if self.0.len() >= MAX_CACHED_SECTIONS {
+            return false;
+        }
+        self.0.push_back(section);
+        true
+    }
+
+    /// The section to clear the encoding of to make room for another one.
+    pub fn least_recent(&self) -> Option<(ChunkPos, i8)> {
+        self.0.front().copied()
+    }
+
+    pub fn remove(&mut self, section: (ChunkPos, i8)) {
+        self.0.retain(|&it| it != section);
     }
 }
 
@@ -70,6 +217,9 @@ pub struct ChunkSection {
     blocks: [BlockState; 16 * 16 * 16],
     non_air_blocks: u16,
     changes: BTreeSet<u16>,
+    /// Network encoding of the section, reused until a block changes.
+    /// Pooled, and only kept for the sections of the world's [`CachedSections`].
+    encoded: Option<Vec<u8>>,
 }
 
 impl ChunkSection {
@@ -79,6 +229,7 @@ impl ChunkSection {
             blocks: [Default::default(); 16 * 16 * 16],
             non_air_blocks: 0,
             changes: BTreeSet::new(),
+            encoded: None,
         }
     }
 
@@ -91,6 +242,7 @@ impl ChunkSection {
         self.blocks[index as usize] = blockstate;
         if blockstate != old {
             self.changes.insert(index);
+            self.clear_encoded();
         }
         if old.is_air() {
             if !blockstate.is_air() {
@@ -120,35 +272,54 @@ impl ChunkSection {
         }
     }
 
+    /// Writes the network encoding, keeping it for the next time if `cache`.
     pub async fn serialize_into<W: embedded_io_async::Write>(
-        &self,
+        &mut self,
         writer: &mut W,
+        cache: bool,
     ) -> Result<(), EIOError<W::Error>> {
+        if self.encoded.is_none() && cache {
+            self.encoded = Some(self.encode());
+        }
+        match &self.encoded {
+            Some(encoded) => writer.write_all(encoded).await?,
+            None => {
+                let encoded = self.encode();
+                let result = writer.write_all(&encoded).await;
+                BufferPool::give(encoded);
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear_encoded(&mut self) {
+        if let Some(encoded) = self.encoded.take() {
+            BufferPool::give(encoded);
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = BufferPool::take(self.serialized_size());
         let non_air_blocks = self.non_air_blocks();
         if non_air_blocks == 0 {
-            writer.write_be(0u16).await?;
-            writer.write_be(0u8).await?;
-            writer.write_varint(0u32).await?;
+            encoded.extend_from_slice(&0u16.to_be_bytes());
+            encoded.push(0); // bits per entry
+            encoded.push(0); // palette value (varint)
         } else {
-            writer.write_be::<u16>(non_air_blocks).await?;
+            encoded.extend_from_slice(&non_air_blocks.to_be_bytes());
             let entry_size = 15u8;
-            writer.write_be(entry_size).await?;
-            let entries_per_long = 64u8 / entry_size;
-            let mut data = self.get_data_array().as_slice();
-            let mut i = 0;
-            let mut long = 0u64;
-            while !data.is_empty() {
-                let n = (i % entries_per_long as u16) as u8;
-                long |= (data[0].0 as u64) << (n * entry_size);
-                data = &data[1..];
-                if n == (entries_per_long - 1) || data.is_empty() {
-                    writer.write_be(long).await?;
-                    long = 0;
+            encoded.push(entry_size);
+            let entries_per_long = (64 / entry_size) as usize;
+            for entries in self.get_data_array().chunks(entries_per_long) {
+                let mut long = 0u64;
+                for (n, blockstate) in entries.iter().enumerate() {
+                    long |= (blockstate.0 as u64) << (n * entry_size as usize);
                 }
-                i += 1;
+                encoded.extend_from_slice(&long.to_be_bytes());
             }
         }
-        Ok(())
+        encoded
     }
 
     async fn gen_blocks_update_packet_and_clear_changes(
@@ -158,7 +329,8 @@ impl ChunkSection {
         if self.changes.is_empty() {
             return None;
         }
-        let mut pkt = MCPacketBuffer::new(77).await; // section_blocks_update
+        let mut pkt =
+            MCPacketBuffer::with_capacity(77, 1 + 8 + 3 + self.changes.len() * 5).await; // section_blocks_update
         pkt.write_be::<u64>(
             (self.section_y as u64 & 0xFFFFF)
                 | ((chunk_pos.y as u64 & 0x3FFFFF) << 20)
@@ -179,3 +351,9 @@ impl ChunkSection {
         Some(pkt)
     }
 }
+
+impl Drop for ChunkSection {
+    fn drop(&mut self) {
+        self.clear_encoded();
+    }
+}
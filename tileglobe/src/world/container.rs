@@ -0,0 +1,43 @@
+use crate::world::item::ItemStack;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+
+/// Item storage, e.g. of a chest.
+pub trait Container {
+    fn size(&self) -> usize;
+
+    fn get_item(&self, slot: usize) -> ItemStack;
+
+    fn set_item(&self, slot: usize, stack: ItemStack);
+}
+
+/// A fixed number of slots, without any restrictions on what goes where.
+pub struct SimpleContainer<M: RawMutex> {
+    items: blocking_mutex::Mutex<M, RefCell<Vec<ItemStack>>>,
+}
+
+impl<M: RawMutex> SimpleContainer<M> {
+    pub fn new(size: usize) -> Self {
+        let mut items = Vec::with_capacity(size);
+        items.resize(size, ItemStack::EMPTY);
+        Self {
+            items: blocking_mutex::Mutex::new(RefCell::new(items)),
+        }
+    }
+}
+
+impl<M: RawMutex> Container for SimpleContainer<M> {
+    fn size(&self) -> usize {
+        self.items.lock(|items| items.borrow().len())
+    }
+
+    fn get_item(&self, slot: usize) -> ItemStack {
+        self.items.lock(|items| items.borrow()[slot].clone())
+    }
+
+    fn set_item(&self, slot: usize, stack: ItemStack) {
+        self.items.lock(|items| items.borrow_mut()[slot] = stack);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::world::item::ItemStack;
+use crate::world::world::{_World, World};
+use glam::DVec3;
+use tileglobe_proc_macro::mc_registry_id;
+use tileglobe_utils::pos::{BlockPos, ChunkPos};
+use tileglobe_utils::resloc::ResLoc;
+
+/// Entities falling this far below the bottom of the world are removed.
+const VOID_Y: f64 = -128.0;
+/// Speeds below this are rounded to 0, so that resting entities don't need to be synced.
+const MIN_SPEED: f64 = 0.003;
+
+/// An entry of the `minecraft:entity_type` registry.
+#[derive(Debug)]
+pub struct EntityType {
+    pub resloc: &'static ResLoc<'static>,
+    /// Network id
+    pub id: u32,
+    /// Players see the entity from up to this many blocks away.
+    pub tracking_range: f64,
+}
+
+impl EntityType {
+    pub const ITEM: Self = Self {
+        resloc: &ResLoc::new("minecraft", "item"),
+        id: mc_registry_id!("entity_type", "item"),
+        tracking_range: 96.0,
+    };
+}
+
+/// A thing in the world that isn't a block, other than players.
+#[derive(Debug, Clone)]
+pub struct Entity {
+    /// Shared with the players, see [`World::allocate_entity_id`].
+    pub id: i32,
+    pub position: DVec3,
+    /// In blocks per tick
+    pub velocity: DVec3,
+    pub on_ground: bool,
+    /// Ticks since the entity was spawned.
+    pub age: u32,
+    pub kind: EntityKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum EntityKind {
+    Item(ItemEntity),
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemEntity {
+    pub stack: ItemStack,
+    /// Ticks until players can pick the item up.
+    pub pickup_delay: u16,
+}
+
+impl ItemEntity {
+    /// Items despawn after 5 minutes.
+    const LIFETIME: u32 = 6000;
+    /// Delay of items dropped by blocks.
+    pub const DEFAULT_PICKUP_DELAY: u16 = 10;
+    /// Delay of items thrown by players.
+    pub const THROWN_PICKUP_DELAY: u16 = 40;
+}
+
+impl Entity {
+    pub fn new(id: i32, position: DVec3, velocity: DVec3, kind: EntityKind) -> Self {
+        Self {
+            id,
+            position,
+            velocity,
+            on_ground: false,
+            age: 0,
+            kind,
+        }
+    }
+
+    pub fn item(id: i32, position: DVec3, velocity: DVec3, stack: ItemStack, pickup_delay: u16) -> Self {
+        Self::new(id, position, velocity, EntityKind::Item(ItemEntity { stack, pickup_delay }))
+    }
+
+    /// The item entity dropped by a block that was broken, like vanilla's `Block.popResource`.
+    /// There is no randomness, the item pops out of the center of the block.
+    pub fn dropped_item(id: i32, pos: BlockPos, stack: ItemStack) -> Self {
+        let position = pos.as_dvec3() + DVec3::new(0.5, 0.25, 0.5);
+        Self::item(id, position, DVec3::new(0.0, 0.2, 0.0), stack, ItemEntity::DEFAULT_PICKUP_DELAY)
+    }
+
+    pub fn entity_type(&self) -> &'static EntityType {
+        match self.kind {
+            EntityKind::Item(_) => &EntityType::ITEM,
+        }
+    }
+
+    pub fn block_pos(&self) -> BlockPos {
+        block_pos_at(self.position)
+    }
+
+    pub fn chunk_pos(&self) -> ChunkPos {
+        self.block_pos().chunk_pos()
+    }
+
+    /// Returns `false` once the entity is to be removed.
+    pub(crate) async fn tick(&mut self, world: &_World) -> bool {
+        self.age += 1;
+        match &mut self.kind {
+            EntityKind::Item(item) => {
+                item.pickup_delay = item.pickup_delay.saturating_sub(1);
+                if self.age >= ItemEntity::LIFETIME || item.stack.is_empty() {
+                    return false;
+                }
+                // see vanilla's `ItemEntity.tick`
+                self.velocity.y -= 0.04;
+                self.move_with_collisions(world).await;
+                let friction = if self.on_ground { 0.6 * 0.98 } else { 0.98 };
+                self.velocity *= DVec3::new(friction, 0.98, friction);
+            }
+        }
+        if self.velocity.x.abs() < MIN_SPEED {
+            self.velocity.x = 0.0;
+        }
+        if self.velocity.z.abs() < MIN_SPEED {
+            self.velocity.z = 0.0;
+        }
+        self.position.y >= VOID_Y
+    }
+
+    /// Moves by the velocity, one axis after the other, stopping at non-air blocks.
+    /// Without collision shapes, every block is treated as a full cube.
+    async fn move_with_collisions(&mut self, world: &_World) {
+        let mut position = self.position;
+
+        position.y += self.velocity.y;
+        if is_solid(world, position).await {
+            if self.velocity.y < 0.0 {
+                position.y = libm::floor(position.y) + 1.0;
+                self.on_ground = true;
+            } else {
+                position.y = self.position.y;
+            }
+            self.velocity.y = 0.0;
+        } else {
+            self.on_ground = false;
+        }
+
+        position.x += self.velocity.x;
+        if is_solid(world, position).await {
+            position.x = self.position.x;
+            self.velocity.x = 0.0;
+        }
+        position.z += self.velocity.z;
+        if is_solid(world, position).await {
+            position.z = self.position.z;
+            self.velocity.z = 0.0;
+        }
+
+        self.position = position;
+    }
+}
+
+pub fn block_pos_at(position: DVec3) -> BlockPos {
+    let floored = position.floor();
+    BlockPos::new(floored.x as i16, floored.y as i16, floored.z as i16)
+}
+
+async fn is_solid(world: &_World, position: DVec3) -> bool {
+    world
+        .get_block_state(block_pos_at(position))
+        .await
+        .is_ok_and(|it| !it.is_air())
+}
@@ -0,0 +1,211 @@
+use crate::world::item::ItemId;
+use alloc::vec::Vec;
+use core::error::Error;
+use tileglobe_proc_macro::mc_registry_id;
+use tileglobe_utils::network::{EIOError, WriteVarInt};
+
+const ENCHANTMENTS_COMPONENT: u32 = mc_registry_id!("data_component_type", "enchantments");
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u8,
+    /// Data component patch in the encoding sent by clients, empty if there are no changes to the defaults.
+    /// Added components are prefixed with their length, so that they can be found without decoding the others.
+    pub components: Vec<u8>,
+}
+
+impl ItemStack {
+    pub const EMPTY: Self = Self {
+        item: ItemId(0),
+        count: 0,
+        components: Vec::new(),
+    };
+
+    pub fn new(item: ItemId, count: u8) -> Self {
+        Self {
+            item,
+            count,
+            components: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item.is_air() || self.count == 0
+    }
+
+    pub fn max_stack_size(&self) -> u8 {
+        self.item.get_item().map_or(64, |it| it.max_stack_size)
+    }
+
+    /// Whether the stacks can be merged into one.
+    pub fn is_stackable_with(&self, other: &ItemStack) -> bool {
+        self.item == other.item && self.components == other.components
+    }
+
+    pub fn with_count(&self, count: u8) -> Self {
+        Self {
+            count,
+            ..self.clone()
+        }
+    }
+
+    /// Takes up to `count` items off this stack.
+    pub fn split(&mut self, count: u8) -> Self {
+        let count = count.min(self.count);
+        self.count -= count;
+        let split = self.with_count(count);
+        if self.count == 0 {
+            *self = Self::EMPTY;
+        }
+        split
+    }
+
+    /// Data of the component added by the patch, see [`ItemStack::components`].
+    pub fn component(&self, component_type: u32) -> Option<&[u8]> {
+        let mut data = self.components.as_slice();
+        let added = read_varint(&mut data)?;
+        read_varint(&mut data)?; // removed
+        for _ in 0..added {
+            let this_type = read_varint(&mut data)?;
+            let length = read_varint(&mut data)? as usize;
+            let (component, rest) = data.split_at_checked(length)?;
+            if this_type == component_type {
+                return Some(component);
+            }
+            data = rest;
+        }
+        None
+    }
+
+    /// Level of the enchantment, by its id in the `minecraft:enchantment` registry, 0 if the item doesn't have it.
+    pub fn enchantment_level(&self, enchantment: u32) -> u32 {
+        let Some(mut data) = self.component(ENCHANTMENTS_COMPONENT) else {
+            return 0;
+        };
+        let count = read_varint(&mut data).unwrap_or(0);
+        for _ in 0..count {
+            let (Some(id), Some(level)) = (read_varint(&mut data), read_varint(&mut data)) else {
+                break;
+            };
+            if id == enchantment {
+                return level;
+            }
+        }
+        0
+    }
+
+    /// Adds as many items of `other` to this stack as fit, leaving the rest in `other`.
+    pub fn merge(&mut self, other: &mut ItemStack, max_count: u8) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            *self = other.split(max_count.min(other.max_stack_size()));
+        } else if self.is_stackable_with(other) {
+            let space = max_count.min(self.max_stack_size()).saturating_sub(self.count);
+            self.count += other.split(space).count;
+        }
+    }
+
+    /// Whether [`ItemStack::components`] is a complete patch, which is needed to send the stack.
+    pub fn has_valid_components(&self) -> bool {
+        if self.components.is_empty() {
+            return true;
+        }
+        let mut data = self.components.as_slice();
+        let (Some(added), Some(removed)) = (read_varint(&mut data), read_varint(&mut data)) else {
+            return false;
+        };
+        for _ in 0..added {
+            if read_varint(&mut data).is_none() {
+                return false;
+            }
+            let Some(rest) = read_varint(&mut data)
+                .and_then(|length| data.split_at_checked(length as usize))
+                .map(|(_, rest)| rest)
+            else {
+                return false;
+            };
+            data = rest;
+        }
+        for _ in 0..removed {
+            if read_varint(&mut data).is_none() {
+                return false;
+            }
+        }
+        data.is_empty()
+    }
+
+    /// Writes the stack in the network encoding, nothing is written if the components are malformed.
+    pub async fn write_into<W: embedded_io_async::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), WriteItemStackError<W::Error>> {
+        if self.is_empty() {
+            writer.write_varint(0u32).await?;
+            return Ok(());
+        }
+        if !self.has_valid_components() {
+            return Err(WriteItemStackError::InvalidComponents);
+        }
+        writer.write_varint(self.count as u32).await?;
+        writer.write_varint(self.item.0 as u32).await?;
+        if self.components.is_empty() {
+            writer.write_varint(0u32).await?; // added components
+            writer.write_varint(0u32).await?; // removed components
+        } else {
+            // the same as stored, without the lengths of the added components
+            let invalid = || WriteItemStackError::InvalidComponents;
+            let mut data = self.components.as_slice();
+            let added = read_varint(&mut data).ok_or_else(invalid)?;
+            let removed = read_varint(&mut data).ok_or_else(invalid)?;
+            writer.write_varint(added).await?;
+            writer.write_varint(removed).await?;
+            for _ in 0..added {
+                writer.write_varint(read_varint(&mut data).ok_or_else(invalid)?).await?;
+                let length = read_varint(&mut data).ok_or_else(invalid)? as usize;
+                let (component, rest) = data.split_at_checked(length).ok_or_else(invalid)?;
+                writer.write_all(component).await?;
+                data = rest;
+            }
+            writer.write_all(data).await?; // removed component types
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, derive_more::Display)]
+#[display("{self:?}")]
+pub enum WriteItemStackError<E: embedded_io_async::Error> {
+    /// [`ItemStack::components`] isn't a complete patch.
+    InvalidComponents,
+    IOError(EIOError<E>),
+}
+
+impl<E: embedded_io_async::Error> From<EIOError<E>> for WriteItemStackError<E> {
+    fn from(value: EIOError<E>) -> Self {
+        Self::IOError(value)
+    }
+}
+
+impl<E: embedded_io_async::Error> From<E> for WriteItemStackError<E> {
+    fn from(value: E) -> Self {
+        Self::IOError(EIOError(value))
+    }
+}
+
+impl<E: embedded_io_async::Error> Error for WriteItemStackError<E> {}
+
+/// Reads a VarInt off the start of the slice, `None` if it's cut short.
+fn read_varint(data: &mut &[u8]) -> Option<u32> {
+    let mut value = 0u32;
+    for (i, &byte) in data.iter().enumerate().take(5) {
+        value |= ((byte & 0x7F) as u32) << (i * 7);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
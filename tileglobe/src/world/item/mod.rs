@@ -0,0 +1,91 @@
+mod item_stack;
+mod registry;
+pub use item_stack::*;
+pub use registry::*;
+
+use crate::world::block::BlockState;
+use defmt_or_log::maybe_derive_format;
+use tileglobe_utils::resloc::ResLoc;
+
+pub type ItemIdType = u16;
+#[derive(
+    Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Copy, derive_more::From, derive_more::Into,
+)]
+#[maybe_derive_format]
+pub struct ItemId(pub ItemIdType);
+
+impl ItemId {
+    pub fn get_item(self) -> Option<&'static Item> {
+        Items.get_item(self)
+    }
+
+    pub fn is_air(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Default for ItemId {
+    fn default() -> Self {
+        ItemId(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Item {
+    pub resloc: &'static ResLoc<'static>,
+    pub id: ItemId,
+    pub max_stack_size: u8,
+    pub tool: Option<Tool>,
+    /// Default state of the block placed by this item, for block items.
+    pub block: Option<BlockState>,
+}
+
+impl Item {
+    /// Mining speed against the block, 1 for items which aren't tools.
+    pub fn destroy_speed(&self, blockstate: BlockState) -> f32 {
+        self.tool.as_ref().map_or(1.0, |it| it.mining_speed(blockstate))
+    }
+
+    /// Whether breaking blocks needing the correct tool with this item drops them.
+    pub fn is_correct_tool_for_drops(&self, blockstate: BlockState) -> bool {
+        self.tool.as_ref().is_some_and(|it| it.is_correct_for_drops(blockstate))
+    }
+}
+
+/// The `minecraft:tool` component of an item, as it is by default.
+#[derive(Debug)]
+pub struct Tool {
+    pub default_mining_speed: f32,
+    /// The first rule matching a block and setting a value decides it.
+    pub rules: &'static [ToolRule],
+}
+
+#[derive(Debug)]
+pub struct ToolRule {
+    /// Sorted ids in the `minecraft:block` registry, see [`BlockState::block_id`].
+    pub blocks: &'static [u16],
+    pub speed: Option<f32>,
+    pub correct_for_drops: Option<bool>,
+}
+
+impl ToolRule {
+    pub fn matches(&self, blockstate: BlockState) -> bool {
+        self.blocks.binary_search(&(blockstate.block_id() as u16)).is_ok()
+    }
+}
+
+impl Tool {
+    pub fn mining_speed(&self, blockstate: BlockState) -> f32 {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.speed.filter(|_| rule.matches(blockstate)))
+            .unwrap_or(self.default_mining_speed)
+    }
+
+    pub fn is_correct_for_drops(&self, blockstate: BlockState) -> bool {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.correct_for_drops.filter(|_| rule.matches(blockstate)))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,30 @@
+use crate::world::block::BlockState;
+use crate::world::item::{Item, ItemId, ItemIdType, Tool, ToolRule};
+use tileglobe_utils::resloc::ResLoc;
+
+pub struct Items;
+impl Items {
+    const _ITEMS_BY_ID: &[Item] = &tileglobe_proc_macro::mc_items_registry! {
+        item: Item,
+        resloc_consts: ItemResLocs,
+    };
+
+    /// Item ids by block id, see [`tileglobe_proc_macro::mc_block_items`].
+    const _ITEMS_BY_BLOCK_ID: &[ItemIdType] = &tileglobe_proc_macro::mc_block_items!();
+
+    pub(super) fn get_item(&self, id: ItemId) -> Option<&'static Item> {
+        Self::_ITEMS_BY_ID.get(id.0 as usize)
+    }
+
+    pub(crate) fn get_item_for_block(&self, blockstate: BlockState) -> Option<&'static Item> {
+        match Self::_ITEMS_BY_BLOCK_ID.get(blockstate.block_id() as usize) {
+            Some(&id) if id != 0 => self.get_item(ItemId(id)),
+            _ => None,
+        }
+    }
+}
+
+pub struct ItemResLocs;
+impl ItemResLocs {
+    tileglobe_proc_macro::mc_item_resloc_consts!();
+}
@@ -1,4 +1,10 @@
 pub mod block;
+pub mod block_entity;
 pub mod chunk;
+pub mod container;
+pub mod entity;
+pub mod item;
+pub mod neighbor_updater;
+pub mod region;
 pub mod utils;
 pub mod world;
@@ -0,0 +1,224 @@
+use alloc::vec::Vec;
+use defmt_or_log::error;
+use tileglobe_utils::direction::Direction;
+use tileglobe_utils::pos::BlockPos;
+
+/// Order in which the neighbors of a block are updated, vanilla's `NeighborUpdater.UPDATE_ORDER`.
+pub const UPDATE_ORDER: [Direction; 6] = [
+    Direction::WEST,
+    Direction::EAST,
+    Direction::DOWN,
+    Direction::UP,
+    Direction::NORTH,
+    Direction::SOUTH,
+];
+
+/// Order in which the shapes of the neighbors of a block are updated, vanilla's `Block.UPDATE_SHAPE_ORDER`.
+pub const UPDATE_SHAPE_ORDER: [Direction; 6] = [
+    Direction::WEST,
+    Direction::EAST,
+    Direction::NORTH,
+    Direction::SOUTH,
+    Direction::DOWN,
+    Direction::UP,
+];
+
+/// Vanilla's default of the `max_chained_neighbor_updates` server property.
+pub const DEFAULT_MAX_CHAINED_UPDATES: u32 = 1_000_000;
+
+/// When neighbor updates of the world run.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum NeighborUpdateMode {
+    /// Immediately, depth first, like vanilla's `CollectingNeighborUpdater`.
+    #[default]
+    Chained,
+    /// Queued and run last in, first out at the end of the tick.
+    /// Neighbors are updated in [`Direction`] order and shape updates run immediately.
+    Deferred,
+}
+
+/// A single update to run.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NeighborUpdateStep {
+    /// A neighbor of the block changed, see [`crate::world::block::Block::update`].
+    Neighbor(BlockPos),
+    /// The shape of a neighbor of the block may have changed, see [`crate::world::block::Block::update_shape`].
+    Shape(BlockPos),
+}
+
+#[derive(Debug)]
+enum NeighborUpdate {
+    Single(Option<NeighborUpdateStep>),
+    /// All neighbors of the source in [`UPDATE_ORDER`], except one of them.
+    Neighbors {
+        source: BlockPos,
+        skip: Option<Direction>,
+        index: usize,
+    },
+}
+
+impl NeighborUpdate {
+    fn next(&mut self) -> Option<NeighborUpdateStep> {
+        match self {
+            Self::Single(step) => step.take(),
+            Self::Neighbors { source, skip, index } => {
+                while let Some(&direction) = UPDATE_ORDER.get(*index) {
+                    *index += 1;
+                    if Some(direction) != *skip {
+                        return Some(NeighborUpdateStep::Neighbor(source.offset_dir(direction)));
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Orders neighbor updates like vanilla's `CollectingNeighborUpdater`: updates caused while running
+/// another update run right after it, before the rest of the updates it came with, in the order they were added.
+///
+/// The updater only collects, whoever added the first update runs the steps from [`Self::next_step`],
+/// so that updates don't recurse.
+#[derive(Debug)]
+pub struct CollectingNeighborUpdater {
+    /// Top at the end
+    stack: Vec<NeighborUpdate>,
+    added_this_layer: Vec<NeighborUpdate>,
+    /// Updates added since the updater was last idle.
+    count: u32,
+    max_chained_updates: u32,
+}
+
+impl CollectingNeighborUpdater {
+    pub fn new(max_chained_updates: u32) -> Self {
+        Self {
+            stack: Vec::new(),
+            added_this_layer: Vec::new(),
+            count: 0,
+            max_chained_updates,
+        }
+    }
+
+    /// `true` if the caller has to run the updates, i.e. no updates are running yet.
+    pub fn add_single(&mut self, step: NeighborUpdateStep) -> bool {
+        self.add(NeighborUpdate::Single(Some(step)))
+    }
+
+    /// Like [`Self::add_single`], for updating the neighbors of the block except the one in the direction.
+    pub fn add_neighbors(&mut self, source: BlockPos, skip: Option<Direction>) -> bool {
+        self.add(NeighborUpdate::Neighbors { source, skip, index: 0 })
+    }
+
+    fn add(&mut self, update: NeighborUpdate) -> bool {
+        let running = self.count > 0;
+        let over_limit = self.count >= self.max_chained_updates;
+        self.count = self.count.saturating_add(1);
+        if !over_limit {
+            if running {
+                self.added_this_layer.push(update);
+            } else {
+                self.stack.push(update);
+            }
+        } else if self.count - 1 == self.max_chained_updates {
+            error!("Too many chained neighbor updates, skipping the rest");
+        }
+        !running
+    }
+
+    /// Drops the updates left, for when whoever ran them stopped halfway, e.g. as its task was cancelled.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+        self.added_this_layer.clear();
+        self.count = 0;
+    }
+
+    /// The next update to run, `None` once all are done.
+    pub fn next_step(&mut self) -> Option<NeighborUpdateStep> {
+        loop {
+            // the first one added ends up on top
+            self.stack.extend(self.added_this_layer.drain(..).rev());
+            let Some(top) = self.stack.last_mut() else {
+                self.count = 0;
+                return None;
+            };
+            match top.next() {
+                Some(step) => return Some(step),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(updater: &mut CollectingNeighborUpdater) -> Vec<NeighborUpdateStep> {
+        core::iter::from_fn(|| updater.next_step()).collect()
+    }
+
+    #[test]
+    fn neighbors_run_in_update_order_without_the_skipped_one() {
+        let source = BlockPos::new(0, 64, 0);
+        let mut updater = CollectingNeighborUpdater::new(DEFAULT_MAX_CHAINED_UPDATES);
+        assert!(updater.add_neighbors(source, Some(Direction::DOWN)));
+
+        let expected = UPDATE_ORDER
+            .iter()
+            .filter(|&&it| it != Direction::DOWN)
+            .map(|&it| NeighborUpdateStep::Neighbor(source.offset_dir(it)))
+            .collect::<Vec<_>>();
+        assert_eq!(drain(&mut updater), expected);
+    }
+
+    #[test]
+    fn updates_added_while_running_run_next_in_the_order_they_were_added() {
+        let source = BlockPos::new(0, 64, 0);
+        let first = BlockPos::new(5, 64, 0);
+        let second = BlockPos::new(6, 64, 0);
+        let mut updater = CollectingNeighborUpdater::new(DEFAULT_MAX_CHAINED_UPDATES);
+        assert!(updater.add_neighbors(source, None));
+        assert_eq!(
+            updater.next_step(),
+            Some(NeighborUpdateStep::Neighbor(source.offset_dir(UPDATE_ORDER[0])))
+        );
+
+        // caused by the update above, the caller doesn't run them itself
+        assert!(!updater.add_single(NeighborUpdateStep::Shape(first)));
+        assert!(!updater.add_single(NeighborUpdateStep::Neighbor(second)));
+        assert_eq!(updater.next_step(), Some(NeighborUpdateStep::Shape(first)));
+        assert_eq!(updater.next_step(), Some(NeighborUpdateStep::Neighbor(second)));
+
+        let rest = UPDATE_ORDER[1..]
+            .iter()
+            .map(|&it| NeighborUpdateStep::Neighbor(source.offset_dir(it)))
+            .collect::<Vec<_>>();
+        assert_eq!(drain(&mut updater), rest);
+        // idle again
+        assert!(updater.add_single(NeighborUpdateStep::Neighbor(source)));
+    }
+
+    #[test]
+    fn updates_over_the_limit_are_skipped() {
+        let mut updater = CollectingNeighborUpdater::new(2);
+        let steps = (0..4).map(|x| NeighborUpdateStep::Neighbor(BlockPos::new(x, 64, 0))).collect::<Vec<_>>();
+        assert!(updater.add_single(steps[0]));
+        assert_eq!(updater.next_step(), Some(steps[0]));
+        for &step in &steps[1..] {
+            assert!(!updater.add_single(step));
+        }
+        assert_eq!(drain(&mut updater), [steps[1]]);
+    }
+
+    #[test]
+    fn clear_drops_the_updates_left() {
+        let mut updater = CollectingNeighborUpdater::new(DEFAULT_MAX_CHAINED_UPDATES);
+        assert!(updater.add_neighbors(BlockPos::new(0, 64, 0), None));
+        updater.next_step();
+        updater.clear();
+        assert_eq!(updater.next_step(), None);
+        assert!(updater.add_single(NeighborUpdateStep::Shape(BlockPos::new(0, 64, 0))));
+    }
+}
@@ -0,0 +1,208 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use tileglobe_utils::pos::BlockPos;
+
+/// What players may not do inside a region.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct RegionFlags {
+    pub no_build: bool,
+    pub no_break: bool,
+    /// Using blocks, e.g. pressing buttons or flipping levers.
+    pub no_interact: bool,
+}
+
+impl RegionFlags {
+    pub const ALL: Self = Self {
+        no_build: true,
+        no_break: true,
+        no_interact: true,
+    };
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            no_build: self.no_build || other.no_build,
+            no_break: self.no_break || other.no_break,
+            no_interact: self.no_interact || other.no_interact,
+        }
+    }
+}
+
+/// An axis-aligned box of blocks protected from players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub min: BlockPos,
+    /// Inclusive
+    pub max: BlockPos,
+    pub flags: RegionFlags,
+}
+
+impl Region {
+    /// The corners can be given in any order.
+    pub fn new(name: String, a: BlockPos, b: BlockPos, flags: RegionFlags) -> Self {
+        Self {
+            name,
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+            flags,
+        }
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x)
+            && (self.min.y..=self.max.y).contains(&pos.y)
+            && (self.min.z..=self.max.z).contains(&pos.z)
+    }
+
+    /// Appends the region as a line of text, `<name> <min x y z> <max x y z> [build] [break] [interact]`,
+    /// with the flags that are set.
+    pub fn encode(&self, out: &mut String) {
+        // writing to a string doesn't fail
+        let _ = write!(
+            out,
+            "{} {} {} {} {} {} {}",
+            self.name, self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z
+        );
+        for (set, flag) in [
+            (self.flags.no_build, "build"),
+            (self.flags.no_break, "break"),
+            (self.flags.no_interact, "interact"),
+        ] {
+            if set {
+                let _ = write!(out, " {}", flag);
+            }
+        }
+        out.push('\n');
+    }
+
+    /// Reads a line written by [`Self::encode`], `None` if it's malformed.
+    pub fn decode(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let name = String::from(fields.next()?);
+        let mut coords = [0i16; 6];
+        for coord in &mut coords {
+            *coord = fields.next()?.parse().ok()?;
+        }
+        let mut flags = RegionFlags::default();
+        for flag in fields {
+            match flag {
+                "build" => flags.no_build = true,
+                "break" => flags.no_break = true,
+                "interact" => flags.no_interact = true,
+                _ => return None,
+            }
+        }
+        Some(Self::new(
+            name,
+            BlockPos::new(coords[0], coords[1], coords[2]),
+            BlockPos::new(coords[3], coords[4], coords[5]),
+            flags,
+        ))
+    }
+}
+
+/// The protected regions of a world, possibly overlapping.
+#[derive(Debug, Default)]
+pub struct Regions {
+    regions: Vec<Region>,
+}
+
+impl Regions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the region, replacing the one of the same name.
+    pub fn set(&mut self, region: Region) {
+        self.remove(&region.name);
+        self.regions.push(region);
+    }
+
+    /// `false` if there is no region of that name.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.regions.len();
+        self.regions.retain(|it| it.name != name);
+        self.regions.len() != len
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    /// Combined flags of all regions containing the block.
+    pub fn flags_at(&self, pos: BlockPos) -> RegionFlags {
+        self.regions
+            .iter()
+            .filter(|it| it.contains(pos))
+            .fold(RegionFlags::default(), |flags, it| flags.union(it.flags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(name: &str, a: (i16, i16, i16), b: (i16, i16, i16), flags: RegionFlags) -> Region {
+        Region::new(String::from(name), BlockPos::new(a.0, a.1, a.2), BlockPos::new(b.0, b.1, b.2), flags)
+    }
+
+    #[test]
+    fn contains_the_corners_given_in_any_order() {
+        let region = region("spawn", (10, 80, -5), (-10, 60, 5), RegionFlags::ALL);
+        assert!(region.contains(BlockPos::new(-10, 60, -5)));
+        assert!(region.contains(BlockPos::new(10, 80, 5)));
+        assert!(region.contains(BlockPos::new(0, 70, 0)));
+        assert!(!region.contains(BlockPos::new(11, 70, 0)));
+        assert!(!region.contains(BlockPos::new(0, 59, 0)));
+        assert!(!region.contains(BlockPos::new(0, 70, -6)));
+    }
+
+    #[test]
+    fn flags_of_overlapping_regions_are_combined() {
+        let no_build = RegionFlags {
+            no_build: true,
+            ..RegionFlags::default()
+        };
+        let no_interact = RegionFlags {
+            no_interact: true,
+            ..RegionFlags::default()
+        };
+        let mut regions = Regions::new();
+        regions.set(region("a", (0, 0, 0), (10, 10, 10), no_build));
+        regions.set(region("b", (5, 5, 5), (15, 15, 15), no_interact));
+
+        assert_eq!(regions.flags_at(BlockPos::new(0, 0, 0)), no_build);
+        assert_eq!(regions.flags_at(BlockPos::new(7, 7, 7)), no_build.union(no_interact));
+        assert_eq!(regions.flags_at(BlockPos::new(20, 7, 7)), RegionFlags::default());
+
+        // replaced by name
+        regions.set(region("b", (20, 0, 0), (20, 10, 10), no_interact));
+        assert_eq!(regions.flags_at(BlockPos::new(7, 7, 7)), no_build);
+        assert!(regions.remove("a"));
+        assert!(!regions.remove("a"));
+        assert_eq!(regions.flags_at(BlockPos::new(7, 7, 7)), RegionFlags::default());
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let flags = RegionFlags {
+            no_break: true,
+            no_interact: true,
+            ..RegionFlags::default()
+        };
+        let region = region("spawn", (10, 80, -5), (-10, 60, 5), flags);
+        let mut line = String::new();
+        region.encode(&mut line);
+        assert_eq!(line, "spawn -10 60 -5 10 80 5 break interact\n");
+        assert_eq!(Region::decode(&line), Some(region));
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        assert_eq!(Region::decode(""), None);
+        assert_eq!(Region::decode("spawn 1 2 3 4 5"), None);
+        assert_eq!(Region::decode("spawn 1 2 3 4 5 x"), None);
+        assert_eq!(Region::decode("spawn 1 2 3 4 5 6 fly"), None);
+    }
+}
@@ -1,28 +1,54 @@
 use crate::world::block::BlockState;
-use crate::world::chunk::{Chunk, ChunkSection};
+use crate::world::block_entity::BlockEntity;
+use crate::world::chunk::{CachedSections, Chunk, ChunkSection};
+use crate::world::entity::Entity;
+use crate::world::neighbor_updater::{
+    CollectingNeighborUpdater, DEFAULT_MAX_CHAINED_UPDATES, NeighborUpdateMode, NeighborUpdateStep, UPDATE_SHAPE_ORDER,
+};
+use crate::world::region::{Region, RegionFlags, Regions};
 use alloc::boxed::Box;
-use alloc::collections::BTreeSet;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
 use alloc::vec::Vec;
 use core::cmp::{Ordering, max};
 use core::mem::MaybeUninit;
-use defmt_or_log::info;
+use core::sync::atomic::{AtomicI32, Ordering as AtomicOrdering};
+use defmt_or_log::{error, info};
 use dynify::Dynify;
 use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex, RawMutex};
 use embassy_sync::mutex::{MappedMutexGuard, Mutex, MutexGuard};
 use smallvec::SmallVec;
 use tileglobe_utils::direction::Direction;
 use tileglobe_utils::indexed_enum::IndexedEnum;
-use tileglobe_utils::network::{EIOError, MCPacketBuffer, WriteNumPrimitive, WriteVarInt};
+use tileglobe_utils::network::{EIOError, MCPacketBuffer, WriteBlockPos, WriteNumPrimitive, WriteVarInt};
 use tileglobe_utils::pos::{BlockPos, ChunkPos};
+use tileglobe_utils::resloc::ResLoc;
 
 #[allow(async_fn_in_trait)]
 pub trait World {
     async fn get_block_state(&self, pos: BlockPos) -> Result<BlockState, ()>;
 
+    /// Also replaces the block entity if the block changes.
     async fn set_block_state(&self, pos: BlockPos, state: BlockState) -> Result<BlockState, ()>;
 
+    /// Calls `f` with the block entity at the position, `None` if there is none of that type.
+    async fn with_block_entity<T: BlockEntity, R>(&self, pos: BlockPos, f: impl FnOnce(&mut T) -> R) -> Option<R>;
+
+    /// Sends the client data of the block entity to the players again.
+    async fn block_entity_changed(&self, pos: BlockPos);
+
+    type EditGuard<'a>
+    where
+        Self: 'a;
+    /// Held by [`Self::tick`] and by whoever else changes blocks from outside the world, e.g. for a player,
+    /// so that the chains of neighbor updates they cause don't interleave.
+    /// Block code already runs with it held, it must not be taken there again.
+    async fn lock_edits(&self) -> Self::EditGuard<'_>;
+
     async fn tick(&self);
 
+    /// The number of the tick running, counting up from 0 like vanilla's game time.
+    async fn current_tick(&self) -> u32;
+
     async fn update_block(&self, pos: BlockPos);
     async fn update_neighbors(&self, pos: BlockPos) {
         for &direction in Direction::variants() {
@@ -44,7 +70,21 @@ pub trait World {
         }
     }
 
-    async fn schedule_tick(&self, pos: BlockPos, delay: u8, priority: i8);
+    /// Schedules a tick of the block at the position, like vanilla's `scheduleTick`.
+    /// Ticks are kept per block, any state of it can be given.
+    /// Ticks run ordered by time, then by priority (lower first), then in the order they were scheduled.
+    /// Ignored if the block already has a tick scheduled there, and dropped if the block is replaced meanwhile.
+    async fn schedule_tick(&self, pos: BlockPos, blockstate: BlockState, delay: u8, priority: i8);
+
+    /// Whether a tick of the block at the position is scheduled for a later tick, or later in this one.
+    async fn has_scheduled_tick(&self, pos: BlockPos, blockstate: BlockState) -> bool;
+
+    /// Whether a tick of the block at the position is about to run in the tick currently running.
+    /// Such ticks don't count for [`Self::has_scheduled_tick`].
+    async fn will_tick_this_tick(&self, pos: BlockPos, blockstate: BlockState) -> bool;
+
+    /// Whether the scheduled ticks of the tick are running, like vanilla's `isHandlingTick`.
+    async fn is_handling_scheduled_ticks(&self) -> bool;
 
     async fn get_signal(&self, pos: BlockPos, direction: Direction) -> u8;
 
@@ -68,11 +108,50 @@ pub trait World {
         writer: &mut W,
     ) -> Result<(), EIOError<W::Error>>;
 
-    async fn gen_blocks_update_packets_and_clear_changes(&self) -> Vec<MCPacketBuffer> {
-        Vec::new()
-    }
+    /// Upper bound of the number of bytes [`Self::write_net_chunk`] writes, for pre-sizing buffers.
+    async fn net_chunk_size(&self, pos: ChunkPos) -> usize;
+
+    /// Appends the packets of the block changes and block events since the last call, in the order they happened.
+    async fn gen_blocks_update_packets_and_clear_changes(&self, packets: &mut Vec<MCPacketBuffer>) {}
+
+    /// Queues a block event, run in the block event phase of the tick by [`crate::world::block::Block::trigger_event`]
+    /// if the block is still there, like vanilla's `blockEvent`. The same event is queued only once.
+    async fn block_event(&self, pos: BlockPos, block: &'static ResLoc<'static>, event_type: u8, event_param: u8);
+
+    /// Combined flags of the protected regions containing the block.
+    async fn region_flags_at(&self, pos: BlockPos) -> RegionFlags;
+
+    /// Adds the protected region, replacing the one of the same name.
+    async fn set_region(&self, region: Region);
+
+    /// `false` if there is no region of that name.
+    async fn remove_region(&self, name: &str) -> bool;
+
+    async fn regions(&self) -> Vec<Region>;
+
+    /// A new id for an entity or player, unique within the world.
+    fn allocate_entity_id(&self) -> i32;
+
+    /// Fails if the entity is outside of the loaded chunks, or if the world has [`MAX_ENTITIES`] already.
+    /// The entity is handed back then, e.g. to return the stack of an item entity to where it came from.
+    async fn add_entity(&self, entity: Entity) -> Result<(), Entity>;
+
+    /// Adds all of the entities, or none of them if not all can be added, see [`Self::add_entity`].
+    async fn add_entities(&self, entities: Vec<Entity>) -> Result<(), Vec<Entity>>;
+
+    async fn remove_entity(&self, id: i32) -> Option<Entity>;
+
+    /// Calls `f` with the entity, `None` if there is none with the id.
+    async fn with_entity<R>(&self, id: i32, f: impl FnOnce(&mut Entity) -> R) -> Option<R>;
+
+    /// Snapshot of the entities in the chunks at most `radius` chunks away from `center`,
+    /// e.g. for sending them to a player.
+    async fn entities_near(&self, center: ChunkPos, radius: i16) -> Vec<Entity>;
 }
 
+/// Most entities a world holds at once, so that dropping items can't use up the memory.
+pub const MAX_ENTITIES: usize = 256;
+
 // #[cfg(feature = "rp")]
 // pub type _World = LocalWorld<embassy_rp::spinlock_mutex::SpinlockRawMutex<1>, -1, -1, 3, 3>;
 // #[cfg(not(feature = "rp"))]
@@ -82,6 +161,8 @@ pub type _World = LocalWorld<NoopRawMutex, -1, -1, 3, 3>;
 #[derive(Debug, Eq, PartialEq)]
 struct BlockTick {
     pos: BlockPos,
+    /// See [`BlockState::block_id`]
+    block: u32,
     tick: u32,
     priority: i8,
     sequence: u32,
@@ -90,7 +171,7 @@ impl Ord for BlockTick {
     fn cmp(&self, other: &Self) -> Ordering {
         self.tick
             .cmp(&other.tick)
-            .then_with(|| self.priority.cmp(&other.priority).reverse())
+            .then_with(|| self.priority.cmp(&other.priority))
             .then_with(|| self.sequence.cmp(&other.sequence))
     }
 }
@@ -100,40 +181,80 @@ impl PartialOrd for BlockTick {
     }
 }
 
+#[derive(Debug, Eq, PartialEq)]
+struct BlockEvent {
+    pos: BlockPos,
+    block: &'static ResLoc<'static>,
+    event_type: u8,
+    event_param: u8,
+}
+
+/// Scheduled block ticks, like vanilla's `LevelTicks`.
 struct BlockTickScheduler {
     ticks: BTreeSet<BlockTick>,
-    positions: BTreeSet<BlockPos>,
+    /// Keys of `ticks`, a block has at most one tick scheduled at a position.
+    scheduled: BTreeSet<(BlockPos, u32)>,
+    /// Taken out of `ticks` to run in the current tick, but not run yet.
+    to_run_this_tick: BTreeSet<(BlockPos, u32)>,
+    /// Between [`Self::collect_due`] and [`Self::finish_running`].
+    running: bool,
     sequence: u32,
 }
 impl BlockTickScheduler {
     fn new() -> Self {
         Self {
             ticks: BTreeSet::new(),
-            positions: BTreeSet::new(),
+            scheduled: BTreeSet::new(),
+            to_run_this_tick: BTreeSet::new(),
+            running: false,
             sequence: 0,
         }
     }
 
-    pub fn schedule(&mut self, pos: BlockPos, tick: u32, priority: i8) {
-        if self.positions.insert(pos) {
+    pub fn schedule(&mut self, pos: BlockPos, block: u32, tick: u32, priority: i8) {
+        if self.scheduled.insert((pos, block)) {
             self.ticks.insert(BlockTick {
                 pos,
+                block,
                 tick,
                 priority,
                 sequence: self.sequence,
             });
-            self.sequence += 1;
         }
+        // like vanilla, the sequence also counts ticks that weren't scheduled
+        self.sequence = self.sequence.wrapping_add(1);
     }
 
-    pub fn pop_at_or_before(&mut self, tick: u32) -> Option<BlockTick> {
-        if self.ticks.first()?.tick <= tick {
+    pub fn has_scheduled_tick(&self, pos: BlockPos, block: u32) -> bool {
+        self.scheduled.contains(&(pos, block))
+    }
+
+    pub fn will_tick_this_tick(&self, pos: BlockPos, block: u32) -> bool {
+        self.to_run_this_tick.contains(&(pos, block))
+    }
+
+    /// Takes the ticks due at the tick out, in the order to run them.
+    /// Ticks scheduled while running them run in a later tick at the earliest.
+    pub fn collect_due(&mut self, tick: u32) -> Vec<BlockTick> {
+        let mut due = Vec::new();
+        while self.ticks.first().is_some_and(|it| it.tick <= tick) {
             let block_tick = self.ticks.pop_first().unwrap();
-            self.positions.remove(&block_tick.pos);
-            Some(block_tick)
-        } else {
-            None
+            self.scheduled.remove(&(block_tick.pos, block_tick.block));
+            self.to_run_this_tick.insert((block_tick.pos, block_tick.block));
+            due.push(block_tick);
         }
+        self.running = true;
+        due
+    }
+
+    /// Called right before the collected tick runs.
+    pub fn start_running(&mut self, block_tick: &BlockTick) {
+        self.to_run_this_tick.remove(&(block_tick.pos, block_tick.block));
+    }
+
+    /// Called once the collected ticks ran.
+    pub fn finish_running(&mut self) {
+        self.running = false;
     }
 }
 
@@ -157,10 +278,24 @@ pub struct LocalWorld<
     const SIZE_Y: usize,
 > {
     chunks: [[Mutex<M, Option<Chunk>>; SIZE_Y]; SIZE_X],
+    /// Always locked after the chunk being written, see [`Self::reserve_cached_section`].
+    cached_sections: Mutex<M, CachedSections>,
     tick_number: Mutex<M, u32>,
     block_tick_scheduler: Mutex<M, BlockTickScheduler>,
     pub redstone_override: Option<Mutex<M, Box<dyn DynifiedRedstoneOverride>>>,
+    pub neighbor_update_mode: NeighborUpdateMode,
+    neighbor_updater: Mutex<M, CollectingNeighborUpdater>,
+    /// See [`World::lock_edits`]
+    edit_lock: Mutex<M, ()>,
+    /// Updates of [`NeighborUpdateMode::Deferred`]
     block_updates: Mutex<M, SmallVec<[BlockPos; 1024]>>,
+    /// In the order they were queued, without duplicates.
+    block_events: Mutex<M, VecDeque<BlockEvent>>,
+    /// Block changes and events of this tick already put in order, see [`World::gen_blocks_update_packets_and_clear_changes`].
+    outgoing_packets: Mutex<M, Vec<MCPacketBuffer>>,
+    regions: Mutex<M, Regions>,
+    next_entity_id: AtomicI32,
+    entities: Mutex<M, BTreeMap<i32, Entity>>,
 }
 
 impl<M: RawMutex, const MIN_X: i16, const MIN_Y: i16, const SIZE_X: usize, const SIZE_Y: usize>
@@ -169,10 +304,19 @@ impl<M: RawMutex, const MIN_X: i16, const MIN_Y: i16, const SIZE_X: usize, const
     pub fn new() -> Self {
         Self {
             chunks: core::array::from_fn(|_| core::array::from_fn(|_| Mutex::new(None))),
+            cached_sections: Mutex::new(CachedSections::new()),
             tick_number: Mutex::new(0),
             block_tick_scheduler: Mutex::new(BlockTickScheduler::new()),
             redstone_override: None,
+            neighbor_update_mode: NeighborUpdateMode::default(),
+            neighbor_updater: Mutex::new(CollectingNeighborUpdater::new(DEFAULT_MAX_CHAINED_UPDATES)),
+            edit_lock: Mutex::new(()),
             block_updates: Mutex::new(SmallVec::new()),
+            block_events: Mutex::new(VecDeque::new()),
+            outgoing_packets: Mutex::new(Vec::new()),
+            regions: Mutex::new(Regions::new()),
+            next_entity_id: AtomicI32::new(1),
+            entities: Mutex::new(BTreeMap::new()),
         }
     }
 
@@ -198,6 +342,69 @@ impl<M: RawMutex, const MIN_X: i16, const MIN_Y: i16, const SIZE_X: usize, const
         Ok(())
     }
 
+    /// Makes room for the section of the locked chunk to keep its network encoding, `false` if it can't.
+    /// Other chunks are only tried to be locked, as they may be waiting for the cached sections themselves.
+    fn reserve_cached_section(
+        &self,
+        cached_sections: &mut CachedSections,
+        chunk: &mut Chunk,
+        section: (ChunkPos, i8),
+    ) -> bool {
+        if cached_sections.touch(section) {
+            return true;
+        }
+        let Some(evicted) = cached_sections.least_recent() else {
+            return false;
+        };
+        if evicted.0 == section.0 {
+            if let Ok(evicted_section) = chunk.get_section_mut(evicted.1) {
+                evicted_section.clear_encoded();
+            }
+        } else if let Ok(chunk_mutex) = self.get_chunk_mutex(evicted.0) {
+            let Ok(mut evicted_chunk) = chunk_mutex.try_lock() else {
+                return false;
+            };
+            if let Some(evicted_chunk) = evicted_chunk.as_mut()
+                && let Ok(evicted_section) = evicted_chunk.get_section_mut(evicted.1)
+            {
+                evicted_section.clear_encoded();
+            }
+        }
+        cached_sections.remove(evicted);
+        cached_sections.touch(section)
+    }
+
+    /// Like vanilla's `max_chained_neighbor_updates`, only used with [`NeighborUpdateMode::Chained`].
+    pub async fn set_max_chained_neighbor_updates(&self, max: u32) {
+        *self.neighbor_updater.lock().await = CollectingNeighborUpdater::new(max);
+    }
+
+    async fn collect_block_update_packets(&self, packets: &mut Vec<MCPacketBuffer>) {
+        for x in MIN_X..(MIN_X + SIZE_X as i16) {
+            for y in MIN_Y..(MIN_Y + SIZE_Y as i16) {
+                let pos = ChunkPos::new(x, y);
+                if let Ok(mut chunk) = self.get_chunk(pos).await {
+                    chunk.gen_blocks_update_packets_and_clear_changes(pos, packets).await;
+                }
+            }
+        }
+    }
+
+    async fn block_entity_positions(&self) -> Vec<BlockPos> {
+        let mut positions = Vec::new();
+        for x in MIN_X..(MIN_X + SIZE_X as i16) {
+            for y in MIN_Y..(MIN_Y + SIZE_Y as i16) {
+                let chunk_pos = ChunkPos::new(x, y);
+                if let Ok(chunk) = self.get_chunk(chunk_pos).await {
+                    positions.extend(chunk.block_entity_positions().map(|it| {
+                        BlockPos::new(x * 16 + it.x() as i16, it.y(), y * 16 + it.z() as i16)
+                    }));
+                }
+            }
+        }
+        positions
+    }
+
     const fn _min_corner() -> (i16, i16) {
         (MIN_X, MIN_Y)
     }
@@ -226,6 +433,79 @@ impl _World {
             None
         }
     }
+
+    /// The block event phase of the tick, like vanilla's `runBlockEvents`.
+    /// Events queued meanwhile run in the same phase.
+    async fn run_block_events(&self) {
+        if self.block_events.lock().await.is_empty() {
+            return;
+        }
+        // clients get the changes made so far before the events, and the changes they cause after them,
+        // the same order they are applied in here
+        let mut packets = Vec::new();
+        self.collect_block_update_packets(&mut packets).await;
+        self.outgoing_packets.lock().await.append(&mut packets);
+
+        let mut c = SmallVec::<[MaybeUninit<u8>; 512]>::new();
+        while let Some(event) = { self.block_events.lock().await.pop_front() } {
+            let Ok(blockstate) = self.get_block_state(event.pos).await else {
+                continue;
+            };
+            if blockstate.get_block().resloc() != event.block {
+                continue;
+            }
+            let triggered = blockstate
+                .get_block()
+                .trigger_event(self, event.pos, blockstate, event.event_type, event.event_param)
+                .init(&mut c)
+                .await;
+            if triggered {
+                let mut pkt = MCPacketBuffer::new(7).await; // block_event
+                pkt.write_block_pos(event.pos).await.unwrap();
+                pkt.write_be(event.event_type).await.unwrap();
+                pkt.write_be(event.event_param).await.unwrap();
+                pkt.write_varint(blockstate.block_id()).await.unwrap();
+                self.outgoing_packets.lock().await.push(pkt);
+            }
+        }
+    }
+
+    /// Runs the collected neighbor updates if `start`, i.e. if they aren't already running further up.
+    async fn run_neighbor_updates(&self, start: bool) {
+        if !start {
+            return;
+        }
+        // if this future is dropped halfway, the updates left must not make the updater look busy forever
+        let _clear_on_drop = ClearOnDrop(&self.neighbor_updater);
+        let mut c = SmallVec::<[MaybeUninit<u8>; 512]>::new();
+        while let Some(step) = { self.neighbor_updater.lock().await.next_step() } {
+            match step {
+                NeighborUpdateStep::Neighbor(pos) => {
+                    if let Ok(blockstate) = self.get_block_state(pos).await {
+                        blockstate.get_block().update(self, pos, blockstate).init(&mut c).await;
+                    }
+                }
+                NeighborUpdateStep::Shape(pos) => {
+                    if let Ok(blockstate) = self.get_block_state(pos).await {
+                        blockstate.get_block().update_shape(self, pos, blockstate).init(&mut c).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Clears the neighbor updater when dropped, see [`CollectingNeighborUpdater::clear`].
+struct ClearOnDrop<'a, M: RawMutex>(&'a Mutex<M, CollectingNeighborUpdater>);
+
+impl<M: RawMutex> Drop for ClearOnDrop<'_, M> {
+    fn drop(&mut self) {
+        // the updater is never locked across an await, so it's free unless another core has it
+        match self.0.try_lock() {
+            Ok(mut updater) => updater.clear(),
+            Err(_) => error!("Neighbor updater busy, couldn't clear it"),
+        }
+    }
 }
 
 impl World for _World {
@@ -236,24 +516,61 @@ impl World for _World {
     }
 
     async fn set_block_state(&self, pos: BlockPos, value: BlockState) -> Result<BlockState, ()> {
-        self.get_chunk(pos.chunk_pos())
-            .await?
-            .set_block_state(pos.chunk_local_pos(), value)
+        let (old, removed) = {
+            let mut chunk = self.get_chunk(pos.chunk_pos()).await?;
+            let local_pos = pos.chunk_local_pos();
+            let old = chunk.set_block_state(local_pos, value)?;
+            let mut removed = None;
+            if old.get_block().resloc() != value.get_block().resloc() {
+                removed = chunk.remove_block_entity(local_pos);
+                if let Some(block_entity) = value.get_block().create_block_entity(value) {
+                    chunk.set_block_entity(local_pos, block_entity);
+                }
+            }
+            (old, removed)
+        };
+        if let Some(block_entity) = removed {
+            let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+            old.get_block()
+                .on_block_entity_removed(self, pos, old, block_entity)
+                .init(&mut c)
+                .await;
+        }
+        Ok(old)
+    }
+
+    async fn with_block_entity<T: BlockEntity, R>(&self, pos: BlockPos, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let mut chunk = self.get_chunk(pos.chunk_pos()).await.ok()?;
+        let block_entity = chunk.get_block_entity_mut(pos.chunk_local_pos())?.downcast_mut::<T>()?;
+        Some(f(block_entity))
+    }
+
+    async fn block_entity_changed(&self, pos: BlockPos) {
+        if let Ok(mut chunk) = self.get_chunk(pos.chunk_pos()).await {
+            chunk.mark_block_entity_changed(pos.chunk_local_pos());
+        }
+    }
+
+    type EditGuard<'a> = MutexGuard<'a, NoopRawMutex, ()>;
+
+    async fn lock_edits(&self) -> Self::EditGuard<'_> {
+        self.edit_lock.lock().await
     }
 
     async fn tick(&self) {
+        let _edits = self.lock_edits().await;
         let current_tick = { *self.tick_number.lock().await };
 
         info!("tick {}", current_tick);
 
         let mut c = [MaybeUninit::uninit(); 512];
-        while let Some(block_tick) = {
-            self.block_tick_scheduler
-                .lock()
-                .await
-                .pop_at_or_before(current_tick)
-        } {
-            if let Ok(blockstate) = self.get_block_state(block_tick.pos).await {
+        let due = self.block_tick_scheduler.lock().await.collect_due(current_tick);
+        for block_tick in due {
+            self.block_tick_scheduler.lock().await.start_running(&block_tick);
+            // stale ticks of blocks replaced since they were scheduled are dropped
+            if let Ok(blockstate) = self.get_block_state(block_tick.pos).await
+                && blockstate.block_id() == block_tick.block
+            {
                 blockstate
                     .get_block()
                     .tick(self, block_tick.pos, blockstate)
@@ -261,6 +578,46 @@ impl World for _World {
                     .await;
             }
         }
+        self.block_tick_scheduler.lock().await.finish_running();
+
+        self.run_block_events().await;
+
+        for pos in self.block_entity_positions().await {
+            if let Ok(blockstate) = self.get_block_state(pos).await {
+                blockstate
+                    .get_block()
+                    .tick_block_entity(self, pos, blockstate)
+                    .init(&mut c)
+                    .await;
+            }
+        }
+
+        let entity_ids: Vec<i32> = self.entities.lock().await.keys().copied().collect();
+        for id in entity_ids {
+            // taken out while ticking, as ticking accesses the chunks
+            let Some(mut entity) = self.entities.lock().await.remove(&id) else {
+                continue;
+            };
+            let old_chunk_pos = entity.chunk_pos();
+            if !entity.tick(self).await {
+                if let Ok(mut chunk) = self.get_chunk(old_chunk_pos).await {
+                    chunk.remove_entity(id);
+                }
+                continue;
+            }
+            let new_chunk_pos = entity.chunk_pos();
+            if new_chunk_pos != old_chunk_pos {
+                if let Ok(mut chunk) = self.get_chunk(old_chunk_pos).await {
+                    chunk.remove_entity(id);
+                }
+                // entities leaving the loaded chunks are removed
+                let Ok(mut chunk) = self.get_chunk(new_chunk_pos).await else {
+                    continue;
+                };
+                chunk.add_entity(id);
+            }
+            self.entities.lock().await.insert(id, entity);
+        }
 
         while let Some(pos) = { self.block_updates.lock().await.pop() } {
             if let Ok(blockstate) = self.get_block_state(pos).await {
@@ -277,35 +634,94 @@ impl World for _World {
         *self.tick_number.lock().await += 1;
     }
 
+    async fn current_tick(&self) -> u32 {
+        *self.tick_number.lock().await
+    }
+
     async fn update_block(&self, pos: BlockPos) {
-        // if let Ok(blockstate) = self.get_block_state(pos).await {
-        //     let mut c = SmallVec::<[MaybeUninit<u8>; 1024]>::new();
-        //     blockstate
-        //         .get_block()
-        //         .update(self, pos, blockstate)
-        //         .init(&mut c)
-        //         .await;
-        // }
-        self.block_updates.lock().await.push(pos);
+        match self.neighbor_update_mode {
+            NeighborUpdateMode::Chained => {
+                let start = self.neighbor_updater.lock().await.add_single(NeighborUpdateStep::Neighbor(pos));
+                self.run_neighbor_updates(start).await;
+            }
+            NeighborUpdateMode::Deferred => self.block_updates.lock().await.push(pos),
+        }
+    }
+
+    async fn update_neighbors(&self, pos: BlockPos) {
+        match self.neighbor_update_mode {
+            NeighborUpdateMode::Chained => {
+                let start = self.neighbor_updater.lock().await.add_neighbors(pos, None);
+                self.run_neighbor_updates(start).await;
+            }
+            NeighborUpdateMode::Deferred => {
+                for &direction in Direction::variants() {
+                    self.update_block(pos.offset_dir(direction)).await;
+                }
+            }
+        }
+    }
+
+    async fn update_neighbors_except_for_direction(&self, pos: BlockPos, except: Direction) {
+        match self.neighbor_update_mode {
+            NeighborUpdateMode::Chained => {
+                let start = self.neighbor_updater.lock().await.add_neighbors(pos, Some(except));
+                self.run_neighbor_updates(start).await;
+            }
+            NeighborUpdateMode::Deferred => {
+                for &direction in Direction::variants() {
+                    if direction != except {
+                        self.update_block(pos.offset_dir(direction)).await;
+                    }
+                }
+            }
+        }
     }
 
     async fn update_block_shape(&self, pos: BlockPos) {
-        if let Ok(blockstate) = self.get_block_state(pos).await {
-            let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
-            blockstate
-                .get_block()
-                .update_shape(self, pos, blockstate)
-                .init(&mut c)
-                .await;
+        match self.neighbor_update_mode {
+            NeighborUpdateMode::Chained => {
+                let start = self.neighbor_updater.lock().await.add_single(NeighborUpdateStep::Shape(pos));
+                self.run_neighbor_updates(start).await;
+            }
+            NeighborUpdateMode::Deferred => {
+                if let Ok(blockstate) = self.get_block_state(pos).await {
+                    let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+                    blockstate
+                        .get_block()
+                        .update_shape(self, pos, blockstate)
+                        .init(&mut c)
+                        .await;
+                }
+            }
         }
     }
 
-    async fn schedule_tick(&self, pos: BlockPos, delay: u8, priority: i8) {
-        self.block_tick_scheduler.lock().await.schedule(
-            pos,
-            *self.tick_number.lock().await + delay as u32,
-            priority,
-        );
+    async fn update_neighbors_shape(&self, pos: BlockPos) {
+        let order = match self.neighbor_update_mode {
+            NeighborUpdateMode::Chained => &UPDATE_SHAPE_ORDER,
+            NeighborUpdateMode::Deferred => Direction::variants(),
+        };
+        for &direction in order {
+            self.update_block_shape(pos.offset_dir(direction)).await;
+        }
+    }
+
+    async fn schedule_tick(&self, pos: BlockPos, blockstate: BlockState, delay: u8, priority: i8) {
+        let tick = *self.tick_number.lock().await + delay as u32;
+        self.block_tick_scheduler.lock().await.schedule(pos, blockstate.block_id(), tick, priority);
+    }
+
+    async fn has_scheduled_tick(&self, pos: BlockPos, blockstate: BlockState) -> bool {
+        self.block_tick_scheduler.lock().await.has_scheduled_tick(pos, blockstate.block_id())
+    }
+
+    async fn will_tick_this_tick(&self, pos: BlockPos, blockstate: BlockState) -> bool {
+        self.block_tick_scheduler.lock().await.will_tick_this_tick(pos, blockstate.block_id())
+    }
+
+    async fn is_handling_scheduled_ticks(&self) -> bool {
+        self.block_tick_scheduler.lock().await.running
     }
 
     async fn get_signal(&self, pos: BlockPos, direction: Direction) -> u8 {
@@ -400,26 +816,30 @@ impl World for _World {
         pos: ChunkPos,
         writer: &mut W,
     ) -> Result<(), EIOError<W::Error>> {
-        let chunk = self.get_chunk(pos).await;
+        let mut chunk = self.get_chunk(pos).await;
 
         writer.write_varint(0u32).await?; // heightmaps
 
         let total_size = match chunk.as_ref() {
-            Ok(c) => (-4..20)
-                .map(|y| c.get_section(y).unwrap().serialized_size() + 1 + 1)
-                .sum(),
+            Ok(c) => c.serialized_size(),
             Err(_) => (2 + 1 + 1 + 1 + 1) * 24,
         };
         writer.write_varint::<u32>(total_size as u32).await?; // bytes
 
+        let mut cached_sections = self.cached_sections.lock().await;
         for cy in -4..20 {
             // blocks
-            let section = match chunk.as_ref() {
-                Ok(c) => c.get_section(cy),
+            let section = match chunk.as_mut() {
+                Ok(c) => {
+                    // empty sections are as quick to encode as to copy
+                    let cache = c.get_section(cy).is_ok_and(|s| s.non_air_blocks() != 0)
+                        && self.reserve_cached_section(&mut cached_sections, c, (pos, cy));
+                    c.get_section_mut(cy).map(|s| (s, cache))
+                }
                 Err(_) => Err(()),
             };
             match section {
-                Ok(s) => s.serialize_into(writer).await?,
+                Ok((s, cache)) => s.serialize_into(writer, cache).await?,
                 Err(_) => {
                     // write empty section
                     writer.write_be(0u16).await?;
@@ -432,8 +852,12 @@ impl World for _World {
             writer.write_be(0u8).await?;
             writer.write_varint(0u32).await?;
         }
+        drop(cached_sections);
 
-        writer.write_varint(0u32).await?; // block entities
+        match chunk.as_ref() {
+            Ok(c) => c.serialize_block_entities_into(writer).await?,
+            Err(_) => writer.write_varint(0u32).await.map(|_| ())?,
+        }
 
         // light
         // writer.write_varint(1u32).await?;
@@ -472,18 +896,177 @@ impl World for _World {
         Ok(())
     }
 
-    async fn gen_blocks_update_packets_and_clear_changes(&self) -> Vec<MCPacketBuffer> {
-        let mut vec = Vec::<MCPacketBuffer>::new();
-        let (min_x, min_y) = Self::_min_corner();
-        let (size_x, size_y) = Self::_size();
-        for x in min_x..(min_x + size_x as i16) {
-            for y in min_y..(min_y + size_y as i16) {
-                let pos = ChunkPos::new(x, y);
-                if let Ok(mut chunk) = self.get_chunk(pos).await {
-                    vec.extend(chunk.gen_blocks_update_packets_and_clear_changes(pos).await);
+    async fn net_chunk_size(&self, pos: ChunkPos) -> usize {
+        let (data_size, block_entities_size) = match self.get_chunk(pos).await {
+            Ok(chunk) => (chunk.serialized_size(), chunk.block_entities_serialized_size()),
+            Err(_) => ((2 + 1 + 1 + 1 + 1) * 24, 1),
+        };
+        // heightmaps, data length, block entities, light masks & arrays
+        1 + 3 + data_size + block_entities_size + 4 * (1 + 8) + 2
+    }
+
+    async fn gen_blocks_update_packets_and_clear_changes(&self, packets: &mut Vec<MCPacketBuffer>) {
+        packets.append(&mut *self.outgoing_packets.lock().await);
+        self.collect_block_update_packets(packets).await;
+    }
+
+    async fn block_event(&self, pos: BlockPos, block: &'static ResLoc<'static>, event_type: u8, event_param: u8) {
+        let event = BlockEvent {
+            pos,
+            block,
+            event_type,
+            event_param,
+        };
+        let mut block_events = self.block_events.lock().await;
+        if !block_events.contains(&event) {
+            block_events.push_back(event);
+        }
+    }
+
+    async fn region_flags_at(&self, pos: BlockPos) -> RegionFlags {
+        self.regions.lock().await.flags_at(pos)
+    }
+
+    async fn set_region(&self, region: Region) {
+        self.regions.lock().await.set(region);
+    }
+
+    async fn remove_region(&self, name: &str) -> bool {
+        self.regions.lock().await.remove(name)
+    }
+
+    async fn regions(&self) -> Vec<Region> {
+        self.regions.lock().await.iter().cloned().collect()
+    }
+
+    fn allocate_entity_id(&self) -> i32 {
+        self.next_entity_id.fetch_add(1, AtomicOrdering::Relaxed)
+    }
+
+    async fn add_entity(&self, entity: Entity) -> Result<(), Entity> {
+        let Ok(mut chunk) = self.get_chunk(entity.chunk_pos()).await else {
+            return Err(entity);
+        };
+        let mut entities = self.entities.lock().await;
+        if entities.len() >= MAX_ENTITIES {
+            return Err(entity);
+        }
+        chunk.add_entity(entity.id);
+        entities.insert(entity.id, entity);
+        Ok(())
+    }
+
+    async fn add_entities(&self, entities: Vec<Entity>) -> Result<(), Vec<Entity>> {
+        for entity in &entities {
+            if self.get_chunk(entity.chunk_pos()).await.is_err() {
+                return Err(entities);
+            }
+        }
+        let added = entities.iter().map(|it| (it.id, it.chunk_pos())).collect::<Vec<_>>();
+        {
+            let mut all = self.entities.lock().await;
+            if all.len() + entities.len() > MAX_ENTITIES {
+                return Err(entities);
+            }
+            for entity in entities {
+                all.insert(entity.id, entity);
+            }
+        }
+        for (id, chunk_pos) in added {
+            // loaded as checked above
+            if let Ok(mut chunk) = self.get_chunk(chunk_pos).await {
+                chunk.add_entity(id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_entity(&self, id: i32) -> Option<Entity> {
+        let entity = self.entities.lock().await.remove(&id)?;
+        if let Ok(mut chunk) = self.get_chunk(entity.chunk_pos()).await {
+            chunk.remove_entity(id);
+        }
+        Some(entity)
+    }
+
+    async fn with_entity<R>(&self, id: i32, f: impl FnOnce(&mut Entity) -> R) -> Option<R> {
+        self.entities.lock().await.get_mut(&id).map(f)
+    }
+
+    async fn entities_near(&self, center: ChunkPos, radius: i16) -> Vec<Entity> {
+        let mut ids = Vec::new();
+        for cz in center.y - radius..=center.y + radius {
+            for cx in center.x - radius..=center.x + radius {
+                if let Ok(chunk) = self.get_chunk(ChunkPos::new(cx, cz)).await {
+                    ids.extend(chunk.entities());
                 }
             }
         }
-        vec
+        let entities = self.entities.lock().await;
+        ids.iter().filter_map(|id| entities.get(id).cloned()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(due: &[BlockTick]) -> Vec<(BlockPos, u32)> {
+        due.iter().map(|it| (it.pos, it.block)).collect()
+    }
+
+    #[test]
+    fn ticks_run_by_time_then_priority_then_schedule_order() {
+        let (a, b, c, d) = (
+            BlockPos::new(0, 64, 0),
+            BlockPos::new(1, 64, 0),
+            BlockPos::new(2, 64, 0),
+            BlockPos::new(3, 64, 0),
+        );
+        let mut scheduler = BlockTickScheduler::new();
+        scheduler.schedule(a, 1, 12, 0);
+        scheduler.schedule(b, 1, 10, 0);
+        scheduler.schedule(c, 1, 10, -1);
+        scheduler.schedule(d, 1, 10, 0);
+
+        assert_eq!(order(&scheduler.collect_due(10)), [(c, 1), (b, 1), (d, 1)]);
+        scheduler.finish_running();
+        assert!(scheduler.collect_due(11).is_empty());
+        scheduler.finish_running();
+        assert_eq!(order(&scheduler.collect_due(12)), [(a, 1)]);
+    }
+
+    #[test]
+    fn a_block_has_one_tick_per_position() {
+        let pos = BlockPos::new(0, 64, 0);
+        let mut scheduler = BlockTickScheduler::new();
+        scheduler.schedule(pos, 1, 10, 0);
+        scheduler.schedule(pos, 1, 5, -3);
+        scheduler.schedule(pos, 2, 10, 0);
+        assert!(scheduler.has_scheduled_tick(pos, 1));
+        assert!(scheduler.has_scheduled_tick(pos, 2));
+        assert!(!scheduler.has_scheduled_tick(pos, 3));
+
+        // the second tick of block 1 was dropped
+        assert!(scheduler.collect_due(5).is_empty());
+        scheduler.finish_running();
+        assert_eq!(order(&scheduler.collect_due(10)), [(pos, 1), (pos, 2)]);
+        assert!(!scheduler.has_scheduled_tick(pos, 1));
+    }
+
+    #[test]
+    fn collected_ticks_count_as_ticking_this_tick_until_they_run() {
+        let pos = BlockPos::new(0, 64, 0);
+        let mut scheduler = BlockTickScheduler::new();
+        scheduler.schedule(pos, 1, 10, 0);
+        let due = scheduler.collect_due(10);
+        assert!(scheduler.will_tick_this_tick(pos, 1));
+
+        scheduler.start_running(&due[0]);
+        assert!(!scheduler.will_tick_this_tick(pos, 1));
+        // can be scheduled again while it runs
+        scheduler.schedule(pos, 1, 10, 0);
+        assert!(scheduler.has_scheduled_tick(pos, 1));
+        scheduler.finish_running();
     }
 }
@@ -1,6 +1,8 @@
 #![allow(unused)]
 
-use crate::utils::{list_resloc_files_in_dir, read_json, resloc_path};
+use crate::utils::{list_resloc_files_in_dir, read_json, resloc_consts, resloc_const_ident, resloc_path};
+use itertools::Itertools;
+use std::collections::HashMap;
 use std::convert::{AsRef, Into};
 use std::error::Error;
 use std::str::FromStr;
@@ -15,6 +17,8 @@ pub struct BlockDef {
     default_state: u32,
     id_base: u32,
     total_states: u32,
+    hardness: f32,
+    requires_correct_tool_for_drops: bool,
     #[serde(rename = "blockstate_properties")]
     properties: Vec<Property>,
 }
@@ -70,6 +74,19 @@ impl BlockDef {
                 .unwrap()
         })
     }
+
+    /// Ids in the `minecraft:block` registry, which is sorted by the blocks' first state.
+    pub fn block_ids() -> HashMap<ResLoc<'static>, u16> {
+        Self::load_all()
+            .sorted_by_key(|block| block.id_base)
+            .enumerate()
+            .map(|(id, block)| (block.resloc, id as u16))
+            .collect()
+    }
+
+    pub fn default_state_id(&self) -> u32 {
+        self.id_base + self.default_state
+    }
 }
 
 pub mod macros {
@@ -92,32 +109,8 @@ pub mod macros {
         Literal::u32_unsuffixed(id_base).into_token_stream().into()
     }
 
-    fn resloc_const_ident(resloc: &ResLoc) -> String {
-        if resloc.namespace != MINECRAFT {
-            format!(
-                "{}_{}",
-                resloc.namespace.to_ascii_uppercase(),
-                resloc.path.to_ascii_uppercase()
-            )
-        } else {
-            format!("{}", resloc.path.to_ascii_uppercase())
-        }
-    }
-
     pub fn mc_block_resloc_consts(_input: TokenStream) -> TokenStream {
-        let lines = BlockDef::all_reslocs().map(|resloc| {
-            let name = format_ident!("{}", resloc_const_ident(&resloc));
-            let namespace = if resloc.namespace != MINECRAFT {
-                let namespace = resloc.namespace;
-                quote! {namespace}
-            } else {
-                quote! {::tileglobe_utils::MINECRAFT}
-            };
-            let path = resloc.path;
-            quote! {pub const #name: &'static ResLoc<'static> = &ResLoc::new(#namespace, #path);}
-        });
-
-        quote! {#(#lines)*}.into()
+        resloc_consts(BlockDef::all_reslocs()).into()
     }
 
     pub fn mc_blocks_registry(input: TokenStream) -> TokenStream {
@@ -207,4 +200,35 @@ pub mod macros {
 
         quote! {[#(#elements),*]}.into()
     }
+
+    /// Properties of all blocks, in the same order as [`mc_blocks_registry`].
+    pub fn mc_blocks_properties(_input: TokenStream) -> TokenStream {
+        let elements = BlockDef::load_all()
+            .sorted_by_key(|block| block.id_base)
+            .map(|block| {
+                let hardness = block.hardness;
+                let requires_correct_tool_for_drops = block.requires_correct_tool_for_drops;
+                let state_properties = block.properties.iter().map(|property| {
+                    let (def, values) = match property {
+                        Property::Boolean { def } => (def, vec!["true".to_owned(), "false".to_owned()]),
+                        Property::Integer { def, min, max } => (def, (*min..=*max).map(|it| it.to_string()).collect()),
+                        Property::Enum { def, values } => (def, values.clone()),
+                    };
+                    let name = &def.name;
+                    let id_group_size = def.id_group_size as u16;
+                    quote! {StateProperty {
+                        name: #name,
+                        values: &[#(#values),*],
+                        id_group_size: #id_group_size,
+                    }}
+                });
+                quote! {BlockProperties {
+                    hardness: #hardness,
+                    requires_correct_tool_for_drops: #requires_correct_tool_for_drops,
+                    state_properties: &[#(#state_properties),*],
+                }}
+            });
+
+        quote! {[#(#elements),*]}.into()
+    }
 }
@@ -0,0 +1,205 @@
+use crate::blocks::BlockDef;
+use crate::utils::{list_resloc_files_in_dir, read_json, resloc_path};
+use std::error::Error;
+use tileglobe_utils::resloc::ResLoc;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ItemDef {
+    #[serde(rename = "resource_location")]
+    #[serde(deserialize_with = "ResLoc::de_owned")]
+    resloc: ResLoc<'static>,
+    id: u32,
+    max_stack_size: u32,
+    tool: Option<ToolDef>,
+    /// The block placed by this item, if it's a block item.
+    #[serde(default, deserialize_with = "ResLoc::de_owned_option")]
+    block: Option<ResLoc<'static>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ToolDef {
+    default_mining_speed: f32,
+    rules: Vec<ToolRuleDef>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ToolRuleDef {
+    #[serde(deserialize_with = "ResLoc::de_owned_vec")]
+    blocks: Vec<ResLoc<'static>>,
+    speed: Option<f32>,
+    correct_for_drops: Option<bool>,
+}
+
+impl ItemDef {
+    pub const PATH: &'static str = "item_def";
+
+    pub fn load(resloc: &ResLoc) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_value(read_json(resloc_path(
+            Self::PATH,
+            resloc,
+            "json",
+        ))?)?)
+    }
+
+    pub fn all_reslocs() -> impl Iterator<Item = ResLoc<'static>> {
+        list_resloc_files_in_dir(Self::PATH).map(|e| e.0)
+    }
+
+    pub fn load_all() -> impl Iterator<Item = Self> {
+        Self::all_reslocs().map(|resloc| {
+            Self::load(&resloc)
+                .map_err(|err| format!("Failed to load ItemDef of {resloc}: {err}"))
+                .unwrap()
+        })
+    }
+}
+
+pub mod macros {
+    use super::*;
+    use crate::utils::{ParseIdent, resloc_const_ident, resloc_consts};
+    use itertools::Itertools;
+    use proc_macro::TokenStream;
+    use proc_macro2::Literal;
+    use quote::{format_ident, quote};
+    use syn::Token;
+    use syn::parse::{Parse, ParseStream};
+
+    pub fn mc_item_resloc_consts(_input: TokenStream) -> TokenStream {
+        resloc_consts(ItemDef::all_reslocs()).into()
+    }
+
+    /// The item placing each block, indexed by the block's id in the `minecraft:block` registry, 0 for none.
+    /// Blocks placed by multiple items get the first of them.
+    pub fn mc_block_items(_input: TokenStream) -> TokenStream {
+        let block_ids = BlockDef::block_ids();
+        let mut block_items = vec![0u32; block_ids.len()];
+        for item in ItemDef::load_all().sorted_by_key(|item| item.id) {
+            if let Some(block) = &item.block {
+                let block_id = *block_ids
+                    .get(block)
+                    .unwrap_or_else(|| panic!("Unknown block {block} placed by {}", item.resloc));
+                let block_item = &mut block_items[block_id as usize];
+                if *block_item == 0 {
+                    *block_item = item.id;
+                }
+            }
+        }
+
+        let elements = block_items.into_iter().map(Literal::u32_unsuffixed);
+        quote! {[#(#elements),*]}.into()
+    }
+
+    /// All items, indexed by their id.
+    pub fn mc_items_registry(input: TokenStream) -> TokenStream {
+        struct Input {
+            item: syn::Path,
+            resloc_consts: syn::Path,
+        }
+
+        impl Parse for Input {
+            fn parse(input: ParseStream) -> syn::Result<Self> {
+                input.parse_ident("item")?;
+                input.parse::<Token![:]>()?;
+                let item = input.parse::<syn::Path>()?;
+                input.parse::<Token![,]>()?;
+
+                input.parse_ident("resloc_consts")?;
+                input.parse::<Token![:]>()?;
+                let resloc_consts = input.parse::<syn::Path>()?;
+                input.parse::<Option<Token![,]>>()?;
+
+                Ok(Self { item, resloc_consts })
+            }
+        }
+
+        let input = syn::parse_macro_input!(input as Input);
+
+        let items = ItemDef::load_all()
+            .sorted_by_key(|item| item.id)
+            .collect::<Vec<_>>();
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(item.id as usize, i, "Item ids are not contiguous at {}", item.resloc);
+        }
+
+        let block_ids = BlockDef::block_ids();
+        // the same tags are used by many tools, so each list of blocks is only emitted once
+        let mut tool_blocks = Vec::<Vec<u16>>::new();
+        let mut tool_blocks_index = |reslocs: &[ResLoc<'static>], item: &ResLoc| {
+            let ids = reslocs
+                .iter()
+                .map(|resloc| {
+                    *block_ids
+                        .get(resloc)
+                        .unwrap_or_else(|| panic!("Unknown block {resloc} in a tool rule of {item}"))
+                })
+                .sorted()
+                .collect::<Vec<_>>();
+            tool_blocks.iter().position(|it| *it == ids).unwrap_or_else(|| {
+                tool_blocks.push(ids);
+                tool_blocks.len() - 1
+            })
+        };
+
+        let elements = items.iter().map(|item| {
+            let item_struct = &input.item;
+            let resloc_consts = &input.resloc_consts;
+            let const_ident = format_ident!("{}", resloc_const_ident(&item.resloc));
+            let id = Literal::u32_unsuffixed(item.id);
+            let max_stack_size = Literal::u32_unsuffixed(item.max_stack_size);
+            let tool = match &item.tool {
+                Some(tool) => {
+                    let default_mining_speed = tool.default_mining_speed;
+                    let rules = tool.rules.iter().map(|rule| {
+                        let blocks = format_ident!("TOOL_BLOCKS_{}", tool_blocks_index(&rule.blocks, &item.resloc));
+                        let speed = match rule.speed {
+                            Some(speed) => quote! {Some(#speed)},
+                            None => quote! {None},
+                        };
+                        let correct_for_drops = match rule.correct_for_drops {
+                            Some(correct_for_drops) => quote! {Some(#correct_for_drops)},
+                            None => quote! {None},
+                        };
+                        quote! {ToolRule {
+                            blocks: #blocks,
+                            speed: #speed,
+                            correct_for_drops: #correct_for_drops,
+                        }}
+                    });
+                    quote! {Some(Tool {
+                        default_mining_speed: #default_mining_speed,
+                        rules: &[#(#rules),*],
+                    })}
+                }
+                None => quote! {None},
+            };
+            let block = match &item.block {
+                Some(block) => {
+                    let block = BlockDef::load(block)
+                        .map_err(|err| format!("Failed to load BlockDef of {block} (placed by {}): {err}", item.resloc))
+                        .unwrap();
+                    let default_state = Literal::u32_unsuffixed(block.default_state_id());
+                    quote! {Some(BlockState(#default_state))}
+                }
+                None => quote! {None},
+            };
+            quote! {#item_struct {
+                resloc: #resloc_consts::#const_ident,
+                id: ItemId(#id),
+                max_stack_size: #max_stack_size,
+                tool: #tool,
+                block: #block,
+            }}
+        }).collect::<Vec<_>>();
+
+        let tool_blocks = tool_blocks.iter().enumerate().map(|(i, ids)| {
+            let ident = format_ident!("TOOL_BLOCKS_{}", i);
+            let ids = ids.iter().map(|&id| Literal::u16_unsuffixed(id));
+            quote! {const #ident: &[u16] = &[#(#ids),*];}
+        });
+
+        quote! {{
+            #(#tool_blocks)*
+            [#(#elements),*]
+        }}.into()
+    }
+}
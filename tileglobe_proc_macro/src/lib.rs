@@ -1,6 +1,8 @@
 use proc_macro::TokenStream;
 
 mod blocks;
+mod items;
+mod registries;
 mod utils;
 
 #[proc_macro]
@@ -17,3 +19,28 @@ pub fn mc_block_resloc_consts(input: TokenStream) -> TokenStream {
 pub fn mc_blocks_registry(input: TokenStream) -> TokenStream {
     blocks::macros::mc_blocks_registry(input)
 }
+
+#[proc_macro]
+pub fn mc_blocks_properties(input: TokenStream) -> TokenStream {
+    blocks::macros::mc_blocks_properties(input)
+}
+
+#[proc_macro]
+pub fn mc_item_resloc_consts(input: TokenStream) -> TokenStream {
+    items::macros::mc_item_resloc_consts(input)
+}
+
+#[proc_macro]
+pub fn mc_items_registry(input: TokenStream) -> TokenStream {
+    items::macros::mc_items_registry(input)
+}
+
+#[proc_macro]
+pub fn mc_block_items(input: TokenStream) -> TokenStream {
+    items::macros::mc_block_items(input)
+}
+
+#[proc_macro]
+pub fn mc_registry_id(input: TokenStream) -> TokenStream {
+    registries::macros::mc_registry_id(input)
+}
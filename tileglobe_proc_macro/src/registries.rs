@@ -0,0 +1,62 @@
+use crate::utils::{read_json, resloc_path};
+use std::collections::HashMap;
+use std::error::Error;
+use tileglobe_utils::resloc::ResLoc;
+
+/// Network ids of the entries of a registry without defs of its own, e.g. `minecraft:entity_type`.
+pub struct RegistryIds(HashMap<String, u32>);
+
+impl RegistryIds {
+    pub const PATH: &'static str = "registry_ids";
+
+    pub fn load(registry: &ResLoc) -> Result<Self, Box<dyn Error>> {
+        Ok(Self(serde_json::from_value(read_json(resloc_path(
+            Self::PATH,
+            registry,
+            "json",
+        ))?)?))
+    }
+
+    pub fn get(&self, entry: &ResLoc) -> Option<u32> {
+        self.0.get(&entry.to_string()).copied()
+    }
+}
+
+pub mod macros {
+    use super::*;
+    use proc_macro::TokenStream;
+    use proc_macro2::Literal;
+    use quote::ToTokens;
+    use syn::parse::{Parse, ParseStream};
+    use syn::{LitStr, Token};
+
+    /// The network id of the entry, e.g. `mc_registry_id!("entity_type", "item")`.
+    pub fn mc_registry_id(input: TokenStream) -> TokenStream {
+        struct Input {
+            registry: LitStr,
+            entry: LitStr,
+        }
+
+        impl Parse for Input {
+            fn parse(input: ParseStream) -> syn::Result<Self> {
+                let registry = input.parse()?;
+                input.parse::<Token![,]>()?;
+                let entry = input.parse()?;
+                input.parse::<Option<Token![,]>>()?;
+                Ok(Self { registry, entry })
+            }
+        }
+
+        let input = syn::parse_macro_input!(input as Input);
+        let registry = input.registry.value();
+        let entry = input.entry.value();
+        let registry = ResLoc::try_from(registry.as_str()).unwrap();
+        let entry = ResLoc::try_from(entry.as_str()).unwrap();
+
+        let id = RegistryIds::load(&registry)
+            .unwrap_or_else(|err| panic!("Failed to load the ids of {registry}: {err}"))
+            .get(&entry)
+            .unwrap_or_else(|| panic!("Unknown entry {entry} of {registry}"));
+        Literal::u32_unsuffixed(id).into_token_stream().into()
+    }
+}
@@ -1,9 +1,11 @@
 use itertools::Itertools;
+use quote::{format_ident, quote};
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use syn::parse::ParseBuffer;
+use tileglobe_utils::MINECRAFT;
 use tileglobe_utils::resloc::ResLoc;
 use walkdir::WalkDir;
 
@@ -53,6 +55,35 @@ pub fn list_resloc_files_in_dir(
         })
 }
 
+pub fn resloc_const_ident(resloc: &ResLoc) -> String {
+    if resloc.namespace != MINECRAFT {
+        format!(
+            "{}_{}",
+            resloc.namespace.to_ascii_uppercase(),
+            resloc.path.to_ascii_uppercase()
+        )
+    } else {
+        format!("{}", resloc.path.to_ascii_uppercase())
+    }
+}
+
+/// `pub const NAME: &'static ResLoc<'static> = ...;` for each resloc.
+pub fn resloc_consts(reslocs: impl Iterator<Item = ResLoc<'static>>) -> proc_macro2::TokenStream {
+    let lines = reslocs.map(|resloc| {
+        let name = format_ident!("{}", resloc_const_ident(&resloc));
+        let namespace = if resloc.namespace != MINECRAFT {
+            let namespace = resloc.namespace;
+            quote! {#namespace}
+        } else {
+            quote! {::tileglobe_utils::MINECRAFT}
+        };
+        let path = resloc.path;
+        quote! {pub const #name: &'static ResLoc<'static> = &ResLoc::new(#namespace, #path);}
+    });
+
+    quote! {#(#lines)*}
+}
+
 pub trait ParseIdent {
     fn parse_ident(&self, expected: &str) -> syn::Result<syn::Ident>;
 }
@@ -6,7 +6,10 @@ extern crate alloc;
 
 use tileglobe_server::MCClient;
 use tileglobe_proc_macro::mc_block_id_base;
+use embassy_rp::flash::Flash;
+use tileglobe_server::flash_storage::FlashStorage;
 use tileglobe_server::mc_server::MCServer;
+use tileglobe_server::player_lists::PlayerListConfig;
 use tileglobe::world::chunk::Chunk;
 use tileglobe_utils::pos::ChunkLocalPos;
 use tileglobe_utils::pos::ChunkPos;
@@ -54,6 +57,9 @@ use embedded_alloc::LlffHeap as Heap;
 #[global_allocator]
 static HEAP: Heap = Heap::empty();
 
+/// Size of the flash, as in `memory.x`, whose end keeps the player lists.
+const FLASH_SIZE: usize = 16 * 1024 * 1024;
+
 #[embassy_executor::task(pool_size = 1)]
 async fn main_task(spawner: Spawner, ps: Peripherals) -> ! {
     {
@@ -249,7 +255,12 @@ async fn main_task(spawner: Spawner, ps: Peripherals) -> ! {
         ],
     })));
 
+    static STORAGE: StaticCell<FlashStorage<SpinlockRawMutex<0>, FLASH_SIZE>> = StaticCell::new();
+    let storage = STORAGE.init(FlashStorage::new(Flash::new_blocking(ps.FLASH)));
+
     let mc_server = MC_SERVER.init(MCServer::new(world));
+    mc_server.load_player_lists(storage).await;
+    mc_server.apply_player_list_config(PlayerListConfig::from_build_env()).await;
 
     #[embassy_executor::task(pool_size = 3)]
     async fn socket_task(
@@ -315,6 +326,7 @@ async fn main_task(spawner: Spawner, ps: Peripherals) -> ! {
             let pos = BlockPos::new(x, 0, z);
             if let Ok(blockstate) = world.get_block_state(pos).await {
                 if ADC_BLOCKS.contains(&blockstate) {
+                    let _edits = world.lock_edits().await;
                     world.update_neighbors(pos).await;
                 }
                 if let Some((_, out)) = dac_blocks.iter_mut().find(|(bs, _)| *bs == blockstate) {
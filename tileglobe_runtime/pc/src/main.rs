@@ -7,6 +7,7 @@ use tileglobe::world::block::BlockState;
 use tileglobe::world::chunk::Chunk;
 use tileglobe::world::world::{LocalWorld, World, _World};
 use tileglobe_server::mc_server::MCServer;
+use tileglobe_server::player_lists::{PlayerListKind, PlayerListStorage};
 use tileglobe_server::MCClient;
 use tileglobe_utils::network::{MCPacketBuffer, WriteVarInt};
 use tileglobe_utils::pos::{ChunkLocalPos, ChunkPos};
@@ -42,6 +43,27 @@ async fn net_task(spawner: Spawner, mc_server: &'static MCServer<'static, Critic
     }
 }
 
+/// Keeps the player lists as text files in the working directory.
+struct FilePlayerListStorage;
+
+impl FilePlayerListStorage {
+    fn path(list: PlayerListKind) -> String {
+        format!("{}.txt", list.name())
+    }
+}
+
+impl PlayerListStorage for FilePlayerListStorage {
+    async fn load(&self, list: PlayerListKind) -> Option<Vec<u8>> {
+        std::fs::read(Self::path(list)).ok()
+    }
+
+    async fn save(&self, list: PlayerListKind, data: &[u8]) {
+        if let Err(err) = std::fs::write(Self::path(list), data) {
+            warn!("Failed to save {}: {err}", Self::path(list));
+        }
+    }
+}
+
 static WORLD: StaticCell<_World> = StaticCell::new();
 static MC_SERVER: StaticCell<MCServer<'_, CriticalSectionRawMutex, _World>> = StaticCell::new();
 
@@ -71,6 +93,7 @@ async fn main_task(spawner: Spawner) {
     }
 
     let mc_server = MC_SERVER.init(MCServer::new(world));
+    mc_server.load_player_lists(&FilePlayerListStorage).await;
 
     spawner.spawn(net_task(spawner, mc_server).unwrap());
 
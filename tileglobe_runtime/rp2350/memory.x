@@ -1,5 +1,6 @@
 MEMORY {
-    FLASH : ORIGIN = 0x10000000, LENGTH = 16384K
+    /* the last 128K keep the player lists, see tileglobe_server::flash_storage::STORAGE_SIZE */
+    FLASH : ORIGIN = 0x10000000, LENGTH = 16384K - 128K
     PSRAM : ORIGIN = 0x11000000, LENGTH = 8192K
     RAM : ORIGIN = 0x20000000, LENGTH = 512K
     SRAM8 : ORIGIN = 0x20080000, LENGTH = 4K
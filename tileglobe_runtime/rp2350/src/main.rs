@@ -46,7 +46,10 @@ use tileglobe::world::chunk::Chunk;
 use tileglobe::world::world::{LocalWorld, World};
 use tileglobe_proc_macro::mc_block_id_base;
 use tileglobe_server::MCClient;
+use embassy_rp::flash::Flash;
+use tileglobe_server::flash_storage::FlashStorage;
 use tileglobe_server::mc_server::MCServer;
+use tileglobe_server::player_lists::PlayerListConfig;
 use tileglobe_utils::direction::Direction;
 use tileglobe_utils::pos::{BlockPos, ChunkLocalPos, ChunkPos};
 
@@ -56,6 +59,9 @@ static HEAP: Heap = Heap::empty();
 static WORLD: StaticCell<_World> = StaticCell::new();
 static MC_SERVER: StaticCell<MCServer<'_, SpinlockRawMutex<0>, _World>> = StaticCell::new();
 
+/// Size of the flash, as in `memory.x`, whose end keeps the player lists.
+const FLASH_SIZE: usize = 16 * 1024 * 1024;
+
 #[embassy_executor::task(pool_size = 1)]
 async fn main_task(spawner: Spawner, ps: Peripherals) -> ! {
     {
@@ -231,7 +237,12 @@ async fn main_task(spawner: Spawner, ps: Peripherals) -> ! {
         ],
     })));
 
+    static STORAGE: StaticCell<FlashStorage<SpinlockRawMutex<0>, FLASH_SIZE>> = StaticCell::new();
+    let storage = STORAGE.init(FlashStorage::new(Flash::new_blocking(ps.FLASH)));
+
     let mc_server = MC_SERVER.init(MCServer::new(world));
+    mc_server.load_player_lists(storage).await;
+    mc_server.apply_player_list_config(PlayerListConfig::from_build_env()).await;
 
     #[embassy_executor::task(pool_size = 3)]
     async fn socket_task(
@@ -352,6 +363,7 @@ async fn main_task(spawner: Spawner, ps: Peripherals) -> ! {
             let pos = BlockPos::new(x, 0, z);
             if let Ok(blockstate) = world.get_block_state(pos).await {
                 if ADC_BLOCKS.contains(&blockstate) {
+                    let _edits = world.lock_edits().await;
                     world.update_neighbors(pos).await;
                 }
                 if let Some((_, out)) = dac_blocks.iter_mut().find(|(bs, _)| *bs == blockstate) {
@@ -0,0 +1,535 @@
+use crate::mc_server::MCServer;
+use crate::player::{DynifiedPlayer, GameMode};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::net::IpAddr;
+use core::str::FromStr;
+use dynify::Dynify;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use smallvec::SmallVec;
+use tileglobe::world::region::{Region, RegionFlags};
+use tileglobe::world::world::World;
+use tileglobe_utils::network::{MCPacketBuffer, WriteNumPrimitive, WriteUTF8, WriteVarInt};
+use tileglobe_utils::pos::BlockPos;
+
+/// Operator permission levels, see vanilla's `Commands.LEVEL_*`.
+pub const LEVEL_ALL: u8 = 0;
+pub const LEVEL_MODERATORS: u8 = 1;
+pub const LEVEL_GAMEMASTERS: u8 = 2;
+pub const LEVEL_ADMINS: u8 = 3;
+pub const LEVEL_OWNERS: u8 = 4;
+
+/// A command run by a player, e.g. `/gamemode creative`.
+#[dynify::dynify(DynifiedCommand)]
+pub trait Command<M, WORLD>
+where
+    M: RawMutex,
+    WORLD: World,
+{
+    /// `args` is everything after the command name, the error is shown to the sender.
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String>;
+}
+
+pub struct CommandEntry<'a, M: RawMutex, WORLD: World> {
+    pub name: &'static str,
+    /// Players below this level neither see nor can run the command.
+    pub permission_level: u8,
+    pub command: &'a dyn DynifiedCommand<M, WORLD>,
+}
+
+impl<M: RawMutex, WORLD: World> Clone for CommandEntry<'_, M, WORLD> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            permission_level: self.permission_level,
+            command: self.command,
+        }
+    }
+}
+
+/// The `commands` packet declaring the commands to the client, for completion and highlighting.
+/// Each command is declared with a single optional argument taking the rest of the line.
+pub async fn commands_packet<M: RawMutex, WORLD: World>(commands: &[CommandEntry<'_, M, WORLD>]) -> MCPacketBuffer {
+    const FLAG_LITERAL: u8 = 0x01;
+    const FLAG_ARGUMENT: u8 = 0x02;
+    const FLAG_EXECUTABLE: u8 = 0x04;
+    const PARSER_STRING: u32 = 5; // brigadier:string
+    const STRING_GREEDY_PHRASE: u32 = 2;
+
+    let mut pkt = MCPacketBuffer::new(16).await; // commands
+    let _ = pkt.write_varint(1 + 2 * commands.len() as u32).await;
+    // root, node 0
+    let _ = pkt.write_be(0u8).await;
+    let _ = pkt.write_varint(commands.len() as u32).await;
+    for i in 0..commands.len() {
+        let _ = pkt.write_varint(1 + 2 * i as u32).await;
+    }
+    for (i, entry) in commands.iter().enumerate() {
+        let _ = pkt.write_be(FLAG_LITERAL | FLAG_EXECUTABLE).await;
+        let _ = pkt.write_varint(1u32).await;
+        let _ = pkt.write_varint(2 + 2 * i as u32).await;
+        let _ = pkt.write_utf8(entry.name).await;
+
+        let _ = pkt.write_be(FLAG_ARGUMENT | FLAG_EXECUTABLE).await;
+        let _ = pkt.write_varint(0u32).await;
+        let _ = pkt.write_utf8("args").await;
+        let _ = pkt.write_varint(PARSER_STRING).await;
+        let _ = pkt.write_varint(STRING_GREEDY_PHRASE).await;
+    }
+    let _ = pkt.write_varint(0u32).await; // root index
+    pkt
+}
+
+/// The commands every server has, like their vanilla counterparts.
+pub fn builtin_commands<'a, M: RawMutex, WORLD: World>() -> [CommandEntry<'a, M, WORLD>; 10] {
+    [
+        CommandEntry {
+            name: "gamemode",
+            permission_level: LEVEL_GAMEMASTERS,
+            command: &GameModeCommand,
+        },
+        CommandEntry {
+            name: "kick",
+            permission_level: LEVEL_ADMINS,
+            command: &KickCommand,
+        },
+        CommandEntry {
+            name: "ban",
+            permission_level: LEVEL_ADMINS,
+            command: &BanCommand,
+        },
+        CommandEntry {
+            name: "pardon",
+            permission_level: LEVEL_ADMINS,
+            command: &PardonCommand,
+        },
+        CommandEntry {
+            name: "ban-ip",
+            permission_level: LEVEL_ADMINS,
+            command: &BanIpCommand,
+        },
+        CommandEntry {
+            name: "pardon-ip",
+            permission_level: LEVEL_ADMINS,
+            command: &PardonIpCommand,
+        },
+        CommandEntry {
+            name: "op",
+            permission_level: LEVEL_ADMINS,
+            command: &OpCommand,
+        },
+        CommandEntry {
+            name: "deop",
+            permission_level: LEVEL_ADMINS,
+            command: &DeopCommand,
+        },
+        CommandEntry {
+            name: "whitelist",
+            permission_level: LEVEL_ADMINS,
+            command: &WhitelistCommand,
+        },
+        CommandEntry {
+            name: "region",
+            permission_level: LEVEL_GAMEMASTERS,
+            command: &RegionCommand,
+        },
+    ]
+}
+
+/// Splits off the first word of the arguments, the rest is trimmed.
+fn next_arg(args: &str) -> (&str, &str) {
+    let args = args.trim_start();
+    let (arg, rest) = args.split_once(' ').unwrap_or((args, ""));
+    (arg, rest.trim())
+}
+
+async fn online_player<'a, M: RawMutex, WORLD: World>(
+    server: &MCServer<'a, M, WORLD>,
+    name: &str,
+) -> Result<Arc<dyn DynifiedPlayer + 'a>, String> {
+    server
+        .player_by_name(name)
+        .await
+        .ok_or_else(|| format!("No player was found: {}", name))
+}
+
+fn require<'s>(arg: &'s str, usage: &str) -> Result<&'s str, String> {
+    if arg.is_empty() {
+        return Err(format!("Usage: {}", usage));
+    }
+    Ok(arg)
+}
+
+struct GameModeCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for GameModeCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        const USAGE: &str = "/gamemode <survival|creative|adventure|spectator> [player]";
+        let (mode, rest) = next_arg(args);
+        let game_mode =
+            GameMode::from_name(require(mode, USAGE)?).ok_or_else(|| format!("Unknown game mode: {}", mode))?;
+        let (target, _) = next_arg(rest);
+        if target.is_empty() {
+            sender.set_game_mode(game_mode).init(&mut c).await;
+        } else {
+            let player = online_player(server, target).await?;
+            player.set_game_mode(game_mode).init(&mut c).await;
+            let message = format!("Set {}'s game mode to {}", target, game_mode.name());
+            sender.send_message(&message).init(&mut c).await;
+        }
+        Ok(())
+    }
+}
+
+struct KickCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for KickCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (name, reason) = next_arg(args);
+        let player = online_player(server, require(name, "/kick <player> [reason]")?).await?;
+        let reason = if reason.is_empty() {
+            "Kicked by an operator"
+        } else {
+            reason
+        };
+        player.kick(reason).init(&mut c).await;
+        sender
+            .send_message(&format!("Kicked {}: {}", name, reason))
+            .init(&mut c)
+            .await;
+        Ok(())
+    }
+}
+
+struct BanCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for BanCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (name, reason) = next_arg(args);
+        let name = require(name, "/ban <player> [reason]")?;
+        let reason = if reason.is_empty() {
+            "Banned by an operator"
+        } else {
+            reason
+        };
+        server.ban(name, reason).await;
+        sender
+            .send_message(&format!("Banned {}: {}", name, reason))
+            .init(&mut c)
+            .await;
+        Ok(())
+    }
+}
+
+struct PardonCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for PardonCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (name, _) = next_arg(args);
+        let name = require(name, "/pardon <player>")?;
+        server.pardon(name).await;
+        sender.send_message(&format!("Unbanned {}", name)).init(&mut c).await;
+        Ok(())
+    }
+}
+
+struct BanIpCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for BanIpCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (target, reason) = next_arg(args);
+        let target = require(target, "/ban-ip <address|player> [reason]")?;
+        // like vanilla, a player's name bans the address it's connected from
+        let ip = match IpAddr::from_str(target) {
+            Ok(ip) => ip,
+            Err(_) => online_player(server, target)
+                .await?
+                .address()
+                .init(&mut c)
+                .await
+                .map(|it| it.ip())
+                .ok_or_else(|| format!("The address of {} is unknown", target))?,
+        };
+        let reason = if reason.is_empty() {
+            "Banned by an operator"
+        } else {
+            reason
+        };
+        server.ban_ip(ip, reason).await;
+        sender
+            .send_message(&format!("Banned IP {}: {}", ip, reason))
+            .init(&mut c)
+            .await;
+        Ok(())
+    }
+}
+
+struct PardonIpCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for PardonIpCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (ip, _) = next_arg(args);
+        let ip =
+            IpAddr::from_str(require(ip, "/pardon-ip <address>")?).map_err(|_| String::from("Invalid IP address"))?;
+        server.pardon_ip(ip).await;
+        sender.send_message(&format!("Unbanned IP {}", ip)).init(&mut c).await;
+        Ok(())
+    }
+}
+
+struct OpCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for OpCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        const USAGE: &str = "/op <player> [level]";
+        let (name, rest) = next_arg(args);
+        let name = require(name, USAGE)?;
+        let (level, _) = next_arg(rest);
+        let level = match level {
+            "" => LEVEL_OWNERS,
+            level => level
+                .parse()
+                .ok()
+                .filter(|it| (LEVEL_MODERATORS..=LEVEL_OWNERS).contains(it))
+                .ok_or_else(|| format!("Usage: {}", USAGE))?,
+        };
+        // nobody can grant more than they have
+        if level > sender.permission_level().init(&mut c).await {
+            return Err(String::from("You can't grant a higher permission level than your own"));
+        }
+        server.op(name, level).await;
+        sender
+            .send_message(&format!("Made {} a server operator (level {})", name, level))
+            .init(&mut c)
+            .await;
+        Ok(())
+    }
+}
+
+struct DeopCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for DeopCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (name, _) = next_arg(args);
+        let name = require(name, "/deop <player>")?;
+        server.deop(name).await;
+        sender
+            .send_message(&format!("Made {} no longer a server operator", name))
+            .init(&mut c)
+            .await;
+        Ok(())
+    }
+}
+
+struct WhitelistCommand;
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for WhitelistCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        const USAGE: &str = "/whitelist <on|off|add|remove> [player]";
+        let (action, rest) = next_arg(args);
+        let (name, _) = next_arg(rest);
+        let message = match require(action, USAGE)? {
+            "on" => {
+                server.set_whitelist_enabled(true).await;
+                String::from("Whitelist is now turned on")
+            }
+            "off" => {
+                server.set_whitelist_enabled(false).await;
+                String::from("Whitelist is now turned off")
+            }
+            "add" => {
+                server.whitelist_add(require(name, USAGE)?).await;
+                format!("Added {} to the whitelist", name)
+            }
+            "remove" => {
+                server.whitelist_remove(require(name, USAGE)?).await;
+                format!("Removed {} from the whitelist", name)
+            }
+            _ => return Err(format!("Usage: {}", USAGE)),
+        };
+        sender.send_message(&message).init(&mut c).await;
+        Ok(())
+    }
+}
+
+struct RegionCommand;
+
+impl RegionCommand {
+    const USAGE: &str = "/region <add|remove|list|bypass> ...";
+    const ADD_USAGE: &str = "/region add <name> <x1> <y1> <z1> <x2> <y2> <z2> [build|break|interact...]";
+
+    fn parse_pos(args: &str) -> Result<(BlockPos, &str), String> {
+        let mut rest = args;
+        let mut coords = [0i16; 3];
+        for coord in &mut coords {
+            let (arg, next) = next_arg(rest);
+            *coord = require(arg, Self::ADD_USAGE)?
+                .parse()
+                .map_err(|_| format!("Invalid coordinate: {}", arg))?;
+            rest = next;
+        }
+        Ok((BlockPos::new(coords[0], coords[1], coords[2]), rest))
+    }
+
+    /// All flags if none are given.
+    fn parse_flags(args: &str) -> Result<RegionFlags, String> {
+        if args.is_empty() {
+            return Ok(RegionFlags::ALL);
+        }
+        let mut flags = RegionFlags::default();
+        for flag in args.split_whitespace() {
+            match flag {
+                "build" => flags.no_build = true,
+                "break" => flags.no_break = true,
+                "interact" => flags.no_interact = true,
+                _ => return Err(format!("Unknown region flag: {}", flag)),
+            }
+        }
+        Ok(flags)
+    }
+
+    fn describe_pos(pos: BlockPos) -> String {
+        format!("{} {} {}", pos.x, pos.y, pos.z)
+    }
+
+    fn describe_flags(flags: RegionFlags) -> String {
+        let names: Vec<&str> = [
+            (flags.no_build, "build"),
+            (flags.no_break, "break"),
+            (flags.no_interact, "interact"),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| name)
+        .collect();
+        names.join(" ")
+    }
+}
+
+impl<M: RawMutex, WORLD: World> Command<M, WORLD> for RegionCommand {
+    async fn execute(
+        &self,
+        server: &MCServer<'_, M, WORLD>,
+        sender: &dyn DynifiedPlayer,
+        args: &str,
+    ) -> Result<(), String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let (action, rest) = next_arg(args);
+        match require(action, Self::USAGE)? {
+            "add" => {
+                let (name, rest) = next_arg(rest);
+                let name = require(name, Self::ADD_USAGE)?;
+                let (a, rest) = Self::parse_pos(rest)?;
+                let (b, rest) = Self::parse_pos(rest)?;
+                let flags = Self::parse_flags(rest)?;
+                server.set_region(Region::new(String::from(name), a, b, flags)).await;
+                let message = format!("Protected region {} from {} to {}", name, Self::describe_pos(a), Self::describe_pos(b));
+                sender.send_message(&message).init(&mut c).await;
+            }
+            "remove" => {
+                let (name, _) = next_arg(rest);
+                if !server.remove_region(require(name, "/region remove <name>")?).await {
+                    return Err(format!("No region was found: {}", name));
+                }
+                sender.send_message(&format!("Removed region {}", name)).init(&mut c).await;
+            }
+            "list" => {
+                let regions = server.world.regions().await;
+                if regions.is_empty() {
+                    sender.send_message("There are no protected regions").init(&mut c).await;
+                }
+                for region in regions {
+                    let message = format!(
+                        "{}: {} to {}, no {}",
+                        region.name,
+                        Self::describe_pos(region.min),
+                        Self::describe_pos(region.max),
+                        Self::describe_flags(region.flags)
+                    );
+                    sender.send_message(&message).init(&mut c).await;
+                }
+            }
+            "bypass" => {
+                let (target, _) = next_arg(rest);
+                let uuid = if target.is_empty() {
+                    sender.uuid().init(&mut c).await
+                } else {
+                    online_player(server, target).await?.uuid().init(&mut c).await
+                };
+                let bypass = !server.bypasses_regions(uuid).await;
+                server.set_region_bypass(uuid, bypass).await;
+                let message = match (bypass, target.is_empty()) {
+                    (true, true) => String::from("You now bypass protected regions"),
+                    (false, true) => String::from("You no longer bypass protected regions"),
+                    (true, false) => format!("{} now bypasses protected regions", target),
+                    (false, false) => format!("{} no longer bypasses protected regions", target),
+                };
+                sender.send_message(&message).init(&mut c).await;
+            }
+            _ => return Err(format!("Usage: {}", Self::USAGE)),
+        }
+        Ok(())
+    }
+}
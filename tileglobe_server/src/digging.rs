@@ -0,0 +1,90 @@
+use tileglobe::world::block::BlockState;
+use tileglobe::world::item::ItemStack;
+use tileglobe_proc_macro::mc_registry_id;
+use tileglobe_utils::network::{MCPacketBuffer, WriteBlockPos, WriteNumPrimitive, WriteVarInt};
+use tileglobe_utils::pos::BlockPos;
+
+/// Minimum share of the destroy time a client must have dug for, before its finish is accepted right away.
+/// Like vanilla, this leaves room for latency, finishes earlier than that are delayed instead.
+const EARLY_FINISH_TOLERANCE: f32 = 0.7;
+
+const EFFICIENCY_ENCHANTMENT: u32 = mc_registry_id!("enchantment", "efficiency");
+
+/// A block being dug in survival or adventure mode.
+#[derive(Debug)]
+pub struct Digging {
+    pub pos: BlockPos,
+    pub start_tick: u32,
+    /// The client finished early, the block is broken once the server's timing catches up.
+    pub delayed: bool,
+    /// Last destruction stage sent to the other players.
+    pub stage: i8,
+}
+
+impl Digging {
+    pub fn new(pos: BlockPos, start_tick: u32) -> Self {
+        Self {
+            pos,
+            start_tick,
+            delayed: false,
+            stage: -1,
+        }
+    }
+
+    /// Share of the block dug by now, 1 or more when it's broken.
+    pub fn progress(&self, per_tick: f32, tick: u32) -> f32 {
+        per_tick * (tick.wrapping_sub(self.start_tick) + 1) as f32
+    }
+
+    pub fn can_finish(&self, per_tick: f32, tick: u32) -> bool {
+        self.progress(per_tick, tick) >= EARLY_FINISH_TOLERANCE
+    }
+
+    /// Destruction stage to show to other players (0-9), -1 for none.
+    pub fn stage_at(&self, per_tick: f32, tick: u32) -> i8 {
+        let progress = self.progress(per_tick, tick);
+        if progress > 0.0 {
+            ((progress * 10.0) as i32).min(9) as i8
+        } else {
+            -1
+        }
+    }
+}
+
+/// Share of the block dug per tick, see vanilla's `BlockState::getDestroyProgress`.
+pub fn destroy_progress_per_tick(blockstate: BlockState, held: &ItemStack, on_ground: bool) -> f32 {
+    let hardness = blockstate.properties().hardness;
+    if hardness < 0.0 {
+        return 0.0;
+    }
+    if hardness == 0.0 {
+        return 1.0;
+    }
+
+    let mut speed = held.item.get_item().map_or(1.0, |it| it.destroy_speed(blockstate));
+    if speed > 1.0 {
+        let efficiency = held.enchantment_level(EFFICIENCY_ENCHANTMENT);
+        if efficiency > 0 {
+            speed += (efficiency * efficiency + 1) as f32;
+        }
+    }
+    if !on_ground {
+        speed /= 5.0;
+    }
+    let divisor = if can_harvest(blockstate, held) { 30.0 } else { 100.0 };
+    speed / hardness / divisor
+}
+
+/// Whether breaking the block with the held item drops it.
+pub fn can_harvest(blockstate: BlockState, held: &ItemStack) -> bool {
+    !blockstate.properties().requires_correct_tool_for_drops
+        || held.item.get_item().is_some_and(|it| it.is_correct_tool_for_drops(blockstate))
+}
+
+pub async fn block_destruction_packet(entity_id: i32, pos: BlockPos, stage: i8) -> MCPacketBuffer {
+    let mut pkt = MCPacketBuffer::new(5).await; // block_destruction
+    let _ = pkt.write_varint(entity_id).await;
+    let _ = pkt.write_block_pos(pos).await;
+    let _ = pkt.write_be(stage).await;
+    pkt
+}
@@ -0,0 +1,159 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use glam::DVec3;
+use tileglobe::world::entity::{Entity, EntityKind};
+use tileglobe::world::item::ItemStack;
+use tileglobe_utils::network::{MCPacketBuffer, WriteNumPrimitive, WriteUUID, WriteVarInt};
+use uuid::Uuid;
+
+/// Metadata index of the stack of item entities.
+const ITEM_STACK_DATA_INDEX: u8 = 8;
+/// Serializer id of item stacks in entity metadata.
+const ITEM_STACK_SERIALIZER: u32 = 7;
+const DATA_END: u8 = 0xFF;
+/// Chunks around a player searched for entities to show, enough for the largest tracking range (96 blocks).
+pub(crate) const TRACKING_CHUNK_RADIUS: i16 = 6;
+
+/// An entity as last sent to a player.
+struct TrackedEntity {
+    position: DVec3,
+    velocity: DVec3,
+    stack: ItemStack,
+}
+
+/// The world's entities a player sees, like vanilla's `ChunkMap.TrackedEntity` from the other side.
+#[derive(Default)]
+pub(crate) struct EntityTracker {
+    entities: BTreeMap<i32, TrackedEntity>,
+}
+
+impl EntityTracker {
+    /// Packets spawning, moving and removing entities for a player at the position.
+    pub(crate) async fn update(&mut self, viewer: DVec3, entities: &[Entity]) -> Vec<MCPacketBuffer> {
+        let mut packets = Vec::new();
+        let mut seen = BTreeSet::new();
+        for entity in entities.iter().filter(|it| in_range(viewer, it)) {
+            seen.insert(entity.id);
+            let stack = entity_stack(entity);
+            match self.entities.get_mut(&entity.id) {
+                None => {
+                    packets.push(add_entity_packet(entity).await);
+                    packets.push(set_entity_data_packet(entity.id, &stack).await);
+                    self.entities.insert(
+                        entity.id,
+                        TrackedEntity {
+                            position: entity.position,
+                            velocity: entity.velocity,
+                            stack,
+                        },
+                    );
+                }
+                Some(tracked) => {
+                    if tracked.position != entity.position || tracked.velocity != entity.velocity {
+                        packets.push(entity_position_sync_packet(entity).await);
+                        tracked.position = entity.position;
+                        tracked.velocity = entity.velocity;
+                    }
+                    if tracked.stack != stack {
+                        packets.push(set_entity_data_packet(entity.id, &stack).await);
+                        tracked.stack = stack;
+                    }
+                }
+            }
+        }
+
+        let removed = self
+            .entities
+            .keys()
+            .copied()
+            .filter(|it| !seen.contains(it))
+            .collect::<Vec<_>>();
+        if !removed.is_empty() {
+            let mut pkt = MCPacketBuffer::new(70).await; // remove_entities
+            let _ = pkt.write_varint(removed.len() as u32).await;
+            for id in removed {
+                let _ = pkt.write_varint(id).await;
+                self.entities.remove(&id);
+            }
+            packets.push(pkt);
+        }
+        packets
+    }
+}
+
+fn in_range(viewer: DVec3, entity: &Entity) -> bool {
+    let range = entity.entity_type().tracking_range;
+    let delta = (entity.position - viewer).abs();
+    delta.x <= range && delta.z <= range
+}
+
+fn entity_stack(entity: &Entity) -> ItemStack {
+    match &entity.kind {
+        EntityKind::Item(item) => item.stack.clone(),
+    }
+}
+
+/// Entities other than players have no UUID of their own, the client only needs it to be unique.
+fn entity_uuid(id: i32) -> Uuid {
+    Uuid::from_u64_pair(0, id as u32 as u64)
+}
+
+/// In 1/8000 blocks per tick, clamped like vanilla.
+fn encode_velocity(velocity: f64) -> i16 {
+    (velocity.clamp(-3.9, 3.9) * 8000.0) as i16
+}
+
+async fn add_entity_packet(entity: &Entity) -> MCPacketBuffer {
+    let mut pkt = MCPacketBuffer::new(1).await; // add_entity
+    let _ = pkt.write_varint(entity.id).await;
+    let _ = pkt.write_uuid(entity_uuid(entity.id)).await;
+    let _ = pkt.write_varint(entity.entity_type().id).await;
+    let _ = pkt.write_be(entity.position.x).await;
+    let _ = pkt.write_be(entity.position.y).await;
+    let _ = pkt.write_be(entity.position.z).await;
+    let _ = pkt.write_be(0u8).await; // pitch
+    let _ = pkt.write_be(0u8).await; // yaw
+    let _ = pkt.write_be(0u8).await; // head yaw
+    let _ = pkt.write_varint(0u32).await; // data
+    let _ = pkt.write_be(encode_velocity(entity.velocity.x)).await;
+    let _ = pkt.write_be(encode_velocity(entity.velocity.y)).await;
+    let _ = pkt.write_be(encode_velocity(entity.velocity.z)).await;
+    pkt
+}
+
+async fn set_entity_data_packet(id: i32, stack: &ItemStack) -> MCPacketBuffer {
+    let mut pkt = MCPacketBuffer::new(92).await; // set_entity_data
+    let _ = pkt.write_varint(id).await;
+    let _ = pkt.write_be(ITEM_STACK_DATA_INDEX).await;
+    let _ = pkt.write_varint(ITEM_STACK_SERIALIZER).await;
+    if stack.write_into(&mut pkt).await.is_err() {
+        // malformed components, shown as an empty stack rather than breaking the packet
+        let _ = ItemStack::EMPTY.write_into(&mut pkt).await;
+    }
+    let _ = pkt.write_be(DATA_END).await;
+    pkt
+}
+
+async fn entity_position_sync_packet(entity: &Entity) -> MCPacketBuffer {
+    let mut pkt = MCPacketBuffer::new(31).await; // entity_position_sync
+    let _ = pkt.write_varint(entity.id).await;
+    let _ = pkt.write_be(entity.position.x).await;
+    let _ = pkt.write_be(entity.position.y).await;
+    let _ = pkt.write_be(entity.position.z).await;
+    let _ = pkt.write_be(entity.velocity.x).await;
+    let _ = pkt.write_be(entity.velocity.y).await;
+    let _ = pkt.write_be(entity.velocity.z).await;
+    let _ = pkt.write_be(0f32).await; // yaw
+    let _ = pkt.write_be(0f32).await; // pitch
+    let _ = pkt.write_be(entity.on_ground as u8).await;
+    pkt
+}
+
+/// Plays the pickup animation of the item entity flying to the collector, before it is removed.
+pub(crate) async fn take_item_entity_packet(item_id: i32, collector_id: i32, count: u8) -> MCPacketBuffer {
+    let mut pkt = MCPacketBuffer::new(117).await; // take_item_entity
+    let _ = pkt.write_varint(item_id).await;
+    let _ = pkt.write_varint(collector_id).await;
+    let _ = pkt.write_varint(count as u32).await;
+    pkt
+}
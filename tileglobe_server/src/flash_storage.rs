@@ -0,0 +1,122 @@
+use crate::player_lists::{PlayerListKind, PlayerListStorage};
+use alloc::vec;
+use alloc::vec::Vec;
+use defmt_or_log::*;
+use embassy_rp::flash::{Blocking, ERASE_SIZE, Flash};
+use embassy_rp::peripherals::FLASH;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::Mutex;
+
+/// Bytes at the end of the flash kept for the storage, the firmware must end before them (see `memory.x`).
+pub const STORAGE_SIZE: usize = 128 * 1024;
+
+/// Sectors of each slot, 16 KiB.
+const SECTORS_PER_SLOT: usize = 4;
+const SLOT_SIZE: usize = SECTORS_PER_SLOT * ERASE_SIZE;
+/// Bytes before the data of a slot, its length.
+const HEADER_SIZE: usize = 4;
+/// Erased flash, a slot that was never written.
+const ERASED_LENGTH: u32 = u32::MAX;
+/// Data is written in pieces copied from the heap to the stack, as the PSRAM the heap is in can't be read while
+/// the flash is written.
+const WRITE_CHUNK_SIZE: usize = 256;
+
+const PLAYER_LIST_SLOTS: usize = PlayerListKind::ALL.len();
+const _: () = core::assert!(PLAYER_LIST_SLOTS * SLOT_SIZE <= STORAGE_SIZE);
+
+/// Keeps the player lists and regions in the last [`STORAGE_SIZE`] bytes of the RP2350's flash,
+/// for runtimes without files.
+///
+/// Each list has a slot of [`SLOT_SIZE`] bytes, holding its length as u32 LE followed by the data.
+/// A slot is erased before it's written, so a list is lost if the power is cut while saving it.
+pub struct FlashStorage<M: RawMutex, const FLASH_SIZE: usize> {
+    flash: Mutex<M, Flash<'static, FLASH, Blocking, FLASH_SIZE>>,
+}
+
+impl<M: RawMutex, const FLASH_SIZE: usize> FlashStorage<M, FLASH_SIZE> {
+    pub fn new(flash: Flash<'static, FLASH, Blocking, FLASH_SIZE>) -> Self {
+        Self {
+            flash: Mutex::new(flash),
+        }
+    }
+
+    fn slot_offset(slot: usize) -> u32 {
+        (FLASH_SIZE - STORAGE_SIZE + slot * SLOT_SIZE) as u32
+    }
+
+    /// `None` if the slot was never written or can't be read.
+    async fn load_slot(&self, slot: usize) -> Option<Vec<u8>> {
+        let mut flash = self.flash.lock().await;
+        let offset = Self::slot_offset(slot);
+        let mut length = [0u8; HEADER_SIZE];
+        if let Err(err) = flash.blocking_read(offset, &mut length) {
+            error!("Failed to read flash slot {}: {:?}", slot, err);
+            return None;
+        }
+        let length = u32::from_le_bytes(length);
+        if length == ERASED_LENGTH {
+            return None;
+        }
+        if length as usize > SLOT_SIZE - HEADER_SIZE {
+            warn!("Flash slot {} has an invalid length {}, ignoring it", slot, length);
+            return None;
+        }
+        let mut data = vec![0u8; length as usize];
+        if let Err(err) = flash.blocking_read(offset + HEADER_SIZE as u32, &mut data) {
+            error!("Failed to read flash slot {}: {:?}", slot, err);
+            return None;
+        }
+        Some(data)
+    }
+
+    /// `false` if the data doesn't fit or couldn't be written.
+    async fn save_slot(&self, slot: usize, data: &[u8]) -> bool {
+        if data.len() > SLOT_SIZE - HEADER_SIZE {
+            error!("{} bytes don't fit in flash slot {}", data.len(), slot);
+            return false;
+        }
+        let mut flash = self.flash.lock().await;
+        let offset = Self::slot_offset(slot);
+        let used = (HEADER_SIZE + data.len()).next_multiple_of(ERASE_SIZE);
+        if let Err(err) = flash.blocking_erase(offset, offset + used as u32) {
+            error!("Failed to erase flash slot {}: {:?}", slot, err);
+            return false;
+        }
+
+        let mut chunk = [0u8; WRITE_CHUNK_SIZE];
+        chunk[..HEADER_SIZE].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        let (first, rest) = data.split_at(data.len().min(WRITE_CHUNK_SIZE - HEADER_SIZE));
+        chunk[HEADER_SIZE..HEADER_SIZE + first.len()].copy_from_slice(first);
+        let mut written = HEADER_SIZE + first.len();
+        let mut result = flash.blocking_write(offset, &chunk[..written]);
+        for piece in rest.chunks(WRITE_CHUNK_SIZE) {
+            if result.is_err() {
+                break;
+            }
+            chunk[..piece.len()].copy_from_slice(piece);
+            result = flash.blocking_write(offset + written as u32, &chunk[..piece.len()]);
+            written += piece.len();
+        }
+        if let Err(err) = result {
+            error!("Failed to write flash slot {}: {:?}", slot, err);
+            return false;
+        }
+        true
+    }
+}
+
+impl<M: RawMutex, const FLASH_SIZE: usize> PlayerListStorage for FlashStorage<M, FLASH_SIZE> {
+    async fn load(&self, list: PlayerListKind) -> Option<Vec<u8>> {
+        self.load_slot(player_list_slot(list)).await
+    }
+
+    async fn save(&self, list: PlayerListKind, data: &[u8]) {
+        if !self.save_slot(player_list_slot(list), data).await {
+            error!("Player list {} was not saved", list.name());
+        }
+    }
+}
+
+fn player_list_slot(list: PlayerListKind) -> usize {
+    PlayerListKind::ALL.iter().position(|&it| it == list).unwrap()
+}
@@ -0,0 +1,120 @@
+use crate::player::GameMode;
+use glam::{DVec2, DVec3, Vec2, Vec3};
+use tileglobe_utils::direction::Direction;
+use tileglobe_utils::pos::BlockPos;
+
+/// Default `block_interaction_range` attribute, creative mode adds 0.5.
+const BLOCK_INTERACTION_RANGE: f64 = 4.5;
+/// Allowance for the player moving between the client's check and the server's, like vanilla.
+const REACH_TOLERANCE: f64 = 1.0;
+const EYE_HEIGHT: f64 = 1.62;
+/// Leeway for floating point error of the cursor position.
+const CURSOR_EPSILON: f32 = 1.0e-4;
+
+/// Whether a player at the position can reach the block, from the eyes to the closest point of the block.
+pub fn can_reach(position: DVec3, game_mode: GameMode, pos: BlockPos) -> bool {
+    let range = match game_mode {
+        GameMode::Creative => BLOCK_INTERACTION_RANGE + 0.5,
+        _ => BLOCK_INTERACTION_RANGE,
+    } + REACH_TOLERANCE;
+    let eyes = position + DVec3::new(0.0, EYE_HEIGHT, 0.0);
+    let min = pos.as_dvec3();
+    let closest = eyes.clamp(min, min + DVec3::ONE);
+    eyes.distance_squared(closest) <= range * range
+}
+
+/// Whether the clicked point lies on the block, on the side of the face the player is looking at.
+/// From inside the block, any face can be clicked.
+pub fn is_valid_hit(position: DVec3, pos: BlockPos, face: Direction, cursor: Vec3, inside_block: bool) -> bool {
+    let on_block = cursor.cmpge(Vec3::splat(-CURSOR_EPSILON)).all()
+        && cursor.cmple(Vec3::splat(1.0 + CURSOR_EPSILON)).all();
+    if !on_block {
+        return false;
+    }
+    if inside_block {
+        return true;
+    }
+    // faces can only be clicked from the front, the eyes are in front of the clicked point
+    // the tolerance also covers the lower eyes while sneaking
+    let hit = pos.as_dvec3() + cursor.as_dvec3();
+    let eyes = position + DVec3::new(0.0, EYE_HEIGHT, 0.0);
+    (eyes - hit).dot(face.normal_i16().as_dvec3()) > -REACH_TOLERANCE
+}
+
+/// Where an item thrown by a player at the position appears and how fast it flies,
+/// like vanilla's `Player.drop` without the random spread. The rotation is (yaw, pitch) in degrees.
+pub fn throw_item_motion(position: DVec3, rotation: Vec2) -> (DVec3, DVec3) {
+    // (cos, sin) of the angles
+    let yaw = DVec2::from_angle(rotation.x.to_radians() as f64);
+    let pitch = DVec2::from_angle(rotation.y.to_radians() as f64);
+    let origin = position + DVec3::new(0.0, EYE_HEIGHT - 0.3, 0.0);
+    let velocity = DVec3::new(-yaw.y * pitch.x * 0.3, -pitch.y * 0.3 + 0.1, yaw.x * pitch.x * 0.3);
+    (origin, velocity)
+}
+
+/// Whether a player at the position touches the item entity, with vanilla's pickup range of
+/// the player's box inflated by (1, 0.5, 1) overlapping the item's box.
+pub fn can_pick_up(position: DVec3, item_position: DVec3) -> bool {
+    let delta = item_position - position;
+    delta.x.abs() < 0.3 + 1.0 + 0.125 && delta.z.abs() < 0.3 + 1.0 + 0.125 && (-0.75..2.3).contains(&delta.y)
+}
+
+/// Limits how often a player can do something, e.g. interact with blocks, as a token bucket refilled every tick.
+#[derive(Debug)]
+pub struct RateLimiter {
+    tokens: u32,
+    capacity: u32,
+    refill_per_tick: u32,
+}
+
+impl RateLimiter {
+    pub const fn new(capacity: u32, refill_per_tick: u32) -> Self {
+        Self {
+            tokens: capacity,
+            capacity,
+            refill_per_tick,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.tokens = (self.tokens + self.refill_per_tick).min(self.capacity);
+    }
+
+    /// Takes a token, `false` if the limit is reached.
+    pub fn try_acquire(&mut self) -> bool {
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acquire_all(limiter: &mut RateLimiter) -> u32 {
+        core::iter::from_fn(|| limiter.try_acquire().then_some(())).count() as u32
+    }
+
+    #[test]
+    fn rate_limiter_starts_full() {
+        let mut limiter = RateLimiter::new(5, 2);
+        assert_eq!(acquire_all(&mut limiter), 5);
+        assert!(!limiter.try_acquire());
+    }
+
+    #[test]
+    fn rate_limiter_refills_every_tick_up_to_the_capacity() {
+        let mut limiter = RateLimiter::new(5, 2);
+        acquire_all(&mut limiter);
+        limiter.tick();
+        assert_eq!(acquire_all(&mut limiter), 2);
+
+        for _ in 0..10 {
+            limiter.tick();
+        }
+        assert_eq!(acquire_all(&mut limiter), 5);
+    }
+}
@@ -5,8 +5,16 @@
 extern crate alloc;
 
 pub mod utils;
+pub mod commands;
+pub mod digging;
+mod entity_tracker;
+#[cfg(feature = "rp")]
+pub mod flash_storage;
+pub mod interaction;
 pub mod mc_client;
 pub mod mc_server;
+pub mod menu;
 pub mod player;
+pub mod player_lists;
 
 pub use mc_client::MCClient;
@@ -1,35 +1,63 @@
+use crate::commands::LEVEL_GAMEMASTERS;
+use crate::digging::{Digging, block_destruction_packet, can_harvest, destroy_progress_per_tick};
+use crate::interaction::{RateLimiter, can_pick_up, can_reach, is_valid_hit, throw_item_motion};
 use crate::mc_server::MCServer;
-use crate::player::Player;
+use crate::menu::{INVENTORY_SLOTS, Menu, MenuSlots, QuickCraft, add_to_inventory, pick_item};
+use crate::player::{Abilities, GameMode, MCPacketSink, Player, TitleTimes};
 use crate::utils::MCPlayerUUID;
 use alloc::boxed::Box;
 use alloc::format;
 use alloc::string::String;
-use const_for::const_for;
-use core::cmp::max;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::error::Error;
 use core::fmt::{Debug, Formatter};
 use core::mem::MaybeUninit;
 use core::net::SocketAddr;
+use core::pin::pin;
+use core::sync::atomic::{AtomicU32, Ordering};
 use defmt_or_log::*;
 use dynify::Dynify;
-use embassy_futures::select::Either;
+use embassy_futures::select::{Either, Either3, select, select3};
 use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::{Channel, TrySendError};
 use embassy_sync::mutex::{Mutex, MutexGuard};
-use embassy_time::{Duration, Ticker};
-use glam::Vec3;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Ticker, with_timeout};
+use glam::{DVec3, Vec2, Vec3};
 use num_traits::{ToPrimitive, abs};
 use smallvec::SmallVec;
-use tileglobe::world::block::BlockState;
+use tileglobe::world::block::{BlockState, InteractionHand, UseContext};
+use tileglobe::world::entity::{block_pos_at, Entity, EntityKind, ItemEntity};
+use tileglobe::world::item::{ItemId, ItemStack};
+use tileglobe::world::region::RegionFlags;
 use tileglobe::world::world::{_World, World};
 use tileglobe_utils::direction::Direction;
 use tileglobe_utils::network::{
-    EIOError, EIOReadExactError, MCPacketBuffer, ReadBlockPos, ReadBool, ReadExt, ReadIndexedEnum,
+    ByteBuf, EIOError, EIOReadExactError, MCPacketBatch, MCPacketBuffer, ReadBlockPos, ReadBool, ReadExt, ReadIndexedEnum,
     ReadNumPrimitive, ReadUTF8, ReadUTF8Error, ReadUUID, ReadVarInt, ReadVarIntError, VarIntType,
-    WriteMCPacket, WriteNumPrimitive, WriteUTF8, WriteUUID, WriteVarInt,
+    WriteBlockPos, WriteMCPacket, WriteNumPrimitive, WriteTextComponent, WriteUTF8, WriteUUID,
+    WriteVarInt,
 };
-use tileglobe_utils::pos::ChunkPos;
+use tileglobe_utils::pos::{BlockPos, ChunkPos};
 use uuid::Uuid;
 
+/// Capacity of a client's send queue, in ticks worth of packets.
+const SEND_QUEUE_CAPACITY: usize = 32;
+/// Number of consecutive ticks a client's send queue may stay full before the client is disconnected.
+const SEND_QUEUE_MAX_FULL_TICKS: u32 = 100;
+/// How long a batch being written when disconnecting may take to finish, see `play_send_packets`.
+const DISCONNECT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Most bytes of packets held back while the send queue is full, the client is disconnected beyond that.
+const TX_BATCH_MAX_BYTES: usize = 256 * 1024;
+/// Block interactions (placing, starting and finishing digging) a player can make at once,
+/// and how many more every tick after that.
+const INTERACTION_BURST: u32 = 16;
+const INTERACTION_REFILL_PER_TICK: u32 = 2;
+/// Items a player can drop with Q or out of the creative inventory at once, and how many more every tick after that.
+const DROP_BURST: u32 = 16;
+const DROP_REFILL_PER_TICK: u32 = 1;
+
 #[derive(derive_more::Display)]
 #[display("{self:?}")]
 pub struct MCClient<
@@ -42,6 +70,12 @@ pub struct MCClient<
     server: &'a MCServer<'a, SM, _World>,
     rx: Mutex<M, RX>,
     tx: Mutex<M, TX>,
+    /// Outgoing play packets, moved to `send_queue` once per tick.
+    tx_batch: Mutex<M, MCPacketBatch>,
+    /// Drained into `tx` by the client's own task, so that ticking never waits on network I/O.
+    send_queue: Channel<M, MCPacketBatch, SEND_QUEUE_CAPACITY>,
+    send_queue_full_ticks: AtomicU32,
+    disconnect: Signal<M, String>,
     addr: Option<SocketAddr>,
     player_data: Option<Mutex<M, PlayerData>>,
 
@@ -51,8 +85,65 @@ pub struct MCClient<
 struct PlayerData {
     uuid: Uuid,
     name: String,
+    entity_id: i32,
+    position: DVec3,
+    /// (yaw, pitch)
+    rotation: Vec2,
+    on_ground: bool,
+    sneaking: bool,
+    next_teleport_id: i32,
+    /// Set while a teleport is unconfirmed, movement packets are ignored until then.
+    awaiting_teleport: Option<i32>,
+    game_mode: GameMode,
+    abilities: Abilities,
+    permission_level: u8,
+    /// Ticks since the player joined, the clock digging is timed with.
+    ticks: u32,
+    digging: Option<Digging>,
+    interaction_limiter: RateLimiter,
+    drop_limiter: RateLimiter,
     selected_hotbar_slot: u8,
-    inventory_items: [u16; 46],
+    inventory: [ItemStack; INVENTORY_SLOTS],
+    /// Held by the cursor while a menu (or the inventory) is open.
+    carried: ItemStack,
+    menu: Option<OpenMenu>,
+    next_window_id: u8,
+    container_state_id: i32,
+    quick_craft: QuickCraft,
+}
+
+impl PlayerData {
+    fn held_item(&self) -> &ItemStack {
+        &self.inventory[36 + self.selected_hotbar_slot as usize]
+    }
+
+    fn window_id(&self) -> u8 {
+        self.menu.as_ref().map_or(0, |it| it.window_id)
+    }
+
+    fn menu_slots(&mut self) -> MenuSlots<'_> {
+        MenuSlots::new(
+            self.menu.as_ref().map(|it| &it.menu),
+            &mut self.inventory,
+            &mut self.carried,
+        )
+    }
+
+    /// Closes the open menu on the server side, the client is told separately if needed.
+    /// Returns the carried items that don't fit back into the inventory, to be thrown.
+    fn close_menu(&mut self) -> ItemStack {
+        self.quick_craft = QuickCraft::default();
+        let rest = self.menu_slots().return_carried();
+        self.menu = None;
+        rest
+    }
+}
+
+struct OpenMenu {
+    window_id: u8,
+    menu: Menu,
+    /// Container slots as last sent to the client, to send only what others changed.
+    remote_slots: Vec<ItemStack>,
 }
 
 impl<M: RawMutex, RX: embedded_io_async::Read, TX: embedded_io_async::Write, SM: RawMutex> Player
@@ -65,23 +156,195 @@ where
         self.player_data().await.uuid
     }
 
+    async fn name(&self) -> String {
+        self.player_data().await.name.clone()
+    }
+
+    async fn entity_id(&self) -> i32 {
+        self.player_data().await.entity_id
+    }
+
+    async fn position(&self) -> DVec3 {
+        self.player_data().await.position
+    }
+
+    async fn rotation(&self) -> Vec2 {
+        self.player_data().await.rotation
+    }
+
+    async fn on_ground(&self) -> bool {
+        self.player_data().await.on_ground
+    }
+
+    async fn teleport(&self, position: DVec3, rotation: Vec2) {
+        let teleport_id = {
+            let mut player_data = self.player_data().await;
+            player_data.position = position;
+            player_data.rotation = rotation;
+            let teleport_id = player_data.next_teleport_id;
+            player_data.next_teleport_id = teleport_id.wrapping_add(1);
+            player_data.awaiting_teleport = Some(teleport_id);
+            teleport_id
+        };
+
+        let mut pkt = MCPacketBuffer::new(65).await; // player_position
+        let _ = pkt.write_varint(teleport_id).await;
+        let _ = pkt.write_be(position.x).await;
+        let _ = pkt.write_be(position.y).await;
+        let _ = pkt.write_be(position.z).await;
+        let _ = pkt.write_be(0f64).await; // velocity
+        let _ = pkt.write_be(0f64).await;
+        let _ = pkt.write_be(0f64).await;
+        let _ = pkt.write_be(rotation.x).await;
+        let _ = pkt.write_be(rotation.y).await;
+        let _ = pkt.write_be(0u32).await; // relative flags
+        self.queue_mc_packet(&pkt).await;
+    }
+
+    async fn send_message(&self, message: &str) {
+        let mut pkt = MCPacketBuffer::new(114).await; // system_chat
+        let _ = pkt.write_text_component(message).await;
+        let _ = pkt.write_be(false as u8).await; // overlay
+        self.queue_mc_packet(&pkt).await;
+    }
+
+    async fn send_action_bar(&self, message: &str) {
+        let mut pkt = MCPacketBuffer::new(114).await; // system_chat
+        let _ = pkt.write_text_component(message).await;
+        let _ = pkt.write_be(true as u8).await; // overlay
+        self.queue_mc_packet(&pkt).await;
+    }
+
+    async fn set_title(&self, title: &str, subtitle: &str, times: TitleTimes) {
+        let mut pkt = MCPacketBuffer::new(108).await; // set_titles_animation
+        let _ = pkt.write_be(times.fade_in).await;
+        let _ = pkt.write_be(times.stay).await;
+        let _ = pkt.write_be(times.fade_out).await;
+        self.queue_mc_packet(&pkt).await;
+
+        // the subtitle is only shown once the title is set
+        let mut pkt = MCPacketBuffer::new(105).await; // set_subtitle_text
+        let _ = pkt.write_text_component(subtitle).await;
+        self.queue_mc_packet(&pkt).await;
+
+        let mut pkt = MCPacketBuffer::new(107).await; // set_title_text
+        let _ = pkt.write_text_component(title).await;
+        self.queue_mc_packet(&pkt).await;
+    }
+
+    async fn game_mode(&self) -> GameMode {
+        self.player_data().await.game_mode
+    }
+
+    async fn set_game_mode(&self, game_mode: GameMode) {
+        {
+            let mut player_data = self.player_data().await;
+            player_data.game_mode = game_mode;
+            game_mode.update_abilities(&mut player_data.abilities);
+        }
+        self.stop_digging().await;
+
+        let mut pkt = MCPacketBuffer::new(34).await; // game_event
+        let _ = pkt.write_be(3u8).await; // change game mode
+        let _ = pkt.write_be(game_mode.id() as f32).await;
+        self.queue_mc_packet(&pkt).await;
+        self.send_abilities().await;
+    }
+
+    async fn permission_level(&self) -> u8 {
+        self.player_data().await.permission_level
+    }
+
+    async fn set_permission_level(&self, level: u8) {
+        let entity_id = {
+            let mut player_data = self.player_data().await;
+            player_data.permission_level = level;
+            player_data.entity_id
+        };
+        self.send_permission_level(entity_id, level).await;
+    }
+
+    async fn address(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+
+    async fn kick(&self, reason: &str) {
+        self.disconnect.signal(String::from(reason));
+    }
+
+    async fn open_menu(&self, menu: Menu) {
+        let mut pkt = MCPacketBuffer::new(52).await; // open_screen
+        let rest = {
+            let mut player_data = self.player_data().await;
+            let rest = player_data.close_menu();
+            player_data.next_window_id = player_data.next_window_id % 100 + 1;
+            let window_id = player_data.next_window_id;
+
+            let _ = pkt.write_varint(window_id as u32).await;
+            let _ = pkt.write_varint(menu.menu_type.id()).await;
+            let _ = pkt.write_text_component(&menu.title).await;
+
+            player_data.menu = Some(OpenMenu {
+                window_id,
+                menu,
+                remote_slots: Vec::new(),
+            });
+            rest
+        };
+        self.throw_item(rest).await;
+        self.queue_mc_packet(&pkt).await;
+        self.send_container_content().await;
+    }
+
+    async fn close_menu(&self) {
+        let (window_id, rest) = {
+            let mut player_data = self.player_data().await;
+            let window_id = player_data.window_id();
+            if window_id == 0 {
+                return;
+            }
+            (window_id, player_data.close_menu())
+        };
+        self.throw_item(rest).await;
+        let mut pkt = MCPacketBuffer::new(17).await; // container_close
+        let _ = pkt.write_varint(window_id as u32).await;
+        self.queue_mc_packet(&pkt).await;
+        self.send_container_content().await;
+    }
+
     async fn tick(&self) {
+        {
+            let mut player_data = self.player_data().await;
+            player_data.ticks += 1;
+            player_data.interaction_limiter.tick();
+            player_data.drop_limiter.tick();
+        }
+        self.tick_digging().await;
+        self.pick_up_items().await;
+        self.send_container_changes().await;
+
         {
             let mut _block_changes_to_ack = self._block_changes_to_ack.lock().await;
             for seq in &*_block_changes_to_ack {
                 let mut pkt = MCPacketBuffer::new(4).await; // block_changed_ack
                 let _ = pkt.write_varint(*seq).await;
-                let _ = self.write_mc_packet(&pkt).await;
+                self.queue_mc_packet(&pkt).await;
             }
             _block_changes_to_ack.clear();
         }
+
+        self.submit_mc_packets().await;
     }
+}
 
-    // gross...
+impl<M: RawMutex, RX: embedded_io_async::Read, TX: embedded_io_async::Write, SM: RawMutex> MCPacketSink
+    for MCClient<'_, M, RX, TX, SM>
+where
+    RX::Error: 'static,
+    TX::Error: 'static,
+{
     async fn send_mc_packet(&self, pkt: &MCPacketBuffer) {
-        if let Err(err) = self.write_mc_packet(pkt).await {
-            error!("error sending packet: {:?}", Debug2Format(&err));
-        }
+        self.queue_mc_packet(pkt).await;
     }
 }
 
@@ -130,6 +393,46 @@ where
         self.tx.lock().await.write_mc_packet(pkt).await
     }
 
+    /// Queues a packet to be sent with the next [`Self::submit_mc_packets`].
+    /// Clients not keeping up are disconnected once [`TX_BATCH_MAX_BYTES`] pile up, dropping the packets.
+    async fn queue_mc_packet(&self, pkt: &MCPacketBuffer) {
+        let mut tx_batch = self.tx_batch.lock().await;
+        if !tx_batch.is_empty() && tx_batch.len() + pkt.len() > TX_BATCH_MAX_BYTES {
+            warn!("{} has more than {} bytes of packets waiting", self, TX_BATCH_MAX_BYTES);
+            tx_batch.clear();
+            self.disconnect.signal(String::from("Send queue overflow"));
+            return;
+        }
+        tx_batch.push(pkt).await;
+    }
+
+    /// Moves the queued packets onto the send queue without waiting for network I/O.
+    /// If the send queue is full, they are kept and submitted together with the next batch.
+    async fn submit_mc_packets(&self) {
+        let mut tx_batch = self.tx_batch.lock().await;
+        if !tx_batch.is_empty() {
+            let batch = core::mem::replace(&mut *tx_batch, MCPacketBatch::new());
+            if let Err(TrySendError::Full(batch)) = self.send_queue.try_send(batch) {
+                *tx_batch = batch;
+            }
+        }
+
+        if self.send_queue.is_full() {
+            let full_ticks = self.send_queue_full_ticks.fetch_add(1, Ordering::Relaxed) + 1;
+            if full_ticks == SEND_QUEUE_MAX_FULL_TICKS {
+                warn!("{} send queue stayed full for {} ticks", self, full_ticks);
+                self.disconnect.signal(String::from("Send queue overflow"));
+            }
+        } else {
+            self.send_queue_full_ticks.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of ticks worth of packets waiting to be sent.
+    pub fn send_queue_depth(&self) -> usize {
+        self.send_queue.len()
+    }
+
     async fn read_mc_packet_header(&self, rx: &mut RX) -> Result<(usize, i32), MCClientError> {
         let packet_length = rx.read_varint::<i32>().await? as usize;
         let packet_type = rx.read_varint::<i32>().await?;
@@ -140,14 +443,440 @@ where
         Ok((packet_length, packet_type))
     }
 
+    /// Sends every slot of the open window and the carried stack, replacing the client's predictions.
+    async fn send_container_content(&self) {
+        let mut player_data = self.player_data().await;
+        let player_data = &mut *player_data;
+        player_data.container_state_id = (player_data.container_state_id + 1) & 0x7FFF;
+
+        let mut pkt = MCPacketBuffer::new(18).await; // container_set_content
+        let _ = pkt.write_varint(player_data.window_id() as u32).await;
+        let _ = pkt.write_varint(player_data.container_state_id).await;
+        let slots = MenuSlots::new(
+            player_data.menu.as_ref().map(|it| &it.menu),
+            &mut player_data.inventory,
+            &mut player_data.carried,
+        );
+        let _ = pkt.write_varint(slots.len() as u32).await;
+        for slot in 0..slots.len() {
+            write_slot(&mut pkt, &slots.get(slot)).await;
+        }
+        write_slot(&mut pkt, slots.carried).await;
+
+        if let Some(open_menu) = &mut player_data.menu {
+            let container = &open_menu.menu.container;
+            open_menu.remote_slots = (0..open_menu.menu.menu_type.size())
+                .map(|slot| container.get_item(slot))
+                .collect();
+        }
+        self.queue_mc_packet(&pkt).await;
+    }
+
+    /// Sends the slots of the open menu's container that were changed by someone else.
+    async fn send_container_changes(&self) {
+        let mut player_data = self.player_data().await;
+        let state_id = player_data.container_state_id;
+        let Some(open_menu) = &mut player_data.menu else {
+            return;
+        };
+        for (slot, remote) in open_menu.remote_slots.iter_mut().enumerate() {
+            let stack = open_menu.menu.container.get_item(slot);
+            if stack != *remote {
+                let mut pkt = MCPacketBuffer::new(20).await; // container_set_slot
+                let _ = pkt.write_varint(open_menu.window_id as u32).await;
+                let _ = pkt.write_varint(state_id).await;
+                let _ = pkt.write_be(slot as i16).await;
+                write_slot(&mut pkt, &stack).await;
+                self.queue_mc_packet(&pkt).await;
+                *remote = stack;
+            }
+        }
+    }
+
+    /// Reads an item stack sent by the client, whose data components are length-prefixed.
+    /// The lengths are kept, see [`ItemStack::components`].
+    async fn read_untrusted_item_stack(
+        &self,
+        rx: &mut RX,
+        packet_length: usize,
+    ) -> Result<ItemStack, MCClientError> {
+        let count = rx.read_varint::<i32>().await?;
+        if count <= 0 {
+            return Ok(ItemStack::EMPTY);
+        }
+        let item = rx.read_varint::<u32>().await?;
+        let added = rx.read_varint::<u32>().await?;
+        let removed = rx.read_varint::<u32>().await?;
+
+        let mut components = ByteBuf::default();
+        if added > 0 || removed > 0 {
+            components.write_varint(added).await?;
+            components.write_varint(removed).await?;
+            for _ in 0..added {
+                let component_type = rx.read_varint::<u32>().await?;
+                let length = rx.read_varint::<u32>().await? as usize;
+                if length > packet_length {
+                    return Err(MCClientError::ProtocolError(format!(
+                        "Item component length {length} exceeds packet length {packet_length}."
+                    )));
+                }
+                components.write_varint(component_type).await?;
+                components.write_varint(length as u32).await?;
+                let start = components.0.len();
+                components.0.resize(start + length, 0);
+                rx.read_exact(&mut components.0[start..])
+                    .await
+                    .map_err(EIOReadExactError::from)?;
+            }
+            for _ in 0..removed {
+                let component_type = rx.read_varint::<u32>().await?;
+                components.write_varint(component_type).await?;
+            }
+        }
+
+        Ok(ItemStack {
+            item: ItemId(item as u16),
+            count: count.min(99) as u8,
+            components: components.0,
+        })
+    }
+
+    /// Skips an item stack in the hashed form sent with `container_click`.
+    async fn skip_hashed_item_stack(&self, rx: &mut RX) -> Result<(), MCClientError> {
+        if rx.read_bool().await? {
+            let _item = rx.read_varint::<u32>().await?;
+            let _count = rx.read_varint::<u32>().await?;
+            let added = rx.read_varint::<u32>().await?;
+            for _ in 0..added {
+                let _component_type = rx.read_varint::<u32>().await?;
+                let _hash = rx.read_be::<i32>().await?;
+            }
+            let removed = rx.read_varint::<u32>().await?;
+            for _ in 0..removed {
+                let _component_type = rx.read_varint::<u32>().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the server's state of the block, undoing a prediction of the client that was rejected.
+    /// Blocks outside the loaded area are left alone, the client doesn't have them either.
+    async fn resync_block(&self, pos: BlockPos) {
+        if let Ok(blockstate) = self.server.world.get_block_state(pos).await {
+            let mut pkt = MCPacketBuffer::new(8).await; // block_update
+            let _ = pkt.write_block_pos(pos).await;
+            let _ = pkt.write_varint(blockstate.0 as u32).await;
+            self.queue_mc_packet(&pkt).await;
+        }
+    }
+
+    /// What the player may not do at the block because of protected regions.
+    async fn region_flags(&self, pos: BlockPos) -> RegionFlags {
+        let uuid = self.player_data().await.uuid;
+        self.server.region_flags_for(uuid, pos).await
+    }
+
+    /// Whether the player's permission level is enough to place and break blocks.
+    async fn has_build_permission(&self) -> bool {
+        self.permission_level().await >= self.server.build_permission_level()
+    }
+
+    /// Also declares the commands available at the level.
+    async fn send_permission_level(&self, entity_id: i32, level: u8) {
+        let mut pkt = MCPacketBuffer::new(30).await; // entity_event
+        let _ = pkt.write_be::<i32>(entity_id).await;
+        let _ = pkt.write_be(24 + level.min(4)).await; // set op permission level 0-4
+        self.queue_mc_packet(&pkt).await;
+        self.queue_mc_packet(&self.server.commands_packet(level).await).await;
+    }
+
+    async fn send_abilities(&self) {
+        let abilities = self.player_data().await.abilities;
+        let mut pkt = MCPacketBuffer::new(57).await; // player_abilities
+        let _ = pkt.write_be(abilities.flags()).await;
+        let _ = pkt.write_be(0.05f32).await; // flying speed
+        let _ = pkt.write_be(0.1f32).await; // field of view modifier
+        self.queue_mc_packet(&pkt).await;
+    }
+
+    /// Breaks the block as the player, failing that the client is corrected.
+    /// Outside of creative mode, the block drops as item if harvested with the right tool.
+    async fn destroy_block(&self, pos: BlockPos) {
+        self.stop_digging().await;
+        let world = self.server.world;
+        let _edits = world.lock_edits().await;
+        let Ok(blockstate) = world.get_block_state(pos).await else {
+            self.resync_block(pos).await;
+            return;
+        };
+        let drops = {
+            let player_data = self.player_data().await;
+            !player_data.abilities.instabuild && can_harvest(blockstate, player_data.held_item())
+        };
+        // the drop is added first, if the world is full of entities the block stays
+        if drops && let Some(item) = blockstate.as_item() {
+            let stack = ItemStack::new(item.id, 1);
+            if world.add_entity(Entity::dropped_item(world.allocate_entity_id(), pos, stack)).await.is_err() {
+                self.resync_block(pos).await;
+                return;
+            }
+        }
+        if world.set_block_state(pos, BlockState(0)).await.is_ok() {
+            let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+            blockstate.get_block().on_destroyed(world, pos, blockstate, drops).init(&mut c).await;
+        }
+    }
+
+    /// Drops the stack in front of the player, as thrown out of the inventory.
+    async fn throw_item(&self, stack: ItemStack) {
+        if stack.is_empty() {
+            return;
+        }
+        let (position, rotation) = {
+            let player_data = self.player_data().await;
+            (player_data.position, player_data.rotation)
+        };
+        let (origin, velocity) = throw_item_motion(position, rotation);
+        let world = self.server.world;
+        let entity = Entity::item(world.allocate_entity_id(), origin, velocity, stack, ItemEntity::THROWN_PICKUP_DELAY);
+        if let Err(entity) = world.add_entity(entity).await {
+            let EntityKind::Item(item) = entity.kind;
+            self.return_unthrown(item.stack).await;
+        }
+    }
+
+    /// Puts a stack that couldn't be thrown, as the world is full of entities, back into the inventory,
+    /// or the cursor if it doesn't fit. It was taken out of either, so it fits again, except for stacks
+    /// out of the creative inventory which aren't missed.
+    async fn return_unthrown(&self, mut stack: ItemStack) {
+        {
+            let mut player_data = self.player_data().await;
+            let player_data = &mut *player_data;
+            add_to_inventory(&mut player_data.inventory, player_data.selected_hotbar_slot, &mut stack);
+            if !stack.is_empty() && player_data.carried.is_empty() {
+                player_data.carried = stack;
+            }
+        }
+        self.send_container_content().await;
+    }
+
+    /// Throws one or all of the selected hotbar stack (Q or Ctrl+Q).
+    async fn throw_held_item(&self, whole_stack: bool) {
+        let thrown = {
+            let mut player_data = self.player_data().await;
+            if player_data.game_mode == GameMode::Spectator {
+                return;
+            }
+            let slot = 36 + player_data.selected_hotbar_slot as usize;
+            if player_data.inventory[slot].is_empty() {
+                return;
+            }
+            // the client already removed the items, they are put back by sending the inventory
+            if !player_data.drop_limiter.try_acquire() {
+                None
+            } else {
+                Some(player_data.inventory[slot].split(if whole_stack { u8::MAX } else { 1 }))
+            }
+        };
+        match thrown {
+            Some(thrown) => self.throw_item(thrown).await,
+            None => self.send_container_content().await,
+        }
+    }
+
+    /// Collects the item entities the player touches into the inventory.
+    async fn pick_up_items(&self) {
+        let (position, entity_id, game_mode) = {
+            let player_data = self.player_data().await;
+            (player_data.position, player_data.entity_id, player_data.game_mode)
+        };
+        if game_mode == GameMode::Spectator {
+            return;
+        }
+
+        let world = self.server.world;
+        let mut picked_up = false;
+        // the pickup range is less than a chunk
+        for entity in world.entities_near(block_pos_at(position).chunk_pos(), 1).await {
+            match &entity.kind {
+                EntityKind::Item(item) if item.pickup_delay == 0 && can_pick_up(position, entity.position) => {}
+                _ => continue,
+            }
+            // taken from the entity in place, so that no other player picks the same items up
+            // and the rest stays in the world
+            let picked = {
+                let mut player_data = self.player_data().await;
+                let player_data = &mut *player_data;
+                world
+                    .with_entity(entity.id, |entity| {
+                        let EntityKind::Item(item) = &mut entity.kind;
+                        let count = item.stack.count;
+                        add_to_inventory(&mut player_data.inventory, player_data.selected_hotbar_slot, &mut item.stack);
+                        (count - item.stack.count, item.stack.is_empty())
+                    })
+                    .await
+            };
+            let Some((taken, all_taken)) = picked else {
+                continue;
+            };
+            if all_taken {
+                world.remove_entity(entity.id).await;
+            }
+            if taken > 0 {
+                picked_up = true;
+                self.server.item_picked_up(entity.id, entity_id, taken).await;
+            }
+        }
+        if picked_up {
+            self.send_container_content().await;
+        }
+    }
+
+    async fn start_digging(&self, pos: BlockPos) {
+        let Ok(blockstate) = self.server.world.get_block_state(pos).await else {
+            self.resync_block(pos).await;
+            return;
+        };
+        let (abilities, per_tick, tick) = {
+            let player_data = self.player_data().await;
+            let per_tick = destroy_progress_per_tick(blockstate, player_data.held_item(), player_data.on_ground);
+            (player_data.abilities, per_tick, player_data.ticks)
+        };
+
+        if !abilities.may_build || blockstate.is_air() {
+            self.resync_block(pos).await;
+        } else if abilities.instabuild || per_tick >= 1.0 {
+            self.destroy_block(pos).await;
+        } else {
+            self.stop_digging().await;
+            self.player_data().await.digging = Some(Digging::new(pos, tick));
+        }
+    }
+
+    async fn abort_digging(&self, pos: BlockPos) {
+        let matches = {
+            let player_data = self.player_data().await;
+            player_data.digging.as_ref().is_some_and(|it| it.pos == pos && !it.delayed)
+        };
+        if matches {
+            self.stop_digging().await;
+        }
+    }
+
+    async fn finish_digging(&self, pos: BlockPos) {
+        let Ok(blockstate) = self.server.world.get_block_state(pos).await else {
+            self.resync_block(pos).await;
+            return;
+        };
+        let can_finish = {
+            let mut player_data = self.player_data().await;
+            let per_tick = destroy_progress_per_tick(blockstate, player_data.held_item(), player_data.on_ground);
+            let tick = player_data.ticks;
+            match &mut player_data.digging {
+                Some(digging) if digging.pos == pos => {
+                    let can_finish = digging.can_finish(per_tick, tick);
+                    // too early, possibly a slow connection, breaks once the server's timing catches up
+                    digging.delayed = !can_finish;
+                    Some(can_finish)
+                }
+                _ => None,
+            }
+        };
+        match can_finish {
+            Some(true) => self.destroy_block(pos).await,
+            Some(false) => {}
+            None => self.resync_block(pos).await,
+        }
+    }
+
+    /// Middle click on a block, `include_data` (with Ctrl) is ignored, block entity data isn't copied into the item.
+    async fn pick_block(&self, pos: BlockPos) {
+        let Some(item) = self.server.world.get_block_state(pos).await.ok().and_then(BlockState::as_item) else {
+            return;
+        };
+        let selected_hotbar_slot = {
+            let mut player_data = self.player_data().await;
+            let player_data = &mut *player_data;
+            if !can_reach(player_data.position, player_data.game_mode, pos) {
+                return;
+            }
+            let stack = ItemStack::new(item.id, 1);
+            let infinite_materials = player_data.abilities.instabuild;
+            if !pick_item(
+                &mut player_data.inventory,
+                &mut player_data.selected_hotbar_slot,
+                stack,
+                infinite_materials,
+            ) {
+                return;
+            }
+            player_data.selected_hotbar_slot
+        };
+
+        let mut pkt = MCPacketBuffer::new(98).await; // set_held_slot
+        let _ = pkt.write_varint(selected_hotbar_slot as u32).await;
+        self.queue_mc_packet(&pkt).await;
+        self.send_container_content().await;
+    }
+
+    /// Forgets the block being dug, if any, and clears its destruction stage for the other players.
+    async fn stop_digging(&self) {
+        let (digging, entity_id, uuid) = {
+            let mut player_data = self.player_data().await;
+            (player_data.digging.take(), player_data.entity_id, player_data.uuid)
+        };
+        if let Some(digging) = digging
+            && digging.stage >= 0
+        {
+            let pkt = block_destruction_packet(entity_id, digging.pos, -1).await;
+            self.server.send_mc_packet_to_others(&pkt, uuid).await;
+        }
+    }
+
+    async fn tick_digging(&self) {
+        let Some(pos) = self.player_data().await.digging.as_ref().map(|it| it.pos) else {
+            return;
+        };
+        let blockstate = match self.server.world.get_block_state(pos).await {
+            Ok(blockstate) if !blockstate.is_air() => blockstate,
+            // broken by something else meanwhile
+            _ => {
+                self.stop_digging().await;
+                return;
+            }
+        };
+
+        let (finished, stage_changed, entity_id, uuid) = {
+            let mut player_data = self.player_data().await;
+            let player_data = &mut *player_data;
+            let per_tick = destroy_progress_per_tick(blockstate, player_data.held_item(), player_data.on_ground);
+            let Some(digging) = &mut player_data.digging else {
+                return;
+            };
+            let finished = digging.delayed && digging.progress(per_tick, player_data.ticks) >= 1.0;
+            let stage = digging.stage_at(per_tick, player_data.ticks);
+            let stage_changed = stage != digging.stage;
+            digging.stage = stage;
+            (finished, stage_changed.then_some(stage), player_data.entity_id, player_data.uuid)
+        };
+
+        if finished {
+            self.destroy_block(pos).await;
+        } else if let Some(stage) = stage_changed {
+            let pkt = block_destruction_packet(entity_id, pos, stage).await;
+            self.server.send_mc_packet_to_others(&pkt, uuid).await;
+        }
+    }
+
     async fn send_chunk(&self, pos: ChunkPos) -> Result<(), MCClientError> {
-        let mut pkt = MCPacketBuffer::new(39).await; // level_chunk_with_light
+        let size = 4 + 4 + self.server.world.net_chunk_size(pos).await;
+        let mut pkt = MCPacketBuffer::with_capacity(39, size).await; // level_chunk_with_light
         pkt.write_be::<i32>(pos.x.to_i32().unwrap()).await?;
         pkt.write_be::<i32>(pos.y.to_i32().unwrap()).await?;
 
         self.server.world.write_net_chunk(pos, &mut pkt).await?;
 
-        self.write_mc_packet(&pkt).await?;
+        self.queue_mc_packet(&pkt).await;
         Ok(())
     }
 }
@@ -173,6 +902,10 @@ where
             server,
             rx: Mutex::new(rx),
             tx: Mutex::new(tx),
+            tx_batch: Mutex::new(MCPacketBatch::new()),
+            send_queue: Channel::new(),
+            send_queue_full_ticks: AtomicU32::new(0),
+            disconnect: Signal::new(),
             addr,
             player_data: None,
             _block_changes_to_ack: Mutex::new(SmallVec::new()),
@@ -247,12 +980,42 @@ where
                         let player_name = rx.read_utf8().await?;
                         let _given_player_uuid = rx.read_uuid().await?;
                         let player_uuid = Uuid::new_mc_offline_player(&player_name);
+                        if let Err(reason) = self.server.check_join(player_uuid, self.addr.map(|it| it.ip())).await {
+                            let mut pkt = MCPacketBuffer::new(0).await; // minecraft:login_disconnect
+                            pkt.write_json_text_component(&reason).await?;
+                            self.write_mc_packet(&pkt).await?;
+                            return Err(MCClientError::Disconnected(reason));
+                        }
+                        let spawn = self.server.spawn().await;
 
                         self.player_data = Some(Mutex::new(PlayerData {
                             uuid: player_uuid,
                             name: player_name,
+                            entity_id: self.server.allocate_entity_id(),
+                            position: spawn.position(),
+                            rotation: Vec2::new(spawn.yaw, 0.0),
+                            on_ground: false,
+                            sneaking: false,
+                            next_teleport_id: 0,
+                            awaiting_teleport: None,
+                            game_mode: GameMode::Creative,
+                            abilities: {
+                                let mut abilities = Abilities::default();
+                                GameMode::Creative.update_abilities(&mut abilities);
+                                abilities
+                            },
+                            permission_level: self.server.permission_level(player_uuid).await,
+                            ticks: 0,
+                            digging: None,
+                            interaction_limiter: RateLimiter::new(INTERACTION_BURST, INTERACTION_REFILL_PER_TICK),
+                            drop_limiter: RateLimiter::new(DROP_BURST, DROP_REFILL_PER_TICK),
                             selected_hotbar_slot: 0,
-                            inventory_items: [0; 46],
+                            inventory: [const { ItemStack::EMPTY }; INVENTORY_SLOTS],
+                            carried: ItemStack::EMPTY,
+                            menu: None,
+                            next_window_id: 0,
+                            container_state_id: 0,
+                            quick_craft: QuickCraft::default(),
                         }));
                     }
 
@@ -322,7 +1085,7 @@ where
         loop {
             let mut pkt = MCPacketBuffer::new(38).await;
             pkt.write_be(0u64).await?;
-            self.write_mc_packet(&pkt).await?;
+            self.queue_mc_packet(&pkt).await;
             ticker.next().await;
         }
     }
@@ -342,71 +1105,112 @@ where
                         rx.read_be().await?,
                         rx.read_be().await?,
                     );
-                    let _inside_block = rx.read_bool().await?;
+                    let inside_block = rx.read_bool().await?;
                     let _world_border_hit = rx.read_bool().await?;
                     let sequence = rx.read_varint::<i32>().await?;
 
-                    let opos = pos.offset_dir(face);
-                    let player_data = self.player_data().await;
-                    let slot = if hand == 0 {
-                        36 + player_data.selected_hotbar_slot
-                    } else {
-                        45
+                    // always acked, rejected predictions are corrected by resyncing the blocks
+                    self._block_changes_to_ack.lock().await.push(sequence);
+
+                    let allowed = {
+                        let mut player_data = self.player_data().await;
+                        player_data.interaction_limiter.try_acquire()
+                            && can_reach(player_data.position, player_data.game_mode, pos)
+                            && is_valid_hit(player_data.position, pos, face, cursor_pos, inside_block)
+                    };
+                    if !allowed {
+                        self.resync_block(pos).await;
+                        self.resync_block(pos.offset_dir(face)).await;
+                        continue;
+                    }
+
+                    let (ctx, skip_block) = {
+                        let player_data = self.player_data().await;
+                        let hand = if hand == 0 {
+                            InteractionHand::MainHand
+                        } else {
+                            InteractionHand::OffHand
+                        };
+                        let item = match hand {
+                            InteractionHand::MainHand => player_data.held_item(),
+                            InteractionHand::OffHand => &player_data.inventory[45],
+                        };
+                        // like vanilla, sneaking with anything in hand skips the block, e.g. to place against a lever
+                        let skip_block = player_data.sneaking
+                            && !(player_data.held_item().is_empty() && player_data.inventory[45].is_empty());
+                        let ctx = UseContext {
+                            item: item.clone(),
+                            hand,
+                            sneaking: player_data.sneaking,
+                            face,
+                            cursor_pos,
+                            rotation: player_data.rotation,
+                        };
+                        (ctx, skip_block)
                     };
-                    let item = player_data.inventory_items[slot as usize];
 
-                    if item == 0 {
-                        if let Ok(blockstate) = self.server.world.get_block_state(pos).await {
+                    // the block first, then the item
+                    if !skip_block {
+                        if self.region_flags(pos).await.no_interact {
+                            // the client may have predicted e.g. a door opening
+                            self.resync_block(pos).await;
+                        } else if let Ok(blockstate) = self.server.world.get_block_state(pos).await {
                             let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
-                            blockstate
+                            let _edits = self.server.world.lock_edits().await;
+                            let consumed = blockstate
                                 .get_block()
-                                .on_use_without_item(self.server.world, pos, blockstate)
+                                .on_use(self.server.world, pos, blockstate, &ctx)
                                 .init(&mut c)
                                 .await;
-                            self._block_changes_to_ack.lock().await.push(sequence);
+                            if consumed {
+                                continue;
+                            }
                         }
+                    }
+
+                    let item = ctx.item.item;
+                    if item.is_air() {
+                        continue;
+                    }
+                    if !self.player_data().await.abilities.may_build || !self.has_build_permission().await {
+                        self.resync_block(pos).await;
+                        self.resync_block(pos.offset_dir(face)).await;
                     } else {
-                        const ITEM_TO_BLOCK: [BlockState; 1416] = const {
-                            use const_for::const_for;
-                            let mut table = [BlockState(0); 1416];
-
-                            table[1] = BlockState(1); // stone
-                            table[28] = BlockState(10); // dirt
-                            table[688] = BlockState(3042 + 1160); // redstone
-                            table[689] = BlockState(5916); // redstone torch
-                            table[690] = BlockState(10032); // redstone block
-                            table[691] = BlockState(6063); // redstone repeater
-                            table[692] = BlockState(9985); // redstone comparator
-                            table[703] = BlockState(5802 + 9); // lever
-                            table[713] = BlockState(5926 + 9); // stone button
-                            table[715] = BlockState(9396 + 9); // wood button
-                            table[705] = BlockState(10000 + 16); // daylight detector
-                            table[711] = BlockState(8201 + 1); // redstone lamp
-                            table[1407] = BlockState(25768 + 3); // waxed copper bulb
-                            table[712] = BlockState(581 + 1); // note block
-                            table[697] = BlockState(13573 + 5); // observer
-                            table[90] = BlockState(2138); // iron block
-
-                            const_for!(i in 0u16..16 => { // wools
-                                table[(213 + i) as usize] = BlockState(2093 + i);
-                            });
-
-                            table
+                        // clicking a replaceable block places into it
+                        let placement_pos = match self.server.world.get_block_state(pos).await {
+                            Ok(clicked) if clicked.is_replaceable() => pos,
+                            _ => pos.offset_dir(face),
                         };
+                        let replaceable = self
+                            .server
+                            .world
+                            .get_block_state(placement_pos)
+                            .await
+                            .is_ok_and(BlockState::is_replaceable)
+                            && !self.region_flags(placement_pos).await.no_build;
 
-                        if let Some(&blockstate) = ITEM_TO_BLOCK.get(item as usize) {
-                            let block = blockstate.get_block();
-                            let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
-                            let blockstate = block
-                                .get_state_for_placement(self.server.world, opos, face, cursor_pos)
-                                .init(&mut c)
-                                .await;
+                        let mut placed = false;
+                        match item.get_item().and_then(|it| it.block) {
+                            Some(blockstate) if replaceable => {
+                                let block = blockstate.get_block();
+                                let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+                                let _edits = self.server.world.lock_edits().await;
+                                let blockstate = block
+                                    .get_state_for_placement(self.server.world, placement_pos, &ctx)
+                                    .init(&mut c)
+                                    .await;
 
-                            if let Ok(_) = self.server.world.set_block_state(opos, blockstate).await
-                            {
-                                self._block_changes_to_ack.lock().await.push(sequence);
-                                block.on_placed(self.server.world, opos, blockstate).init(&mut c).await;
+                                if let Ok(_) = self.server.world.set_block_state(placement_pos, blockstate).await {
+                                    placed = true;
+                                    block.on_placed(self.server.world, placement_pos, blockstate).init(&mut c).await;
+                                }
                             }
+                            _ => {}
+                        }
+
+                        if !placed {
+                            self.resync_block(pos).await;
+                            self.resync_block(pos.offset_dir(face)).await;
                         }
                     }
                 }
@@ -417,58 +1221,148 @@ where
                 }
                 55 => {
                     // set_creative_mode_slot
-                    let mut pkt_consumed = packet_type.varint_size();
                     let slot = rx.read_be::<i16>().await?;
-                    pkt_consumed += 2;
-                    let item_count = rx.read_varint::<u32>().await?;
-                    pkt_consumed += item_count.varint_size();
-                    let item_id = if item_count > 0 {
-                        let id = rx.read_varint::<i32>().await?;
-                        pkt_consumed += id.varint_size();
-                        max(0, id) as u16
-                    } else {
-                        0
+                    let stack = self.read_untrusted_item_stack(rx, packet_length).await?;
+                    let thrown = {
+                        let mut player_data = self.player_data().await;
+                        if player_data.game_mode != GameMode::Creative {
+                            continue;
+                        }
+                        match usize::try_from(slot).ok().filter(|it| *it < INVENTORY_SLOTS) {
+                            Some(slot) => {
+                                player_data.inventory[slot] = stack;
+                                continue;
+                            }
+                            // slot -1 is dropping out of the creative inventory, which is never short of items
+                            None if slot == -1 && player_data.drop_limiter.try_acquire() => stack,
+                            None => continue,
+                        }
+                    };
+                    self.throw_item(thrown).await;
+                }
+                17 => {
+                    // container_click
+                    let window_id = rx.read_varint::<u32>().await?;
+                    let _state_id = rx.read_varint::<i32>().await?;
+                    let slot = rx.read_be::<i16>().await?;
+                    let button = rx.read_be::<i8>().await?;
+                    let mode = rx.read_varint::<u32>().await?;
+                    // the client's prediction of the result, only hashed, the result is computed here instead
+                    let changed_slots = rx.read_varint::<u32>().await?;
+                    for _ in 0..changed_slots {
+                        let _slot = rx.read_be::<i16>().await?;
+                        self.skip_hashed_item_stack(rx).await?;
+                    }
+                    self.skip_hashed_item_stack(rx).await?; // carried
+
+                    let thrown = {
+                        let mut player_data = self.player_data().await;
+                        let player_data = &mut *player_data;
+                        if window_id == player_data.window_id() as u32 {
+                            let creative = player_data.game_mode == GameMode::Creative;
+                            MenuSlots::new(
+                                player_data.menu.as_ref().map(|it| &it.menu),
+                                &mut player_data.inventory,
+                                &mut player_data.carried,
+                            )
+                            .click(slot, button, mode, creative, &mut player_data.quick_craft)
+                        } else {
+                            ItemStack::EMPTY
+                        }
                     };
-                    rx.skip_bytes(packet_length - pkt_consumed).await?;
-                    self.player_data().await.inventory_items[slot as usize] = item_id;
-                    info!("slot: {} item: {}", slot, item_id);
+                    self.throw_item(thrown).await;
+                    // dragging over slots only changes anything once it ends
+                    if mode != 5 || button & 3 == 2 {
+                        self.send_container_content().await;
+                    }
+                }
+                18 => {
+                    // container_close
+                    let window_id = rx.read_varint::<u32>().await?;
+                    let rest = {
+                        let mut player_data = self.player_data().await;
+                        if window_id != player_data.window_id() as u32 {
+                            continue;
+                        }
+                        player_data.close_menu()
+                    };
+                    self.throw_item(rest).await;
+                    self.send_container_content().await;
                 }
                 4 => {
                     // change_game_mode
                     let mode = rx.read_varint::<u32>().await?;
-
-                    let mut pkt = MCPacketBuffer::new(34).await;
-                    pkt.write_be(3u8).await?; // change game mode
-                    pkt.write_be(mode as f32).await?;
-                    self.write_mc_packet(&pkt).await?;
+                    let Some(game_mode) = GameMode::from_id(mode) else {
+                        continue;
+                    };
+                    // the F3+F4 switcher, the same permission as /gamemode
+                    if self.permission_level().await < LEVEL_GAMEMASTERS {
+                        warn!("{} tried to change game mode without permission", self);
+                        continue;
+                    }
+                    self.set_game_mode(game_mode).await;
+                }
+                39 => {
+                    // player_abilities
+                    let flags = rx.read_be::<u8>().await?;
+                    let mut player_data = self.player_data().await;
+                    player_data.abilities.flying = flags & 0x02 != 0 && player_data.abilities.may_fly;
                 }
                 29 => {
                     // move_player_pos
-                    let (_x, _y, _z) = (
+                    let position = DVec3::new(
                         rx.read_be::<f64>().await?,
                         rx.read_be::<f64>().await?,
                         rx.read_be::<f64>().await?,
                     );
-                    let _flags = rx.read_be::<u8>().await?;
+                    let flags = rx.read_be::<u8>().await?;
+                    let mut player_data = self.player_data().await;
+                    if player_data.awaiting_teleport.is_none() {
+                        player_data.position = position;
+                        player_data.on_ground = flags & 0x01 != 0;
+                    }
                 }
                 30 => {
                     // move_player_pos_rot
-                    let (_x, _y, _z) = (
+                    let position = DVec3::new(
                         rx.read_be::<f64>().await?,
                         rx.read_be::<f64>().await?,
                         rx.read_be::<f64>().await?,
                     );
-                    let (_yaw, _pitch) = (rx.read_be::<f32>().await?, rx.read_be::<f32>().await?);
-                    let _flags = rx.read_be::<u8>().await?;
+                    let rotation = Vec2::new(rx.read_be::<f32>().await?, rx.read_be::<f32>().await?);
+                    let flags = rx.read_be::<u8>().await?;
+                    let mut player_data = self.player_data().await;
+                    if player_data.awaiting_teleport.is_none() {
+                        player_data.position = position;
+                        player_data.rotation = rotation;
+                        player_data.on_ground = flags & 0x01 != 0;
+                    }
                 }
                 31 => {
                     // move_player_rot
-                    let (_yaw, _pitch) = (rx.read_be::<f32>().await?, rx.read_be::<f32>().await?);
-                    let _flags = rx.read_be::<u8>().await?;
+                    let rotation = Vec2::new(rx.read_be::<f32>().await?, rx.read_be::<f32>().await?);
+                    let flags = rx.read_be::<u8>().await?;
+                    let mut player_data = self.player_data().await;
+                    if player_data.awaiting_teleport.is_none() {
+                        player_data.rotation = rotation;
+                        player_data.on_ground = flags & 0x01 != 0;
+                    }
                 }
                 32 => {
                     // move_player_status_only
-                    let _flags = rx.read_be::<u8>().await?;
+                    let flags = rx.read_be::<u8>().await?;
+                    let mut player_data = self.player_data().await;
+                    if player_data.awaiting_teleport.is_none() {
+                        player_data.on_ground = flags & 0x01 != 0;
+                    }
+                }
+                0 => {
+                    // accept_teleportation
+                    let teleport_id = rx.read_varint::<i32>().await?;
+                    let mut player_data = self.player_data().await;
+                    if player_data.awaiting_teleport == Some(teleport_id) {
+                        player_data.awaiting_teleport = None;
+                    }
                 }
                 12 => { // client_tick_end
                 }
@@ -477,8 +1371,9 @@ where
                     let _id = rx.read_be::<u64>().await?;
                 }
                 42 => {
-                    // player_input
-                    let _flags = rx.read_be::<u8>().await?;
+                    // player_input, since 1.21.2 sneaking is only sent here and no longer as player_command
+                    let flags = rx.read_be::<u8>().await?;
+                    self.player_data().await.sneaking = flags & 0x20 != 0;
                 }
                 40 => {
                     // player_action
@@ -486,20 +1381,42 @@ where
                     let pos = rx.read_block_pos().await?;
                     let _face = rx.read_indexed_enum::<Direction>().await?;
                     let sequence = rx.read_varint::<i32>().await?;
-                    match action {
-                        0 => {
-                            // started digging 
-                            if let Ok(blockstate) =
-                                self.server.world.set_block_state(pos, BlockState(0)).await
-                            {
-                                self._block_changes_to_ack.lock().await.push(sequence);
-                                let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
-                                blockstate.get_block().on_destroyed(self.server.world, pos, blockstate).init(&mut c).await;
-                            }
+                    if action <= 2 {
+                        // digging actions, always acked, rejected ones are corrected by resyncing the block
+                        self._block_changes_to_ack.lock().await.push(sequence);
+
+                        let allowed = {
+                            let mut player_data = self.player_data().await;
+                            // aborting is always fine, it doesn't change anything
+                            (action == 1 || player_data.interaction_limiter.try_acquire())
+                                && can_reach(player_data.position, player_data.game_mode, pos)
+                        } && (action == 1
+                            || (!self.region_flags(pos).await.no_break && self.has_build_permission().await));
+                        if !allowed {
+                            self.resync_block(pos).await;
+                            continue;
                         }
+                    }
+                    match action {
+                        0 => self.start_digging(pos).await,
+                        1 => self.abort_digging(pos).await,
+                        2 => self.finish_digging(pos).await,
+                        3 => self.throw_held_item(true).await,
+                        4 => self.throw_held_item(false).await,
                         _ => {}
                     }
                 }
+                35 => {
+                    // pick_item_from_block
+                    let pos = rx.read_block_pos().await?;
+                    let _include_data = rx.read_bool().await?;
+                    self.pick_block(pos).await;
+                }
+                6 => {
+                    // chat_command
+                    let command = rx.read_utf8().await?;
+                    self.server.execute_command(self, &command).await;
+                }
                 60 => { // swing
                     rx.skip_bytes(1).await?;
                 }
@@ -511,37 +1428,130 @@ where
         }
     }
 
+    /// Sends the queued batches until the client is disconnected, which ends with the disconnect packet.
+    /// A batch being written when disconnecting is finished first, so that the disconnect packet doesn't end up
+    /// in the middle of it. If that takes too long, e.g. as the client stopped reading, it's left out.
+    async fn play_send_packets(&self) -> Result<(), MCClientError> {
+        loop {
+            let batch = match select(self.send_queue.receive(), self.disconnect.wait()).await {
+                Either::First(batch) => batch,
+                Either::Second(reason) => {
+                    self.send_disconnect(&reason).await;
+                    return Err(MCClientError::Disconnected(reason));
+                }
+            };
+            let disconnected = {
+                let mut tx = self.tx.lock().await;
+                let mut write = pin!(tx.write_mc_packet_batch(&batch));
+                match select(&mut write, self.disconnect.wait()).await {
+                    Either::First(result) => {
+                        result?;
+                        None
+                    }
+                    Either::Second(reason) => {
+                        let finished = matches!(with_timeout(DISCONNECT_WRITE_TIMEOUT, write).await, Ok(Ok(())));
+                        Some((reason, finished))
+                    }
+                }
+            };
+            if let Some((reason, finished)) = disconnected {
+                if finished {
+                    self.send_disconnect(&reason).await;
+                }
+                return Err(MCClientError::Disconnected(reason));
+            }
+        }
+    }
+
+    /// Sends what a player sees when joining, after it is registered with the server.
+    async fn join(&self) -> Result<(), MCClientError> {
+        let (entity_id, permission_level) = {
+            let player_data = self.player_data().await;
+            (player_data.entity_id, player_data.permission_level)
+        };
+
+        self.send_permission_level(entity_id, permission_level).await;
+
+        self.send_abilities().await;
+
+        self.send_container_content().await;
+
+        let mut pkt = MCPacketBuffer::new(34).await; // minecraft:game_event
+        pkt.write_be::<u8>(13).await?; // Start waiting for level chunks
+        pkt.write_be::<f32>(0.0).await?;
+        self.queue_mc_packet(&pkt).await;
+
+        let mut pkt = MCPacketBuffer::new(87).await; // set_chunk_cache_center
+        pkt.write_varint(0u32).await?;
+        pkt.write_varint(0u32).await?;
+        self.queue_mc_packet(&pkt).await;
+
+        // for cx in -2i16..=2 {
+        //     for cz in -2i16..=2 {
+        //
+        //     }
+        // }
+
+        for cx in -2i16..=2 {
+            for cz in -2i16..=2 {
+                self.send_chunk(ChunkPos::new(cx, cz)).await?;
+            }
+        }
+
+        let spawn = self.server.spawn().await;
+        self.queue_mc_packet(&spawn.set_default_spawn_position_packet().await).await;
+
+        let (position, rotation) = {
+            let player_data = self.player_data().await;
+            (player_data.position, player_data.rotation)
+        };
+        self.teleport(position, rotation).await;
+        self.submit_mc_packets().await;
+        Ok(())
+    }
+
+    /// Best effort, the connection is closed either way.
+    /// Must not be written while another packet is, see `play_send_packets`.
+    async fn send_disconnect(&self, reason: &str) {
+        let mut pkt = MCPacketBuffer::new(28).await; // disconnect
+        let _ = pkt.write_text_component(reason).await;
+        let _ = self.write_mc_packet(&pkt).await;
+    }
+
     async fn play(&self) -> Result<(), MCClientError> {
-        let result =
-            embassy_futures::select::select(self.play_handle_packets(), self.play_keep_alive())
-                .await;
+        // disconnecting is up to the sending half, see `play_send_packets`
+        let result = select3(self.play_handle_packets(), self.play_keep_alive(), self.play_send_packets()).await;
 
         match result {
-            Either::First(it) => it,
-            Either::Second(it) => it,
+            Either3::First(it) => it,
+            Either3::Second(it) => it,
+            Either3::Third(it) => it,
         }
     }
 
-    pub async fn run(mut self) -> Result<(), MCClientError> {
+    pub async fn run(mut self) -> Result<(), MCClientError>
+    where
+        M: 'a,
+        RX: 'a,
+        TX: 'a,
+    {
         match self.handle_handshake().await? {
             ClientIntent::Status => self.handle_status_intent().await?,
             ClientIntent::Login => {
                 self.handle_login().await?;
                 self.handle_configure().await?;
 
-                // self.server.add_player(Box::new(self)).await;
-                unsafe {
-                    self.server
-                        .add_player(unsafe { &*(&self as *const Self) })
-                        .await
+                let (entity_id, game_mode) = {
+                    let player_data = self.player_data().await;
+                    (player_data.entity_id, player_data.game_mode)
                 };
 
                 let mut pkt = MCPacketBuffer::new(43).await; // minecraft:login
-                pkt.write_be::<u32>(0).await?; // entity id
+                pkt.write_be::<i32>(entity_id).await?; // entity id
                 pkt.write_be(false as u8).await?; // is hardcore
                 pkt.write_varint(1u32).await?; // dimension names
                 pkt.write_utf8("minecraft:overworld").await?;
-                pkt.write_varint(3u32).await?; // max players
+                pkt.write_varint(self.server.max_players()).await?; // max players
                 pkt.write_varint(32u32).await?; // view distance
                 pkt.write_varint(32u32).await?; // sim distance
                 pkt.write_be(false as u8).await?; // reduced debug info
@@ -550,7 +1560,7 @@ where
                 pkt.write_varint(0u32).await?; // dimension id
                 pkt.write_utf8("minecraft:overworld").await?; // dimension name
                 pkt.write_be(0u64).await?; // world seed hash
-                pkt.write_be(1u8).await?; // game mode (0: Survival, 1: Creative, 2: Adventure, 3: Spectator)
+                pkt.write_be(game_mode.id()).await?; // game mode (0: Survival, 1: Creative, 2: Adventure, 3: Spectator)
                 pkt.write_be(-1i8).await?; // prev game mode
                 pkt.write_be(false as u8).await?; // is debug mode world
                 pkt.write_be(false as u8).await?; // is flat world
@@ -558,51 +1568,27 @@ where
                 pkt.write_varint(0u32).await?; // portal cooldown
                 pkt.write_varint(68u32).await?; // sea level
                 pkt.write_be(false as u8).await?; // enforce secure chat
-                self.write_mc_packet(&pkt).await?;
-
-                let mut pkt = MCPacketBuffer::new(30).await; // entity_event
-                pkt.write_be(0u32).await?;
-                pkt.write_be(28u8).await?; //	Set op permission level to 4
-                self.write_mc_packet(&pkt).await?;
-
-                let mut pkt = MCPacketBuffer::new(34).await; // minecraft:game_event
-                pkt.write_be::<u8>(13).await?; // Start waiting for level chunks
-                pkt.write_be::<f32>(0.0).await?;
-                self.write_mc_packet(&pkt).await?;
-
-                let mut pkt = MCPacketBuffer::new(87).await; // set_chunk_cache_center
-                pkt.write_varint(0u32).await?;
-                pkt.write_varint(0u32).await?;
-                self.write_mc_packet(&pkt).await?;
-
-                // for cx in -2i16..=2 {
-                //     for cz in -2i16..=2 {
-                //
-                //     }
-                // }
-
-                for cx in -2i16..=2 {
-                    for cz in -2i16..=2 {
-                        self.send_chunk(ChunkPos::new(cx, cz)).await?;
+                self.queue_mc_packet(&pkt).await;
+
+                let client = Arc::new(self);
+                // registered only now, so that world updates are never queued before the login packet
+                let session = match client.server.add_player(client.clone()).await {
+                    Ok(session) => session,
+                    Err(reason) => {
+                        // the queued login packet isn't needed anymore
+                        client.send_disconnect(&reason).await;
+                        return Err(MCClientError::Disconnected(reason));
                     }
+                };
+                let result = async {
+                    client.join().await?;
+                    client.play().await
                 }
+                .await;
 
-                let mut pkt = MCPacketBuffer::new(65).await; // minecraft:player_position
-                pkt.write_varint::<u32>(0).await?;
-                pkt.write_be::<f64>(0.0).await?;
-                pkt.write_be::<f64>(10.0).await?;
-                pkt.write_be::<f64>(0.0).await?;
-                pkt.write_be::<f64>(0.0).await?;
-                pkt.write_be::<f64>(0.0).await?;
-                pkt.write_be::<f64>(0.0).await?;
-                pkt.write_be::<f32>(0.0).await?;
-                pkt.write_be::<f32>(0.0).await?;
-                pkt.write_be::<u32>(0).await?;
-                self.write_mc_packet(&pkt).await?;
-
-                self.play().await?;
-
-                self.server.remove_player(self.uuid().await).await;
+                // on any exit, so that the other players don't keep seeing a ghost; the guard covers a cancelled task
+                session.remove().await;
+                result?;
             }
         };
         Ok(())
@@ -634,6 +1620,13 @@ where
     }
 }
 
+/// Writes the stack into a slot field, as empty if its components are malformed.
+async fn write_slot(pkt: &mut MCPacketBuffer, stack: &ItemStack) {
+    if stack.write_into(pkt).await.is_err() {
+        let _ = ItemStack::EMPTY.write_into(pkt).await;
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[maybe_derive_format]
 enum ClientIntent {
@@ -650,6 +1643,8 @@ pub enum MCClientError {
     /// Packet data format error (e.g. varint too big)
     DataError(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] Box<dyn Error>),
     NetworkError(#[cfg_attr(feature = "defmt", defmt(Debug2Format))] Box<dyn Error>),
+    /// Disconnected by the server (e.g. the client couldn't keep up with outgoing packets)
+    Disconnected(String),
 }
 
 impl<IOE: embedded_io_async::Error + 'static> From<EIOError<IOE>> for MCClientError {
@@ -662,6 +1657,7 @@ impl Error for MCClientError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             Self::ProtocolError(_) => None,
+            Self::Disconnected(_) => None,
             Self::DataError(err) => Some(err.as_ref()),
             Self::NetworkError(err) => Some(err.as_ref()),
         }
@@ -1,55 +1,498 @@
 use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 use core::mem::MaybeUninit;
+use core::net::IpAddr;
+use core::sync::atomic::{AtomicU32, AtomicU8, Ordering};
 use dynify::Dynify;
+use glam::{DVec3, I64Vec3};
+use num_traits::float::FloatCore;
 use smallvec::SmallVec;
 use uuid::Uuid;
+use embassy_sync::blocking_mutex;
 use embassy_sync::blocking_mutex::raw::RawMutex;
 use embassy_sync::mutex::Mutex;
 use tileglobe::world::block::BlockState;
+use tileglobe::world::entity::block_pos_at;
+use tileglobe::world::region::{Region, RegionFlags};
 use tileglobe::world::world::World;
 use tileglobe_utils::direction::Direction;
+use tileglobe_utils::network::{
+    MCPacketBuffer, WriteBlockPos, WriteNumPrimitive, WriteUTF8, WriteUUID, WriteVarInt,
+};
 use tileglobe_utils::pos::BlockPos;
-use crate::player::DynifiedPlayer;
+use crate::commands::{builtin_commands, commands_packet, CommandEntry};
+use crate::entity_tracker::{take_item_entity_packet, EntityTracker, TRACKING_CHUNK_RADIUS};
+use crate::player_lists::{
+    ban_message, decode_regions, encode_regions, BanEntry, DynifiedPlayerListStorage, OpEntry, PlayerListConfig,
+    PlayerListKind, PlayerLists,
+};
+use crate::utils::MCPlayerUUID;
+use crate::player::{DynifiedMCPacketSink, DynifiedPlayer, DynifiedPlayerListener, MCPacketSink, Player};
+
+const PLAYER_ENTITY_TYPE: u32 = 149;
+const DEFAULT_MAX_PLAYERS: u32 = 3;
+
+/// Where players join the world.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpawnPoint {
+    pub pos: BlockPos,
+    pub yaw: f32,
+}
+
+impl SpawnPoint {
+    /// Bottom center of the spawn block.
+    pub fn position(&self) -> DVec3 {
+        DVec3::new(self.pos.x as f64 + 0.5, self.pos.y as f64, self.pos.z as f64 + 0.5)
+    }
+
+    pub async fn set_default_spawn_position_packet(&self) -> MCPacketBuffer {
+        let mut pkt = MCPacketBuffer::new(90).await; // set_default_spawn_position
+        let _ = pkt.write_block_pos(self.pos).await;
+        let _ = pkt.write_be(self.yaw).await;
+        pkt
+    }
+}
+
+impl Default for SpawnPoint {
+    fn default() -> Self {
+        Self {
+            pos: BlockPos::new(0, 10, 0),
+            yaw: 0.0,
+        }
+    }
+}
 
 pub struct MCServer<'a, M: RawMutex, WORLD: World> {
     pub world: &'a WORLD,
-    players: Mutex<M, BTreeMap<Uuid, &'a dyn DynifiedPlayer>>,
-    // players: Mutex<M, BTreeMap<Uuid, Arc<dyn DynifiedPlayer>>>,
+    players: Mutex<M, BTreeMap<Uuid, TrackedPlayer<'a>>>,
+    listeners: Mutex<M, Vec<&'a dyn DynifiedPlayerListener>>,
+    spawn: Mutex<M, SpawnPoint>,
+    max_players: AtomicU32,
+    /// Permission level needed to place and break blocks, 0 lets everyone build.
+    build_permission_level: AtomicU8,
+    player_lists: Mutex<M, PlayerLists>,
+    player_list_storage: Mutex<M, Option<&'a dyn DynifiedPlayerListStorage>>,
+    commands: Mutex<M, Vec<CommandEntry<'a, M, WORLD>>>,
+    /// Reused every tick, so that sending the block changes doesn't allocate.
+    block_update_packets: Mutex<M, Vec<MCPacketBuffer>>,
+    /// Sessions whose guard was dropped without [`PlayerSession::remove`], removed on the next tick.
+    left_players: blocking_mutex::Mutex<M, RefCell<Vec<(Uuid, i32)>>>,
 }
 impl<'a, M: RawMutex, WORLD: World> MCServer<'a, M, WORLD> {
     pub fn new(world: &'a WORLD) -> Self {
         Self {
             world,
-            // players: Mutex::new(BTreeMap::new()),
             players: Mutex::new(BTreeMap::new()),
+            listeners: Mutex::new(Vec::new()),
+            spawn: Mutex::new(SpawnPoint::default()),
+            max_players: AtomicU32::new(DEFAULT_MAX_PLAYERS),
+            build_permission_level: AtomicU8::new(0),
+            player_lists: Mutex::new(PlayerLists::default()),
+            player_list_storage: Mutex::new(None),
+            commands: Mutex::new(builtin_commands().into()),
+            block_update_packets: Mutex::new(Vec::new()),
+            left_players: blocking_mutex::Mutex::new(RefCell::new(Vec::new())),
         }
     }
 
-    pub async unsafe fn add_player(&self, player: &'a impl DynifiedPlayer) {
+    /// Adds the command, replacing one of the same name.
+    /// Players online already only see it after their permission level changes or they rejoin.
+    pub async fn register_command(&self, entry: CommandEntry<'a, M, WORLD>) {
+        let mut commands = self.commands.lock().await;
+        commands.retain(|it| it.name != entry.name);
+        commands.push(entry);
+    }
+
+    /// Declares the commands available at the permission level.
+    pub(crate) async fn commands_packet(&self, permission_level: u8) -> MCPacketBuffer {
+        let available = self
+            .commands
+            .lock()
+            .await
+            .iter()
+            .filter(|it| it.permission_level <= permission_level)
+            .cloned()
+            .collect::<Vec<_>>();
+        commands_packet(&available).await
+    }
+
+    /// Runs a command line sent by the player, without the slash.
+    pub async fn execute_command(&self, sender: &dyn DynifiedPlayer, command_line: &str) {
         let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
-        self.players.lock().await.insert(player.uuid().init(&mut c).await, player);
+        let (name, args) = command_line.split_once(' ').unwrap_or((command_line, ""));
+        let entry = self.commands.lock().await.iter().find(|it| it.name == name).cloned();
+        let permission_level = sender.permission_level().init(&mut c).await;
+
+        let result = match entry {
+            // like vanilla, commands above the player's level don't exist for it
+            Some(entry) if entry.permission_level <= permission_level => {
+                entry.command.execute(self, sender, args).init(&mut c).await
+            }
+            _ => Err(format!("Unknown or incomplete command: {}", name)),
+        };
+        if let Err(message) = result {
+            sender.send_message(&message).init(&mut c).await;
+        }
     }
 
-    pub async fn remove_player(&self, uuid: Uuid) {
-        self.players.lock().await.remove(&uuid);
+    pub fn max_players(&self) -> u32 {
+        self.max_players.load(Ordering::Relaxed)
     }
 
-    // pub async fn add_player<'s, T: DynifiedPlayer + 'static>(
-    //     &'s self,
-    //     player: T,
-    // ) -> Result<&'s dyn DynifiedPlayer, ()> {
-    //     let uuid = player.uuid();
-    //     let mut players = self.players.lock().await;
-    //     if players.contains_key(&uuid) {
-    //         return Err(());
-    //     }
-    //     players.insert(uuid, Box::new(player));
-    //     players.map()
-    // }
+    /// Applies to joining players, players already online over the new limit stay.
+    pub fn set_max_players(&self, max_players: u32) {
+        self.max_players.store(max_players, Ordering::Relaxed);
+    }
 
-    // pub async fn remove_player(&self, uuid: Uuid) -> Option<Box<dyn DynifiedPlayer>> {
-    //     self.players.lock().await.remove(&uuid)
-    // }
+    pub fn build_permission_level(&self) -> u8 {
+        self.build_permission_level.load(Ordering::Relaxed)
+    }
+
+    /// Players below the permission level (0 to 4) may not place or break blocks, like in adventure mode.
+    pub fn set_build_permission_level(&self, level: u8) {
+        self.build_permission_level.store(level.min(4), Ordering::Relaxed);
+    }
+
+    pub async fn player_count(&self) -> usize {
+        self.players.lock().await.len()
+    }
+
+    pub async fn spawn(&self) -> SpawnPoint {
+        *self.spawn.lock().await
+    }
+
+    /// Moves the world spawn, for players joining from now on and for the compasses of the players online.
+    pub async fn set_spawn(&self, spawn: SpawnPoint) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        *self.spawn.lock().await = spawn;
+        let pkt = spawn.set_default_spawn_position_packet().await;
+        for tracked in self.players.lock().await.values() {
+            tracked.sink.send_mc_packet(&pkt).init(&mut c).await;
+        }
+    }
+
+    /// Players share the ids of the world's entities.
+    pub fn allocate_entity_id(&self) -> i32 {
+        self.world.allocate_entity_id()
+    }
+
+    pub async fn add_listener(&self, listener: &'a dyn DynifiedPlayerListener) {
+        self.listeners.lock().await.push(listener);
+    }
+
+    /// Whether the player may join, checked at login, the reason it may not otherwise.
+    pub(crate) async fn check_join(&self, uuid: Uuid, ip: Option<IpAddr>) -> Result<(), String> {
+        self.player_lists.lock().await.check_login(uuid, ip)?;
+        self.check_capacity(&*self.players.lock().await, uuid)
+    }
+
+    /// Loads the player lists and the world's protected regions from the storage, and saves them there from now on.
+    pub async fn load_player_lists(&self, storage: &'a dyn DynifiedPlayerListStorage) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        for list in PlayerListKind::ALL {
+            let Some(data) = storage.load(list).init(&mut c).await else {
+                continue;
+            };
+            if list == PlayerListKind::Regions {
+                for region in decode_regions(&data) {
+                    self.world.set_region(region).await;
+                }
+            } else {
+                self.player_lists.lock().await.decode(list, &data);
+            }
+        }
+        *self.player_list_storage.lock().await = Some(storage);
+    }
+
+    /// Applies the settings on top of the lists, after [`Self::load_player_lists`] so that they're kept.
+    pub async fn apply_player_list_config(&self, config: PlayerListConfig) {
+        if let Some(owner) = config.owner {
+            self.op(owner, 4).await;
+        }
+        if let Some(enabled) = config.whitelist_enabled {
+            self.set_whitelist_enabled(enabled).await;
+        }
+    }
+
+    async fn save_player_list(&self, list: PlayerListKind) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let Some(storage) = *self.player_list_storage.lock().await else {
+            return;
+        };
+        let data = if list == PlayerListKind::Regions {
+            encode_regions(&self.world.regions().await)
+        } else {
+            self.player_lists.lock().await.encode(list)
+        };
+        storage.save(list, &data).init(&mut c).await;
+    }
+
+    /// Only applies to joining players, like vanilla without `enforce-whitelist`.
+    pub async fn set_whitelist_enabled(&self, enabled: bool) {
+        self.player_lists.lock().await.whitelist_enabled = enabled;
+        self.save_player_list(PlayerListKind::WhitelistEnabled).await;
+    }
+
+    pub async fn whitelist_add(&self, name: &str) {
+        let uuid = Uuid::new_mc_offline_player(name);
+        self.player_lists.lock().await.whitelist.insert(uuid, String::from(name));
+        self.save_player_list(PlayerListKind::Whitelist).await;
+    }
+
+    pub async fn whitelist_remove(&self, name: &str) {
+        let uuid = Uuid::new_mc_offline_player(name);
+        self.player_lists.lock().await.whitelist.remove(&uuid);
+        self.save_player_list(PlayerListKind::Whitelist).await;
+    }
+
+    /// Bans the player by name, kicking it if online.
+    pub async fn ban(&self, name: &str, reason: &str) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let uuid = Uuid::new_mc_offline_player(name);
+        // one entry per line in the storage
+        let reason = reason.replace('\n', " ");
+        let ban = BanEntry {
+            name: String::from(name),
+            reason,
+        };
+        let message = ban_message(&ban.reason);
+        self.player_lists.lock().await.banned_players.insert(uuid, ban);
+        self.save_player_list(PlayerListKind::BannedPlayers).await;
+
+        if let Some(player) = self.player_by_uuid(uuid).await {
+            player.kick(&message).init(&mut c).await;
+        }
+    }
+
+    pub async fn pardon(&self, name: &str) {
+        let uuid = Uuid::new_mc_offline_player(name);
+        self.player_lists.lock().await.banned_players.remove(&uuid);
+        self.save_player_list(PlayerListKind::BannedPlayers).await;
+    }
+
+    /// Bans the address, kicking the players online from it.
+    pub async fn ban_ip(&self, ip: IpAddr, reason: &str) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let reason = reason.replace('\n', " ");
+        self.player_lists.lock().await.banned_ips.insert(ip, reason);
+        self.save_player_list(PlayerListKind::BannedIps).await;
+
+        for player in self.players().await {
+            if player.address().init(&mut c).await.is_some_and(|it| it.ip() == ip) {
+                player.kick("Your IP address is banned from this server.").init(&mut c).await;
+            }
+        }
+    }
+
+    pub async fn pardon_ip(&self, ip: IpAddr) {
+        self.player_lists.lock().await.banned_ips.remove(&ip);
+        self.save_player_list(PlayerListKind::BannedIps).await;
+    }
+
+    /// Operator permission level of the player by UUID, 0 if not an operator.
+    pub async fn permission_level(&self, uuid: Uuid) -> u8 {
+        self.player_lists.lock().await.permission_level(uuid)
+    }
+
+    /// Makes the player an operator with the level (1 to 4), also if online already.
+    pub async fn op(&self, name: &str, level: u8) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let uuid = Uuid::new_mc_offline_player(name);
+        let level = level.clamp(1, 4);
+        let op = OpEntry {
+            name: String::from(name),
+            level,
+        };
+        self.player_lists.lock().await.ops.insert(uuid, op);
+        self.save_player_list(PlayerListKind::Ops).await;
+
+        if let Some(player) = self.player_by_uuid(uuid).await {
+            player.set_permission_level(level).init(&mut c).await;
+        }
+    }
+
+    pub async fn deop(&self, name: &str) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let uuid = Uuid::new_mc_offline_player(name);
+        self.player_lists.lock().await.ops.remove(&uuid);
+        self.save_player_list(PlayerListKind::Ops).await;
+
+        if let Some(player) = self.player_by_uuid(uuid).await {
+            player.set_permission_level(0).init(&mut c).await;
+        }
+    }
+
+    pub async fn bypasses_regions(&self, uuid: Uuid) -> bool {
+        self.player_lists.lock().await.region_bypass.contains(&uuid)
+    }
+
+    pub async fn set_region_bypass(&self, uuid: Uuid, bypass: bool) {
+        let mut player_lists = self.player_lists.lock().await;
+        if bypass {
+            player_lists.region_bypass.insert(uuid);
+        } else {
+            player_lists.region_bypass.remove(&uuid);
+        }
+        drop(player_lists);
+        self.save_player_list(PlayerListKind::RegionBypass).await;
+    }
+
+    /// Adds the protected region to the world, replacing the one of the same name.
+    pub async fn set_region(&self, region: Region) {
+        self.world.set_region(region).await;
+        self.save_player_list(PlayerListKind::Regions).await;
+    }
+
+    /// `false` if there is no region of that name.
+    pub async fn remove_region(&self, name: &str) -> bool {
+        let removed = self.world.remove_region(name).await;
+        if removed {
+            self.save_player_list(PlayerListKind::Regions).await;
+        }
+        removed
+    }
+
+    /// What the player may not do at the block, nothing if they bypass the protected regions.
+    pub async fn region_flags_for(&self, uuid: Uuid, pos: BlockPos) -> RegionFlags {
+        if self.bypasses_regions(uuid).await {
+            return RegionFlags::default();
+        }
+        self.world.region_flags_at(pos).await
+    }
+
+    fn check_capacity(&self, players: &BTreeMap<Uuid, TrackedPlayer<'a>>, uuid: Uuid) -> Result<(), String> {
+        // a player logging in again replaces its old session
+        let others = players.len() - players.contains_key(&uuid) as usize;
+        if others >= self.max_players() as usize {
+            return Err(String::from("The server is full"));
+        }
+        Ok(())
+    }
+
+    /// Registers the player and makes it and the players already online visible to each other.
+    /// An older session of the same player is kicked and replaced.
+    /// The session is removed again when the returned guard is dropped.
+    pub(crate) async fn add_player<P: Player + MCPacketSink + 'a>(
+        &self,
+        player: Arc<P>,
+    ) -> Result<PlayerSession<'_, 'a, M, WORLD>, String> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let tracked = TrackedPlayer::new(player.clone(), player.clone()).await;
+        let (uuid, entity_id) = (tracked.uuid, tracked.entity_id);
+        let replaced = {
+            let mut players = self.players.lock().await;
+            // checked again, others might have joined since the login
+            self.check_capacity(&players, tracked.uuid)?;
+
+            let replaced = players.remove(&tracked.uuid);
+            if let Some(replaced) = &replaced {
+                replaced.player.kick("You logged in from another location").init(&mut c).await;
+                let remove_pkts = replaced.remove_packets().await;
+                for other in players.values() {
+                    for pkt in &remove_pkts {
+                        other.sink.send_mc_packet(pkt).init(&mut c).await;
+                    }
+                }
+            }
+
+            let mut online = players.values().map(|it| &*it.player).collect::<Vec<_>>();
+            online.push(&*tracked.player);
+            tracked.sink.send_mc_packet(&player_info_update_packet(&online).await).init(&mut c).await;
+            for other in players.values() {
+                tracked.sink.send_mc_packet(&other.add_entity_packet().await).init(&mut c).await;
+            }
+
+            let info_pkt = player_info_update_packet(&[&*tracked.player]).await;
+            let add_entity_pkt = tracked.add_entity_packet().await;
+            for other in players.values() {
+                other.sink.send_mc_packet(&info_pkt).init(&mut c).await;
+                other.sink.send_mc_packet(&add_entity_pkt).init(&mut c).await;
+            }
+
+            players.insert(tracked.uuid, tracked);
+            replaced
+        };
+        let session = PlayerSession {
+            server: self,
+            uuid,
+            entity_id,
+        };
+
+        // outside of the lock, listeners may look up or message players
+        let listeners = self.listeners.lock().await.clone();
+        for listener in listeners {
+            if let Some(replaced) = &replaced {
+                listener.on_player_leave(&*replaced.player).init(&mut c).await;
+            }
+            listener.on_player_join(&*player).init(&mut c).await;
+        }
+        Ok(session)
+    }
+
+    /// Removes the session, identified by its entity id, unless it was replaced by a newer one already.
+    async fn remove_player(&self, uuid: Uuid, entity_id: i32) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let removed = {
+            let mut players = self.players.lock().await;
+            if players.get(&uuid).is_none_or(|it| it.entity_id != entity_id) {
+                return;
+            }
+            players.remove(&uuid).unwrap()
+        };
+        self.send_mc_packets_to_all(&removed.remove_packets().await).await;
+
+        let listeners = self.listeners.lock().await.clone();
+        for listener in listeners {
+            listener.on_player_leave(&*removed.player).init(&mut c).await;
+        }
+    }
+
+    async fn send_mc_packets_to_all(&self, pkts: &[MCPacketBuffer]) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        for tracked in self.players.lock().await.values() {
+            for pkt in pkts {
+                tracked.sink.send_mc_packet(pkt).init(&mut c).await;
+            }
+        }
+    }
+
+    pub async fn players(&self) -> Vec<Arc<dyn DynifiedPlayer + 'a>> {
+        self.players.lock().await.values().map(|it| it.player.clone()).collect()
+    }
+
+    pub async fn player_by_uuid(&self, uuid: Uuid) -> Option<Arc<dyn DynifiedPlayer + 'a>> {
+        self.players.lock().await.get(&uuid).map(|it| it.player.clone())
+    }
+
+    /// Names are matched ignoring case, like in vanilla commands.
+    pub async fn player_by_name(&self, name: &str) -> Option<Arc<dyn DynifiedPlayer + 'a>> {
+        self.players
+            .lock()
+            .await
+            .values()
+            .find(|it| it.name.eq_ignore_ascii_case(name))
+            .map(|it| it.player.clone())
+    }
+
+    /// Sends the packet to every player except the given one, e.g. the one causing it.
+    pub(crate) async fn send_mc_packet_to_others(&self, pkt: &MCPacketBuffer, except: Uuid) {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        for tracked in self.players.lock().await.values() {
+            if tracked.uuid != except {
+                tracked.sink.send_mc_packet(pkt).init(&mut c).await;
+            }
+        }
+    }
+
+    /// Shows the item entity flying to the collecting player, for everyone.
+    /// The entity itself is removed by the caller, from the world.
+    pub(crate) async fn item_picked_up(&self, item_id: i32, collector_id: i32, count: u8) {
+        self.send_mc_packets_to_all(&[take_item_entity_packet(item_id, collector_id, count).await]).await;
+    }
 
     pub async fn player_use_item_on() {}
 
@@ -62,15 +505,247 @@ impl<'a, M: RawMutex, WORLD: World> MCServer<'a, M, WORLD> {
     pub async fn tick(&self) {
         let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
 
-        let block_update_packets = self.world.gen_blocks_update_packets_and_clear_changes().await;
-        for player in self.players.lock().await.values() {
-            for pkt in &block_update_packets {
-                let _ = player.send_mc_packet(pkt).init(&mut c).await;
+        let left_players = self.left_players.lock(|it| core::mem::take(&mut *it.borrow_mut()));
+        for (uuid, entity_id) in left_players {
+            self.remove_player(uuid, entity_id).await;
+        }
+
+        // players only queue packets here, network I/O happens in their own tasks
+        let mut movement_packets = SmallVec::<[(Uuid, SmallVec<[MCPacketBuffer; 2]>); 8]>::new();
+        let mut entity_packets = SmallVec::<[(Uuid, Vec<MCPacketBuffer>); 8]>::new();
+        let players = {
+            let mut players = self.players.lock().await;
+            for tracked in players.values_mut() {
+                let packets = tracked.gen_movement_packets().await;
+                if !packets.is_empty() {
+                    movement_packets.push((tracked.uuid, packets));
+                }
+                let viewer = tracked.position.as_dvec3() / 4096.0;
+                let entities = self.world.entities_near(block_pos_at(viewer).chunk_pos(), TRACKING_CHUNK_RADIUS).await;
+                let packets = tracked.entities.update(viewer, &entities).await;
+                if !packets.is_empty() {
+                    entity_packets.push((tracked.uuid, packets));
+                }
+            }
+            players
+                .values()
+                .map(|it| (it.uuid, it.player.clone(), it.sink.clone()))
+                .collect::<SmallVec<[(Uuid, Arc<dyn DynifiedPlayer + 'a>, Arc<dyn DynifiedMCPacketSink + 'a>); 8]>>()
+        };
+
+        for (uuid, _, sink) in &players {
+            for (moved, packets) in &movement_packets {
+                if moved != uuid {
+                    for pkt in packets {
+                        sink.send_mc_packet(pkt).init(&mut c).await;
+                    }
+                }
+            }
+            for (viewer, packets) in &entity_packets {
+                if viewer == uuid {
+                    for pkt in packets {
+                        sink.send_mc_packet(pkt).init(&mut c).await;
+                    }
+                }
+            }
+        }
+
+        let mut block_update_packets = self.block_update_packets.lock().await;
+        self.world.gen_blocks_update_packets_and_clear_changes(&mut block_update_packets).await;
+        if !block_update_packets.is_empty() {
+            // bundled, so that the client applies all changes of this tick at once
+            let bundle_delimiter = MCPacketBuffer::new(0).await; // bundle_delimiter
+            for (_, _, sink) in &players {
+                sink.send_mc_packet(&bundle_delimiter).init(&mut c).await;
+                for pkt in block_update_packets.iter() {
+                    sink.send_mc_packet(pkt).init(&mut c).await;
+                }
+                sink.send_mc_packet(&bundle_delimiter).init(&mut c).await;
             }
+            // keeps the capacity, the buffers go back to the pool
+            block_update_packets.clear();
         }
+        drop(block_update_packets);
 
-        for player in self.players.lock().await.values() {
+        // also submits the packets queued above
+        for (_, player, _) in &players {
             player.tick().init(&mut c).await;
         }
     }
 }
+
+/// A registered session, see [`MCServer::add_player`].
+/// Dropping it, e.g. when the connection task is cancelled, removes the session on the next tick.
+#[must_use]
+pub(crate) struct PlayerSession<'s, 'a, M: RawMutex, WORLD: World> {
+    server: &'s MCServer<'a, M, WORLD>,
+    uuid: Uuid,
+    entity_id: i32,
+}
+
+impl<M: RawMutex, WORLD: World> PlayerSession<'_, '_, M, WORLD> {
+    /// Removes the session right away, unless it was replaced by a newer one already.
+    pub(crate) async fn remove(self) {
+        let (server, uuid, entity_id) = (self.server, self.uuid, self.entity_id);
+        core::mem::forget(self);
+        server.remove_player(uuid, entity_id).await;
+    }
+}
+
+impl<M: RawMutex, WORLD: World> Drop for PlayerSession<'_, '_, M, WORLD> {
+    fn drop(&mut self) {
+        // removing needs to await, so it's left to the server tick
+        self.server.left_players.lock(|it| it.borrow_mut().push((self.uuid, self.entity_id)));
+    }
+}
+
+/// A player as last seen by the other players.
+struct TrackedPlayer<'a> {
+    player: Arc<dyn DynifiedPlayer + 'a>,
+    sink: Arc<dyn DynifiedMCPacketSink + 'a>,
+    uuid: Uuid,
+    name: String,
+    entity_id: i32,
+    /// In 1/4096 blocks, the unit of relative move packets.
+    position: I64Vec3,
+    yaw: u8,
+    pitch: u8,
+    /// The world's entities this player sees.
+    entities: EntityTracker,
+}
+
+impl<'a> TrackedPlayer<'a> {
+    async fn new(player: Arc<dyn DynifiedPlayer + 'a>, sink: Arc<dyn DynifiedMCPacketSink + 'a>) -> Self {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let rotation = player.rotation().init(&mut c).await;
+        Self {
+            uuid: player.uuid().init(&mut c).await,
+            name: player.name().init(&mut c).await,
+            entity_id: player.entity_id().init(&mut c).await,
+            position: encode_position(player.position().init(&mut c).await),
+            yaw: encode_angle(rotation.x),
+            pitch: encode_angle(rotation.y),
+            entities: EntityTracker::default(),
+            player,
+            sink,
+        }
+    }
+
+    /// Packets removing the player from the tab list and the world, for the other players.
+    async fn remove_packets(&self) -> [MCPacketBuffer; 2] {
+        let mut info_pkt = MCPacketBuffer::new(62).await; // player_info_remove
+        let _ = info_pkt.write_varint(1u32).await;
+        let _ = info_pkt.write_uuid(self.uuid).await;
+        let mut remove_entity_pkt = MCPacketBuffer::new(70).await; // remove_entities
+        let _ = remove_entity_pkt.write_varint(1u32).await;
+        let _ = remove_entity_pkt.write_varint(self.entity_id).await;
+        [info_pkt, remove_entity_pkt]
+    }
+
+    async fn add_entity_packet(&self) -> MCPacketBuffer {
+        let position = self.position.as_dvec3() / 4096.0;
+        let mut pkt = MCPacketBuffer::new(1).await; // add_entity
+        let _ = pkt.write_varint(self.entity_id).await;
+        let _ = pkt.write_uuid(self.uuid).await;
+        let _ = pkt.write_varint(PLAYER_ENTITY_TYPE).await;
+        let _ = pkt.write_be(position.x).await;
+        let _ = pkt.write_be(position.y).await;
+        let _ = pkt.write_be(position.z).await;
+        let _ = pkt.write_be(self.pitch).await;
+        let _ = pkt.write_be(self.yaw).await;
+        let _ = pkt.write_be(self.yaw).await; // head yaw
+        let _ = pkt.write_varint(0u32).await; // data
+        let _ = pkt.write_be(0i16).await; // velocity
+        let _ = pkt.write_be(0i16).await;
+        let _ = pkt.write_be(0i16).await;
+        pkt
+    }
+
+    /// Packets moving the player's entity to where the player is now, for the other players.
+    async fn gen_movement_packets(&mut self) -> SmallVec<[MCPacketBuffer; 2]> {
+        let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+        let raw_position = self.player.position().init(&mut c).await;
+        let position = encode_position(raw_position);
+        let rotation = self.player.rotation().init(&mut c).await;
+        let (yaw, pitch) = (encode_angle(rotation.x), encode_angle(rotation.y));
+        let on_ground = self.player.on_ground().init(&mut c).await;
+
+        let delta = position - self.position;
+        let moved = delta != I64Vec3::ZERO;
+        let rotated = (yaw, pitch) != (self.yaw, self.pitch);
+
+        let mut packets = SmallVec::new();
+        if moved && delta.to_array().iter().all(|it| i16::try_from(*it).is_ok()) {
+            let mut pkt = if rotated {
+                MCPacketBuffer::new(47).await // move_entity_pos_rot
+            } else {
+                MCPacketBuffer::new(46).await // move_entity_pos
+            };
+            let _ = pkt.write_varint(self.entity_id).await;
+            let _ = pkt.write_be(delta.x as i16).await;
+            let _ = pkt.write_be(delta.y as i16).await;
+            let _ = pkt.write_be(delta.z as i16).await;
+            if rotated {
+                let _ = pkt.write_be(yaw).await;
+                let _ = pkt.write_be(pitch).await;
+            }
+            let _ = pkt.write_be(on_ground as u8).await;
+            packets.push(pkt);
+        } else if moved {
+            // too far for a relative move
+            let mut pkt = MCPacketBuffer::new(31).await; // entity_position_sync
+            let _ = pkt.write_varint(self.entity_id).await;
+            let _ = pkt.write_be(raw_position.x).await;
+            let _ = pkt.write_be(raw_position.y).await;
+            let _ = pkt.write_be(raw_position.z).await;
+            let _ = pkt.write_be(0f64).await; // velocity
+            let _ = pkt.write_be(0f64).await;
+            let _ = pkt.write_be(0f64).await;
+            let _ = pkt.write_be(rotation.x).await;
+            let _ = pkt.write_be(rotation.y).await;
+            let _ = pkt.write_be(on_ground as u8).await;
+            packets.push(pkt);
+        } else if rotated {
+            let mut pkt = MCPacketBuffer::new(49).await; // move_entity_rot
+            let _ = pkt.write_varint(self.entity_id).await;
+            let _ = pkt.write_be(yaw).await;
+            let _ = pkt.write_be(pitch).await;
+            let _ = pkt.write_be(on_ground as u8).await;
+            packets.push(pkt);
+        }
+
+        if yaw != self.yaw {
+            let mut pkt = MCPacketBuffer::new(76).await; // rotate_head
+            let _ = pkt.write_varint(self.entity_id).await;
+            let _ = pkt.write_be(yaw).await;
+            packets.push(pkt);
+        }
+
+        self.position = position;
+        self.yaw = yaw;
+        self.pitch = pitch;
+        packets
+    }
+}
+
+async fn player_info_update_packet(players: &[&dyn DynifiedPlayer]) -> MCPacketBuffer {
+    let mut c = SmallVec::<[MaybeUninit<u8>; 64]>::new();
+    let mut pkt = MCPacketBuffer::new(63).await; // player_info_update
+    let _ = pkt.write_be(0x01u8 | 0x08).await; // actions: add_player, update_listed
+    let _ = pkt.write_varint(players.len() as u32).await;
+    for player in players {
+        let _ = pkt.write_uuid(player.uuid().init(&mut c).await).await;
+        let _ = pkt.write_utf8(&player.name().init(&mut c).await).await;
+        let _ = pkt.write_varint(0u32).await; // properties
+        let _ = pkt.write_be(true as u8).await; // listed
+    }
+    pkt
+}
+
+fn encode_position(position: DVec3) -> I64Vec3 {
+    (position * 4096.0).round().as_i64vec3()
+}
+
+fn encode_angle(degrees: f32) -> u8 {
+    FloatCore::floor(degrees * 256.0 / 360.0) as i32 as u8
+}
@@ -0,0 +1,555 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::ops::Range;
+use smallvec::SmallVec;
+use tileglobe::world::container::Container;
+use tileglobe::world::item::ItemStack;
+
+/// Number of slots of the player's inventory, as seen in its own menu.
+pub const INVENTORY_SLOTS: usize = 46;
+/// Main inventory and hotbar, the part of the player's inventory shown below other menus.
+const INVENTORY_STORAGE: Range<usize> = 9..45;
+const INVENTORY_MAIN: Range<usize> = 9..36;
+const INVENTORY_HOTBAR: Range<usize> = 36..45;
+const INVENTORY_OFFHAND: usize = 45;
+const INVENTORY_CRAFTING_RESULT: usize = 0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MenuType {
+    /// 9 slots per row (chests, barrels...), see [`MenuType::generic_9x`]
+    Generic9x(MenuRows),
+    /// Dispensers, droppers
+    Generic3x3,
+    Hopper,
+}
+
+/// Rows of a [`MenuType::Generic9x`], 1 to 6, the sizes the client has a menu for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MenuRows(u8);
+
+impl MenuRows {
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl MenuType {
+    /// `None` unless `rows` is 1 to 6.
+    pub fn generic_9x(rows: u8) -> Option<Self> {
+        (1..=6).contains(&rows).then_some(Self::Generic9x(MenuRows(rows)))
+    }
+
+    /// Id in the `minecraft:menu` registry.
+    pub fn id(self) -> u32 {
+        match self {
+            Self::Generic9x(rows) => rows.get() as u32 - 1,
+            Self::Generic3x3 => 6,
+            Self::Hopper => 16,
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Self::Generic9x(rows) => rows.get() as usize * 9,
+            Self::Generic3x3 => 9,
+            Self::Hopper => 5,
+        }
+    }
+}
+
+/// A container shown in a screen, above the player's inventory.
+pub struct Menu {
+    pub menu_type: MenuType,
+    pub title: String,
+    pub container: Arc<dyn Container>,
+}
+
+impl Menu {
+    pub fn new(menu_type: MenuType, title: String, container: Arc<dyn Container>) -> Self {
+        assert!(
+            container.size() >= menu_type.size(),
+            "Container too small for {:?}",
+            menu_type
+        );
+        Self {
+            menu_type,
+            title,
+            container,
+        }
+    }
+}
+
+/// State of a drag over multiple slots (`QUICK_CRAFT` clicks), spanning multiple packets.
+#[derive(Debug, Default)]
+pub struct QuickCraft {
+    /// 0: split evenly, 1: one item each, 2: full stacks (creative only)
+    kind: Option<u8>,
+    slots: SmallVec<[usize; 16]>,
+}
+
+/// The slots of a window: those of the open menu followed by the player's inventory,
+/// or only the player's inventory if no menu is open.
+pub struct MenuSlots<'a> {
+    menu: Option<&'a Menu>,
+    inventory: &'a mut [ItemStack; INVENTORY_SLOTS],
+    pub carried: &'a mut ItemStack,
+}
+
+impl<'a> MenuSlots<'a> {
+    pub fn new(
+        menu: Option<&'a Menu>,
+        inventory: &'a mut [ItemStack; INVENTORY_SLOTS],
+        carried: &'a mut ItemStack,
+    ) -> Self {
+        Self {
+            menu,
+            inventory,
+            carried,
+        }
+    }
+
+    fn menu_size(&self) -> usize {
+        self.menu.map_or(0, |it| it.menu_type.size())
+    }
+
+    pub fn len(&self) -> usize {
+        match self.menu {
+            Some(_) => self.menu_size() + INVENTORY_STORAGE.len(),
+            None => INVENTORY_SLOTS,
+        }
+    }
+
+    pub fn get(&self, slot: usize) -> ItemStack {
+        match self.menu {
+            Some(menu) if slot < self.menu_size() => menu.container.get_item(slot),
+            Some(_) => self.inventory[INVENTORY_STORAGE.start + slot - self.menu_size()].clone(),
+            None => self.inventory[slot].clone(),
+        }
+    }
+
+    fn set(&mut self, slot: usize, stack: ItemStack) {
+        match self.menu {
+            Some(menu) if slot < self.menu_size() => menu.container.set_item(slot, stack),
+            Some(_) => self.inventory[INVENTORY_STORAGE.start + slot - self.menu_size()] = stack,
+            None => self.inventory[slot] = stack,
+        }
+    }
+
+    fn may_place(&self, slot: usize) -> bool {
+        // there is no crafting, the result slot stays empty
+        self.menu.is_some() || slot != INVENTORY_CRAFTING_RESULT
+    }
+
+    /// Applies a `container_click`, the client's prediction of the result isn't trusted.
+    /// Returns the stack thrown out of the window, to be dropped as item entity.
+    pub fn click(
+        &mut self,
+        slot: i16,
+        button: i8,
+        mode: u32,
+        creative: bool,
+        quick_craft: &mut QuickCraft,
+    ) -> ItemStack {
+        if mode != 5 {
+            *quick_craft = QuickCraft::default();
+        }
+        let slot_index = usize::try_from(slot).ok().filter(|it| *it < self.len());
+
+        match (mode, slot_index) {
+            // PICKUP outside of the window
+            (0, None) if slot == -999 => {
+                return if button == 0 {
+                    core::mem::take(self.carried)
+                } else {
+                    self.carried.split(1)
+                };
+            }
+            (0, Some(slot)) => self.pickup(slot, button == 1),
+            (1, Some(slot)) => self.quick_move(slot),
+            // SWAP with a hotbar slot or the offhand
+            (2, Some(slot)) => {
+                let target = match button {
+                    0..=8 => INVENTORY_HOTBAR.start + button as usize,
+                    40 => INVENTORY_OFFHAND,
+                    _ => return ItemStack::EMPTY,
+                };
+                let stack = self.get(slot);
+                let target_stack = self.inventory[target].clone();
+                if self.may_place(slot) || target_stack.is_empty() {
+                    self.inventory[target] = stack;
+                    self.set(slot, target_stack);
+                }
+            }
+            // CLONE
+            (3, Some(slot)) => {
+                let stack = self.get(slot);
+                if creative && self.carried.is_empty() && !stack.is_empty() {
+                    *self.carried = stack.with_count(stack.max_stack_size());
+                }
+            }
+            // THROW, like vanilla only with nothing carried
+            (4, Some(slot)) if self.carried.is_empty() => {
+                let mut stack = self.get(slot);
+                let thrown = if button == 0 {
+                    stack.split(1)
+                } else {
+                    core::mem::take(&mut stack)
+                };
+                self.set(slot, stack);
+                return thrown;
+            }
+            (5, _) => self.quick_craft(slot_index, button as u8, creative, quick_craft),
+            (6, Some(_)) => self.pickup_all(),
+            _ => {}
+        }
+        ItemStack::EMPTY
+    }
+
+    fn pickup(&mut self, slot: usize, right: bool) {
+        let mut stack = self.get(slot);
+        if self.carried.is_empty() {
+            let count = if right { stack.count.div_ceil(2) } else { stack.count };
+            *self.carried = stack.split(count);
+        } else if !self.may_place(slot) {
+            return;
+        } else if stack.is_empty() || stack.is_stackable_with(self.carried) {
+            let mut placed = self.carried.split(if right { 1 } else { u8::MAX });
+            stack.merge(&mut placed, u8::MAX);
+            self.carried.merge(&mut placed, u8::MAX);
+        } else if self.carried.count <= stack.max_stack_size() {
+            core::mem::swap(&mut stack, self.carried);
+        }
+        self.set(slot, stack);
+    }
+
+    /// Shift-click: moves the stack between the menu and the inventory, or within the inventory.
+    fn quick_move(&mut self, slot: usize) {
+        let mut stack = self.get(slot);
+        if stack.is_empty() || !self.may_place(slot) {
+            return;
+        }
+        let (target, reverse) = match self.menu {
+            Some(_) if slot < self.menu_size() => (self.menu_size()..self.len(), true),
+            Some(_) => (0..self.menu_size(), false),
+            None if INVENTORY_MAIN.contains(&slot) => (INVENTORY_HOTBAR, false),
+            None if INVENTORY_HOTBAR.contains(&slot) => (INVENTORY_MAIN, false),
+            None => (INVENTORY_STORAGE, false),
+        };
+        self.move_into(&mut stack, target, reverse);
+        self.set(slot, stack);
+    }
+
+    /// Merges the stack into the slots, first into stacks of the same item, then into empty slots.
+    fn move_into(&mut self, stack: &mut ItemStack, slots: Range<usize>, reverse: bool) {
+        for into_empty in [false, true] {
+            for i in 0..slots.len() {
+                if stack.is_empty() {
+                    return;
+                }
+                let slot = if reverse { slots.end - 1 - i } else { slots.start + i };
+                let mut target = self.get(slot);
+                if target.is_empty() == into_empty && self.may_place(slot) {
+                    target.merge(stack, u8::MAX);
+                    self.set(slot, target);
+                }
+            }
+        }
+    }
+
+    /// Double-click: collects items of the carried kind, from non-full stacks first.
+    fn pickup_all(&mut self) {
+        if self.carried.is_empty() {
+            return;
+        }
+        for take_full in [false, true] {
+            for slot in 0..self.len() {
+                if self.carried.count >= self.carried.max_stack_size() {
+                    return;
+                }
+                let mut stack = self.get(slot);
+                if !stack.is_empty()
+                    && stack.is_stackable_with(self.carried)
+                    && (stack.count >= stack.max_stack_size()) == take_full
+                    && self.may_place(slot)
+                {
+                    self.carried.merge(&mut stack, u8::MAX);
+                    self.set(slot, stack);
+                }
+            }
+        }
+    }
+
+    fn quick_craft(&mut self, slot: Option<usize>, button: u8, creative: bool, state: &mut QuickCraft) {
+        let kind = (button >> 2) & 3;
+        match button & 3 {
+            // start
+            0 => {
+                *state = QuickCraft::default();
+                if !self.carried.is_empty() && (kind != 2 || creative) {
+                    state.kind = Some(kind);
+                }
+            }
+            // add slot
+            1 => {
+                let (Some(kind), Some(slot)) = (state.kind, slot) else {
+                    return;
+                };
+                let stack = self.get(slot);
+                if self.may_place(slot)
+                    && (stack.is_empty() || stack.is_stackable_with(self.carried))
+                    && !state.slots.contains(&slot)
+                    && (kind == 2 || state.slots.len() < self.carried.count as usize)
+                {
+                    state.slots.push(slot);
+                }
+            }
+            // end
+            2 => {
+                let Some(kind) = state.kind.take() else {
+                    return;
+                };
+                let slots = core::mem::take(&mut state.slots);
+                if slots.len() == 1 {
+                    self.pickup(slots[0], kind == 1);
+                    return;
+                }
+                if slots.is_empty() || self.carried.is_empty() {
+                    return;
+                }
+                let per_slot = match kind {
+                    0 => self.carried.count / slots.len() as u8,
+                    1 => 1,
+                    _ => self.carried.max_stack_size(),
+                };
+                for slot in slots {
+                    let mut stack = self.get(slot);
+                    if kind == 2 {
+                        stack.merge(&mut self.carried.with_count(per_slot), u8::MAX);
+                    } else {
+                        let mut portion = self.carried.split(per_slot);
+                        stack.merge(&mut portion, u8::MAX);
+                        self.carried.merge(&mut portion, u8::MAX);
+                    }
+                    self.set(slot, stack);
+                }
+            }
+            _ => *state = QuickCraft::default(),
+        }
+    }
+
+    /// Puts the carried stack back into the inventory, e.g. when the menu is closed.
+    /// Returns what doesn't fit, to be dropped as item entity.
+    pub fn return_carried(&mut self) -> ItemStack {
+        let mut carried = core::mem::take(self.carried);
+        if self.menu.is_some() {
+            let inventory = self.menu_size()..self.len();
+            self.move_into(&mut carried, inventory, false);
+        } else {
+            self.move_into(&mut carried, INVENTORY_STORAGE, false);
+        }
+        carried
+    }
+}
+
+/// Brings the stack into the selected hotbar slot when picking a block (middle click),
+/// like vanilla's `ServerGamePacketListenerImpl.tryPickItem`.
+/// A matching stack in the inventory is selected or swapped into the hotbar,
+/// otherwise the stack is added with infinite materials (creative). `false` if it can't be picked.
+pub fn pick_item(
+    inventory: &mut [ItemStack; INVENTORY_SLOTS],
+    selected_hotbar_slot: &mut u8,
+    stack: ItemStack,
+    infinite_materials: bool,
+) -> bool {
+    let found = INVENTORY_HOTBAR
+        .chain(INVENTORY_MAIN)
+        .find(|&slot| !inventory[slot].is_empty() && inventory[slot].is_stackable_with(&stack));
+    let target = match found {
+        Some(slot) if INVENTORY_HOTBAR.contains(&slot) => slot,
+        Some(slot) => {
+            let target = suitable_hotbar_slot(inventory, *selected_hotbar_slot);
+            inventory.swap(slot, target);
+            target
+        }
+        None if infinite_materials => {
+            let target = suitable_hotbar_slot(inventory, *selected_hotbar_slot);
+            // keep what was in the slot if there's room
+            if !inventory[target].is_empty()
+                && let Some(free) = INVENTORY_HOTBAR.chain(INVENTORY_MAIN).find(|&slot| inventory[slot].is_empty())
+            {
+                inventory[free] = core::mem::take(&mut inventory[target]);
+            }
+            inventory[target] = stack;
+            target
+        }
+        None => return false,
+    };
+    *selected_hotbar_slot = (target - INVENTORY_HOTBAR.start) as u8;
+    true
+}
+
+/// Adds as much of the stack to the inventory as fits, leaving the rest in `stack`, like vanilla's `Inventory.add`.
+/// Stacks in the selected slot, the offhand, the hotbar and the main inventory are filled first,
+/// then empty slots in the hotbar and the main inventory.
+pub fn add_to_inventory(inventory: &mut [ItemStack; INVENTORY_SLOTS], selected_hotbar_slot: u8, stack: &mut ItemStack) {
+    let selected = INVENTORY_HOTBAR.start + selected_hotbar_slot as usize;
+    let partial = [selected, INVENTORY_OFFHAND].into_iter().chain(INVENTORY_HOTBAR).chain(INVENTORY_MAIN);
+    for slot in partial {
+        if stack.is_empty() {
+            return;
+        }
+        if !inventory[slot].is_empty() {
+            inventory[slot].merge(stack, u8::MAX);
+        }
+    }
+    for slot in INVENTORY_HOTBAR.chain(INVENTORY_MAIN) {
+        if stack.is_empty() {
+            return;
+        }
+        if inventory[slot].is_empty() {
+            inventory[slot].merge(stack, u8::MAX);
+        }
+    }
+}
+
+/// The first empty hotbar slot from the selected one on, else the selected one.
+/// Vanilla would prefer slots without enchanted items next, but the item components aren't interpreted.
+fn suitable_hotbar_slot(inventory: &[ItemStack; INVENTORY_SLOTS], selected_hotbar_slot: u8) -> usize {
+    let hotbar_size = INVENTORY_HOTBAR.len();
+    (0..hotbar_size)
+        .map(|i| INVENTORY_HOTBAR.start + (selected_hotbar_slot as usize + i) % hotbar_size)
+        .find(|&slot| inventory[slot].is_empty())
+        .unwrap_or(INVENTORY_HOTBAR.start + selected_hotbar_slot as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use tileglobe::world::container::SimpleContainer;
+    use tileglobe::world::item::ItemId;
+
+    const PICKUP: u32 = 0;
+    const QUICK_MOVE: u32 = 1;
+    const THROW: u32 = 4;
+
+    fn stone(count: u8) -> ItemStack {
+        ItemStack::new(ItemId(1), count)
+    }
+
+    fn granite(count: u8) -> ItemStack {
+        ItemStack::new(ItemId(2), count)
+    }
+
+    fn empty_inventory() -> [ItemStack; INVENTORY_SLOTS] {
+        core::array::from_fn(|_| ItemStack::EMPTY)
+    }
+
+    fn click(slots: &mut MenuSlots, slot: i16, button: i8, mode: u32) -> ItemStack {
+        slots.click(slot, button, mode, false, &mut QuickCraft::default())
+    }
+
+    #[test]
+    fn pickup_takes_and_places_stacks() {
+        let mut inventory = empty_inventory();
+        inventory[9] = stone(5);
+        inventory[10] = granite(1);
+        let mut carried = ItemStack::EMPTY;
+        let mut slots = MenuSlots::new(None, &mut inventory, &mut carried);
+
+        // right click takes half, rounded up
+        click(&mut slots, 9, 1, PICKUP);
+        assert_eq!(*slots.carried, stone(3));
+        assert_eq!(slots.get(9), stone(2));
+        // right click places one
+        click(&mut slots, 11, 1, PICKUP);
+        assert_eq!(*slots.carried, stone(2));
+        assert_eq!(slots.get(11), stone(1));
+        // a different item is swapped
+        click(&mut slots, 10, 0, PICKUP);
+        assert_eq!(*slots.carried, granite(1));
+        assert_eq!(slots.get(10), stone(2));
+        // nothing can be put into the crafting result
+        click(&mut slots, INVENTORY_CRAFTING_RESULT as i16, 0, PICKUP);
+        assert_eq!(*slots.carried, granite(1));
+        assert!(slots.get(INVENTORY_CRAFTING_RESULT).is_empty());
+    }
+
+    #[test]
+    fn clicks_outside_throw_the_carried_stack() {
+        let mut inventory = empty_inventory();
+        let mut carried = stone(5);
+        let mut slots = MenuSlots::new(None, &mut inventory, &mut carried);
+        assert_eq!(click(&mut slots, -999, 1, PICKUP), stone(1));
+        assert_eq!(click(&mut slots, -999, 0, PICKUP), stone(4));
+        assert!(slots.carried.is_empty());
+    }
+
+    #[test]
+    fn throw_only_without_a_carried_stack() {
+        let mut inventory = empty_inventory();
+        inventory[36] = stone(5);
+        let mut carried = granite(1);
+        let mut slots = MenuSlots::new(None, &mut inventory, &mut carried);
+        assert!(click(&mut slots, 36, 0, THROW).is_empty());
+        assert_eq!(slots.get(36), stone(5));
+
+        *slots.carried = ItemStack::EMPTY;
+        assert_eq!(click(&mut slots, 36, 0, THROW), stone(1));
+        assert_eq!(click(&mut slots, 36, 1, THROW), stone(4));
+        assert!(slots.get(36).is_empty());
+    }
+
+    #[test]
+    fn quick_move_between_the_menu_and_the_inventory() {
+        let container = Arc::new(SimpleContainer::<NoopRawMutex>::new(9));
+        container.set_item(0, stone(10));
+        let menu = Menu::new(MenuType::generic_9x(1).unwrap(), String::new(), container.clone());
+        let mut inventory = empty_inventory();
+        inventory[40] = stone(60);
+        let mut carried = ItemStack::EMPTY;
+        let mut slots = MenuSlots::new(Some(&menu), &mut inventory, &mut carried);
+        assert_eq!(slots.len(), 9 + 36);
+
+        // filling the stack of the same item first, then the last empty slot
+        click(&mut slots, 0, 0, QUICK_MOVE);
+        assert!(container.get_item(0).is_empty());
+        assert_eq!(slots.get(9 + 31), stone(64));
+        assert_eq!(slots.get(9 + 35), stone(6));
+
+        // back into the first slot of the menu
+        click(&mut slots, 9 + 35, 0, QUICK_MOVE);
+        assert_eq!(container.get_item(0), stone(6));
+    }
+
+    #[test]
+    fn pick_item_selects_or_swaps_a_matching_stack() {
+        let mut inventory = empty_inventory();
+        inventory[38] = granite(1);
+        inventory[20] = stone(5);
+        let mut selected = 0;
+
+        assert!(pick_item(&mut inventory, &mut selected, granite(1), false));
+        assert_eq!(selected, 2);
+
+        // swapped into the first empty hotbar slot from the selected one on
+        assert!(pick_item(&mut inventory, &mut selected, stone(1), false));
+        assert_eq!(selected, 3);
+        assert_eq!(inventory[39], stone(5));
+        assert!(inventory[20].is_empty());
+    }
+
+    #[test]
+    fn pick_item_adds_missing_stacks_only_with_infinite_materials() {
+        let mut inventory = empty_inventory();
+        inventory[36] = granite(1);
+        let mut selected = 0;
+        assert!(!pick_item(&mut inventory, &mut selected, stone(1), false));
+        assert_eq!(selected, 0);
+
+        assert!(pick_item(&mut inventory, &mut selected, stone(1), true));
+        assert_eq!(selected, 1);
+        assert_eq!(inventory[37], stone(1));
+        assert_eq!(inventory[36], granite(1));
+    }
+}
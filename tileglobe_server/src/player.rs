@@ -1,12 +1,175 @@
+use alloc::string::String;
+use core::net::SocketAddr;
+use glam::{DVec3, Vec2};
 use uuid::Uuid;
 use tileglobe_utils::network::MCPacketBuffer;
+use crate::menu::Menu;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(u8)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Self::Survival),
+            1 => Some(Self::Creative),
+            2 => Some(Self::Adventure),
+            3 => Some(Self::Spectator),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as in commands.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Survival => "survival",
+            Self::Creative => "creative",
+            Self::Adventure => "adventure",
+            Self::Spectator => "spectator",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Survival, Self::Creative, Self::Adventure, Self::Spectator]
+            .into_iter()
+            .find(|it| it.name() == name)
+    }
+
+    /// Sets the abilities that come with the game mode, see vanilla's `GameType::updatePlayerAbilities`.
+    pub fn update_abilities(self, abilities: &mut Abilities) {
+        match self {
+            Self::Creative => {
+                abilities.may_fly = true;
+                abilities.instabuild = true;
+                abilities.invulnerable = true;
+            }
+            Self::Spectator => {
+                abilities.may_fly = true;
+                abilities.instabuild = false;
+                abilities.invulnerable = true;
+                abilities.flying = true;
+            }
+            Self::Survival | Self::Adventure => {
+                abilities.may_fly = false;
+                abilities.instabuild = false;
+                abilities.invulnerable = false;
+                abilities.flying = false;
+            }
+        }
+        abilities.may_build = matches!(self, Self::Survival | Self::Creative);
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Abilities {
+    pub invulnerable: bool,
+    pub flying: bool,
+    pub may_fly: bool,
+    /// Blocks break instantly, and items aren't used up.
+    pub instabuild: bool,
+    pub may_build: bool,
+}
+
+impl Abilities {
+    /// Flags of the `player_abilities` packet.
+    pub fn flags(self) -> u8 {
+        (self.invulnerable as u8)
+            | (self.flying as u8) << 1
+            | (self.may_fly as u8) << 2
+            | (self.instabuild as u8) << 3
+    }
+}
+
+/// Durations of the title animation, in ticks.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TitleTimes {
+    pub fade_in: u32,
+    pub stay: u32,
+    pub fade_out: u32,
+}
+
+impl Default for TitleTimes {
+    fn default() -> Self {
+        Self {
+            fade_in: 10,
+            stay: 70,
+            fade_out: 20,
+        }
+    }
+}
 
 #[dynify::dynify(DynifiedPlayer)]
 pub trait Player {
     async fn uuid(&self) -> Uuid;
-    
+
+    async fn name(&self) -> String;
+
+    async fn entity_id(&self) -> i32;
+
+    async fn position(&self) -> DVec3;
+
+    /// (yaw, pitch) in degrees.
+    async fn rotation(&self) -> Vec2;
+
+    async fn on_ground(&self) -> bool;
+
+    /// Movement reported by the client is ignored until it confirms the teleport.
+    async fn teleport(&self, position: DVec3, rotation: Vec2);
+
+    /// Shows the message in the chat.
+    async fn send_message(&self, message: &str);
+
+    /// Shows the message above the hotbar.
+    async fn send_action_bar(&self, message: &str);
+
+    /// Shows a title in the middle of the screen, with the subtitle below it.
+    async fn set_title(&self, title: &str, subtitle: &str, times: TitleTimes);
+
+    async fn game_mode(&self) -> GameMode;
+
+    async fn set_game_mode(&self, game_mode: GameMode);
+
+    /// Operator permission level, 0 to 4.
+    async fn permission_level(&self) -> u8;
+
+    async fn set_permission_level(&self, level: u8);
+
+    /// Remote address of the connection, if known.
+    async fn address(&self) -> Option<SocketAddr>;
+
+    /// Disconnects the player, showing the reason.
+    async fn kick(&self, reason: &str);
+
+    /// Opens the menu in a screen, closing the one open before.
+    async fn open_menu(&self, menu: Menu);
+
+    async fn close_menu(&self);
+
     async fn tick(&self);
+}
 
-    //TODO: remove this method, let player impl handle the packet logic instead
+/// Where the server queues the packets for a player, raw packets don't leave the server crate.
+#[dynify::dynify(DynifiedMCPacketSink)]
+pub(crate) trait MCPacketSink {
     async fn send_mc_packet(&self, pkt: &MCPacketBuffer);
-}
\ No newline at end of file
+}
+
+/// Subscribed with [`crate::mc_server::MCServer::add_listener`].
+#[dynify::dynify(DynifiedPlayerListener)]
+pub trait PlayerListener {
+    /// The player is in the world and visible to the others.
+    async fn on_player_join(&self, player: &dyn DynifiedPlayer);
+
+    /// The player is gone from the world, its connection might already be closed.
+    async fn on_player_leave(&self, player: &dyn DynifiedPlayer);
+}
@@ -0,0 +1,375 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::net::IpAddr;
+use core::str::FromStr;
+use defmt_or_log::*;
+use tileglobe::world::region::Region;
+use uuid::Uuid;
+
+/// The lists saved to a [`PlayerListStorage`], like vanilla's `whitelist.json`, `ops.json`...
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PlayerListKind {
+    Whitelist,
+    /// Whether the whitelist is enforced, kept along with the lists.
+    WhitelistEnabled,
+    Ops,
+    BannedPlayers,
+    BannedIps,
+    /// Players ignoring the protected regions.
+    RegionBypass,
+    /// The protected regions of the world, kept by the world rather than [`PlayerLists`].
+    Regions,
+}
+
+impl PlayerListKind {
+    pub const ALL: [Self; 7] = [
+        Self::Whitelist,
+        Self::WhitelistEnabled,
+        Self::Ops,
+        Self::BannedPlayers,
+        Self::BannedIps,
+        Self::RegionBypass,
+        Self::Regions,
+    ];
+
+    /// Name of the list in the storage, e.g. as file name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Whitelist => "whitelist",
+            Self::WhitelistEnabled => "whitelist-enabled",
+            Self::Ops => "ops",
+            Self::BannedPlayers => "banned-players",
+            Self::BannedIps => "banned-ips",
+            Self::RegionBypass => "region-bypass",
+            Self::Regions => "regions",
+        }
+    }
+}
+
+/// Keeps the player lists across restarts, as files, in flash...
+///
+/// Each list is stored as UTF-8 text, one entry per line, with the fields separated by spaces:
+/// - whitelist: `<uuid> <name>`
+/// - whitelist enabled: `true` or `false`
+/// - ops: `<uuid> <name> <level>`
+/// - banned players: `<uuid> <name> <reason>`
+/// - banned IPs: `<ip> <reason>`
+/// - region bypass: `<uuid>`
+/// - regions: see [`tileglobe::world::region::Region::encode`]
+#[dynify::dynify(DynifiedPlayerListStorage)]
+pub trait PlayerListStorage {
+    /// `None` if the list was never saved.
+    async fn load(&self, list: PlayerListKind) -> Option<Vec<u8>>;
+
+    async fn save(&self, list: PlayerListKind, data: &[u8]);
+}
+
+/// Player list settings given when building, for runtimes without a console to run `/op` and `/whitelist` from.
+/// Unset settings leave the stored lists as they are.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct PlayerListConfig {
+    /// Made operator at startup, `TILEGLOBE_OWNER`.
+    pub owner: Option<&'static str>,
+    /// Whether only operators and whitelisted players may join, `TILEGLOBE_WHITELIST=true` or `false`.
+    pub whitelist_enabled: Option<bool>,
+}
+
+impl PlayerListConfig {
+    /// From the environment variables set when building.
+    pub fn from_build_env() -> Self {
+        Self::parse(option_env!("TILEGLOBE_OWNER"), option_env!("TILEGLOBE_WHITELIST"))
+    }
+
+    /// Blank values count as unset, invalid ones are skipped.
+    pub fn parse(owner: Option<&'static str>, whitelist: Option<&str>) -> Self {
+        let whitelist = whitelist.map(str::trim).filter(|it| !it.is_empty());
+        Self {
+            owner: owner.map(str::trim).filter(|it| !it.is_empty()),
+            whitelist_enabled: whitelist.and_then(|it| {
+                let enabled = it.parse().ok();
+                if enabled.is_none() {
+                    warn!("Ignoring TILEGLOBE_WHITELIST={}, expected true or false", it);
+                }
+                enabled
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpEntry {
+    pub name: String,
+    /// 1 to 4
+    pub level: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct BanEntry {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Who may join, and who is operator.
+/// Players are identified by their offline mode UUID, the names are kept for display.
+#[derive(Debug, Default)]
+pub struct PlayerLists {
+    pub whitelist_enabled: bool,
+    pub whitelist: BTreeMap<Uuid, String>,
+    pub ops: BTreeMap<Uuid, OpEntry>,
+    pub banned_players: BTreeMap<Uuid, BanEntry>,
+    pub banned_ips: BTreeMap<IpAddr, String>,
+    pub region_bypass: BTreeSet<Uuid>,
+}
+
+impl PlayerLists {
+    /// The reason the player may not join, if so.
+    pub fn check_login(&self, uuid: Uuid, ip: Option<IpAddr>) -> Result<(), String> {
+        if let Some(ban) = self.banned_players.get(&uuid) {
+            return Err(ban_message(&ban.reason));
+        }
+        // like vanilla, operators don't need to be whitelisted
+        if self.whitelist_enabled && !self.whitelist.contains_key(&uuid) && !self.ops.contains_key(&uuid) {
+            return Err(String::from("You are not white-listed on this server!"));
+        }
+        if let Some(reason) = ip.and_then(|it| self.banned_ips.get(&it)) {
+            return Err(format!("Your IP address is banned from this server.\nReason: {}", reason));
+        }
+        Ok(())
+    }
+
+    pub fn permission_level(&self, uuid: Uuid) -> u8 {
+        self.ops.get(&uuid).map_or(0, |it| it.level)
+    }
+
+    pub fn encode(&self, list: PlayerListKind) -> Vec<u8> {
+        let mut text = String::new();
+        // writing to a string doesn't fail
+        match list {
+            PlayerListKind::Whitelist => {
+                for (uuid, name) in &self.whitelist {
+                    let _ = writeln!(text, "{} {}", uuid, name);
+                }
+            }
+            PlayerListKind::WhitelistEnabled => {
+                let _ = writeln!(text, "{}", self.whitelist_enabled);
+            }
+            PlayerListKind::Ops => {
+                for (uuid, op) in &self.ops {
+                    let _ = writeln!(text, "{} {} {}", uuid, op.name, op.level);
+                }
+            }
+            PlayerListKind::BannedPlayers => {
+                for (uuid, ban) in &self.banned_players {
+                    let _ = writeln!(text, "{} {} {}", uuid, ban.name, ban.reason);
+                }
+            }
+            PlayerListKind::BannedIps => {
+                for (ip, reason) in &self.banned_ips {
+                    let _ = writeln!(text, "{} {}", ip, reason);
+                }
+            }
+            PlayerListKind::RegionBypass => {
+                for uuid in &self.region_bypass {
+                    let _ = writeln!(text, "{}", uuid);
+                }
+            }
+            PlayerListKind::Regions => {}
+        }
+        text.into_bytes()
+    }
+
+    /// Replaces the list with the stored one, malformed entries are skipped.
+    pub fn decode(&mut self, list: PlayerListKind, data: &[u8]) {
+        let Some(text) = decode_text(list, data) else {
+            return;
+        };
+        match list {
+            PlayerListKind::Whitelist => self.whitelist.clear(),
+            PlayerListKind::WhitelistEnabled => self.whitelist_enabled = false,
+            PlayerListKind::Ops => self.ops.clear(),
+            PlayerListKind::BannedPlayers => self.banned_players.clear(),
+            PlayerListKind::BannedIps => self.banned_ips.clear(),
+            PlayerListKind::RegionBypass => self.region_bypass.clear(),
+            PlayerListKind::Regions => return,
+        }
+        for line in text.lines().filter(|it| !it.trim().is_empty()) {
+            if self.decode_entry(list, line).is_none() {
+                warn!("Skipping malformed entry of player list {}: {}", list.name(), line);
+            }
+        }
+    }
+
+    fn decode_entry(&mut self, list: PlayerListKind, line: &str) -> Option<()> {
+        let mut fields = line.splitn(3, ' ');
+        match list {
+            PlayerListKind::Whitelist => {
+                let uuid = Uuid::try_parse(fields.next()?).ok()?;
+                self.whitelist.insert(uuid, String::from(fields.next()?));
+            }
+            PlayerListKind::WhitelistEnabled => {
+                self.whitelist_enabled = line.trim().parse().ok()?;
+            }
+            PlayerListKind::Ops => {
+                let uuid = Uuid::try_parse(fields.next()?).ok()?;
+                let name = String::from(fields.next()?);
+                let level = fields.next()?.parse().ok().filter(|it| (1..=4).contains(it))?;
+                self.ops.insert(uuid, OpEntry { name, level });
+            }
+            PlayerListKind::BannedPlayers => {
+                let uuid = Uuid::try_parse(fields.next()?).ok()?;
+                let name = String::from(fields.next()?);
+                let reason = String::from(fields.next().unwrap_or_default());
+                self.banned_players.insert(uuid, BanEntry { name, reason });
+            }
+            PlayerListKind::BannedIps => {
+                let mut fields = line.splitn(2, ' ');
+                let ip = IpAddr::from_str(fields.next()?).ok()?;
+                self.banned_ips.insert(ip, String::from(fields.next().unwrap_or_default()));
+            }
+            PlayerListKind::RegionBypass => {
+                self.region_bypass.insert(Uuid::try_parse(line.trim()).ok()?);
+            }
+            PlayerListKind::Regions => return None,
+        }
+        Some(())
+    }
+}
+
+/// Encodes the regions as stored in [`PlayerListKind::Regions`].
+pub fn encode_regions(regions: &[Region]) -> Vec<u8> {
+    let mut text = String::new();
+    for region in regions {
+        region.encode(&mut text);
+    }
+    text.into_bytes()
+}
+
+/// Decodes [`PlayerListKind::Regions`], malformed regions are skipped.
+pub fn decode_regions(data: &[u8]) -> Vec<Region> {
+    let Some(text) = decode_text(PlayerListKind::Regions, data) else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter(|it| !it.trim().is_empty())
+        .filter_map(|line| {
+            let region = Region::decode(line);
+            if region.is_none() {
+                warn!("Skipping malformed region: {}", line);
+            }
+            region
+        })
+        .collect()
+}
+
+fn decode_text(list: PlayerListKind, data: &[u8]) -> Option<&str> {
+    let text = core::str::from_utf8(data).ok();
+    if text.is_none() {
+        warn!("Player list {} is not valid UTF-8, ignoring it", list.name());
+    }
+    text
+}
+
+pub fn ban_message(reason: &str) -> String {
+    format!("You are banned from this server.\nReason: {}", reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // the logging macros are glob imported too
+    use core::{assert, assert_eq};
+
+    const ALICE: Uuid = Uuid::from_u128(1);
+    const BOB: Uuid = Uuid::from_u128(2);
+
+    fn decoded(list: PlayerListKind, text: &str) -> PlayerLists {
+        let mut lists = PlayerLists::default();
+        lists.decode(list, text.as_bytes());
+        lists
+    }
+
+    fn op(name: &str, level: u8) -> OpEntry {
+        OpEntry {
+            name: String::from(name),
+            level,
+        }
+    }
+
+    fn ban(name: &str, reason: &str) -> BanEntry {
+        BanEntry {
+            name: String::from(name),
+            reason: String::from(reason),
+        }
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let mut lists = PlayerLists::default();
+        lists.whitelist_enabled = true;
+        lists.whitelist.insert(ALICE, String::from("Alice"));
+        lists.ops.insert(BOB, op("Bob", 3));
+        lists.banned_players.insert(ALICE, ban("Alice", "Griefing the spawn"));
+        lists.banned_ips.insert(IpAddr::from([10, 0, 0, 1]), String::from("Spam"));
+        lists.region_bypass.insert(BOB);
+
+        let mut copy = PlayerLists::default();
+        for list in PlayerListKind::ALL {
+            copy.decode(list, &lists.encode(list));
+        }
+        assert!(copy.whitelist_enabled);
+        assert_eq!(copy.whitelist, lists.whitelist);
+        assert_eq!(copy.ops[&BOB].name, "Bob");
+        assert_eq!(copy.permission_level(BOB), 3);
+        assert_eq!(copy.banned_players[&ALICE].reason, "Griefing the spawn");
+        assert_eq!(copy.banned_ips, lists.banned_ips);
+        assert_eq!(copy.region_bypass, lists.region_bypass);
+    }
+
+    #[test]
+    fn malformed_entries_are_skipped() {
+        let text = format!("not-a-uuid Alice 4\n{ALICE} Alice 5\n{ALICE} Bob\n\n{BOB} Bob 2\n");
+        let lists = decoded(PlayerListKind::Ops, &text);
+        assert_eq!(lists.ops.keys().copied().collect::<Vec<_>>(), [BOB]);
+        assert_eq!(lists.permission_level(BOB), 2);
+        assert_eq!(lists.permission_level(ALICE), 0);
+
+        let lists = decoded(PlayerListKind::BannedIps, "10.0.0.300 Spam\n::1\n");
+        let localhost = IpAddr::from([0u16, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(lists.banned_ips.keys().copied().collect::<Vec<_>>(), [localhost]);
+    }
+
+    #[test]
+    fn invalid_utf8_keeps_the_list() {
+        let mut lists = decoded(PlayerListKind::Whitelist, &format!("{ALICE} Alice\n"));
+        lists.decode(PlayerListKind::Whitelist, &[0xff, 0xfe]);
+        assert!(lists.whitelist.contains_key(&ALICE));
+    }
+
+    #[test]
+    fn operators_join_without_being_whitelisted() {
+        let mut lists = PlayerLists::default();
+        lists.whitelist_enabled = true;
+        assert!(lists.check_login(ALICE, None).is_err());
+        lists.ops.insert(ALICE, op("Alice", 4));
+        assert!(lists.check_login(ALICE, None).is_ok());
+        lists.banned_players.insert(ALICE, ban("Alice", ""));
+        assert!(lists.check_login(ALICE, None).is_err());
+    }
+
+    #[test]
+    fn config_skips_blank_and_invalid_values() {
+        let parsed = PlayerListConfig::parse(Some(" Alice "), Some("true"));
+        assert_eq!(parsed.owner, Some("Alice"));
+        assert_eq!(parsed.whitelist_enabled, Some(true));
+
+        let parsed = PlayerListConfig::parse(Some(""), Some(" false\n"));
+        assert_eq!(parsed.owner, None);
+        assert_eq!(parsed.whitelist_enabled, Some(false));
+
+        assert_eq!(PlayerListConfig::parse(Some("  "), Some("yes")), PlayerListConfig::default());
+        assert_eq!(PlayerListConfig::parse(None, None), PlayerListConfig::default());
+    }
+}
@@ -10,5 +10,6 @@ pub mod network;
 pub mod indexed_enum;
 pub mod pos;
 pub mod direction;
+pub mod nbt;
 
 pub const MINECRAFT: &str = "minecraft";
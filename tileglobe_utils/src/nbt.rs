@@ -0,0 +1,246 @@
+//! Named Binary Tag, in the network encoding where the root compound has no name.
+//! Strings are written as UTF-8 rather than modified UTF-8, which only differs for NUL and characters outside the BMP.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// All elements must be of the same type.
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    fn type_id(&self) -> u8 {
+        match self {
+            Self::Byte(_) => TAG_BYTE,
+            Self::Short(_) => TAG_SHORT,
+            Self::Int(_) => TAG_INT,
+            Self::Long(_) => TAG_LONG,
+            Self::Float(_) => TAG_FLOAT,
+            Self::Double(_) => TAG_DOUBLE,
+            Self::ByteArray(_) => TAG_BYTE_ARRAY,
+            Self::String(_) => TAG_STRING,
+            Self::List(_) => TAG_LIST,
+            Self::Compound(_) => TAG_COMPOUND,
+            Self::IntArray(_) => TAG_INT_ARRAY,
+            Self::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Byte(value) => out.push(*value as u8),
+            Self::Short(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::Int(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::Long(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::Float(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::Double(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::ByteArray(values) => {
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                out.extend(values.iter().map(|it| *it as u8));
+            }
+            Self::String(value) => encode_string(value, out),
+            Self::List(values) => {
+                out.push(values.first().map_or(TAG_END, Tag::type_id));
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                for value in values {
+                    value.encode_payload(out);
+                }
+            }
+            Self::Compound(compound) => compound.encode_payload(out),
+            Self::IntArray(values) => {
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                for value in values {
+                    out.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            Self::LongArray(values) => {
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                for value in values {
+                    out.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    /// Number of bytes [`Self::encode_payload`] writes.
+    fn payload_size(&self) -> usize {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) | Self::Float(_) => 4,
+            Self::Long(_) | Self::Double(_) => 8,
+            Self::ByteArray(values) => 4 + values.len(),
+            Self::String(value) => 2 + value.len(),
+            Self::List(values) => 1 + 4 + values.iter().map(Tag::payload_size).sum::<usize>(),
+            Self::Compound(compound) => compound.payload_size(),
+            Self::IntArray(values) => 4 + values.len() * 4,
+            Self::LongArray(values) => 4 + values.len() * 8,
+        }
+    }
+}
+
+/// Named tags, the root of any NBT data.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Compound(BTreeMap<String, Tag>);
+
+impl Compound {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.0.get(name)
+    }
+
+    pub fn insert(&mut self, name: &str, tag: Tag) {
+        self.0.insert(String::from(name), tag);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        self.0.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Tag)> {
+        self.0.iter().map(|(name, tag)| (name.as_str(), tag))
+    }
+
+    pub fn get_byte(&self, name: &str) -> Option<i8> {
+        match self.get(name)? {
+            Tag::Byte(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            Tag::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_float(&self, name: &str) -> Option<f32> {
+        match self.get(name)? {
+            Tag::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_compound(&self, name: &str) -> Option<&Compound> {
+        match self.get(name)? {
+            Tag::Compound(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Appends the compound as network NBT, i.e. as root tag without name.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.push(TAG_COMPOUND);
+        self.encode_payload(out);
+    }
+
+    /// Number of bytes [`Self::encode`] writes.
+    pub fn encoded_size(&self) -> usize {
+        1 + self.payload_size()
+    }
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        for (name, tag) in &self.0 {
+            out.push(tag.type_id());
+            encode_string(name, out);
+            tag.encode_payload(out);
+        }
+        out.push(TAG_END);
+    }
+
+    fn payload_size(&self) -> usize {
+        self.0.iter().map(|(name, tag)| 1 + 2 + name.len() + tag.payload_size()).sum::<usize>() + 1
+    }
+}
+
+fn encode_string(value: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn encoded(compound: &Compound) -> Vec<u8> {
+        let mut out = Vec::new();
+        compound.encode(&mut out);
+        out
+    }
+
+    #[test]
+    fn encodes_the_root_without_name() {
+        let mut compound = Compound::new();
+        compound.insert("a", Tag::Short(0x0102));
+        assert_eq!(encoded(&compound), [TAG_COMPOUND, TAG_SHORT, 0, 1, b'a', 1, 2, TAG_END]);
+        assert_eq!(encoded(&Compound::new()), [TAG_COMPOUND, TAG_END]);
+    }
+
+    #[test]
+    fn encoded_size_is_the_encoded_length() {
+        let mut inner = Compound::new();
+        inner.insert("name", Tag::String(String::from("größe")));
+        inner.insert("list", Tag::List(vec![Tag::Int(1), Tag::Int(2)]));
+
+        let mut compound = Compound::new();
+        compound.insert("byte", Tag::Byte(-1));
+        compound.insert("short", Tag::Short(2));
+        compound.insert("int", Tag::Int(3));
+        compound.insert("long", Tag::Long(4));
+        compound.insert("float", Tag::Float(5.0));
+        compound.insert("double", Tag::Double(6.0));
+        compound.insert("byte_array", Tag::ByteArray(vec![1, 2, 3]));
+        compound.insert("empty_list", Tag::List(Vec::new()));
+        compound.insert("compounds", Tag::List(vec![Tag::Compound(inner.clone()), Tag::Compound(Compound::new())]));
+        compound.insert("compound", Tag::Compound(inner));
+        compound.insert("int_array", Tag::IntArray(vec![1, 2]));
+        compound.insert("long_array", Tag::LongArray(vec![1, 2, 3]));
+
+        assert_eq!(compound.encoded_size(), encoded(&compound).len());
+        assert_eq!(Compound::new().encoded_size(), encoded(&Compound::new()).len());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::network::{EIOReadExactError, ReadNumPrimitive};
+use crate::network::{EIOError, EIOReadExactError, ReadNumPrimitive, WriteNumPrimitive};
 use crate::pos::BlockPos;
 use num_traits::PrimInt;
 
@@ -14,3 +14,15 @@ pub trait ReadBlockPos: embedded_io_async::Read {
 }
 
 impl<T: embedded_io_async::Read> ReadBlockPos for T {}
+
+#[allow(async_fn_in_trait)]
+pub trait WriteBlockPos: embedded_io_async::Write {
+    async fn write_block_pos(mut self: &mut Self, pos: BlockPos) -> Result<(), EIOError<Self::Error>> {
+        let packed = ((pos.x as u64 & 0x3FFFFFF) << 38)
+            | ((pos.z as u64 & 0x3FFFFFF) << 12)
+            | (pos.y as u64 & 0xFFF);
+        self.write_be(packed).await
+    }
+}
+
+impl<T: embedded_io_async::Write> WriteBlockPos for T {}
@@ -0,0 +1,58 @@
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+
+/// Maximum number of idle buffers kept around.
+const MAX_POOLED_BUFFERS: usize = 32;
+/// Buffers bigger than this are freed instead of pooled.
+const MAX_POOLED_BUFFER_CAPACITY: usize = 256 * 1024;
+/// Pooled buffers up to this capacity serve any request that fits,
+/// bigger ones only requests of at least a quarter of their capacity.
+const SMALL_BUFFER_CAPACITY: usize = 4 * 1024;
+
+static POOL: Mutex<CriticalSectionRawMutex, RefCell<Vec<Vec<u8>>>> =
+    Mutex::new(RefCell::new(Vec::new()));
+
+/// Pool of byte buffers backing [`super::MCPacketBuffer`] and [`super::MCPacketBatch`],
+/// so that steady-state packet traffic doesn't allocate (and fragment the heap).
+pub struct BufferPool;
+
+impl BufferPool {
+    /// Takes an empty buffer with at least `capacity` bytes of capacity.
+    /// The smallest pooled buffer that fits is reused, buffers much bigger than needed are left for big requests.
+    pub fn take(capacity: usize) -> Vec<u8> {
+        let buffer = POOL.lock(|pool| {
+            let mut pool = pool.borrow_mut();
+            let index = pool
+                .iter()
+                .enumerate()
+                .filter(|(_, buf)| fits(buf.capacity(), capacity))
+                .min_by_key(|(_, buf)| buf.capacity())
+                .map(|(index, _)| index);
+            index.map(|index| pool.swap_remove(index))
+        });
+        buffer.unwrap_or_else(|| Vec::with_capacity(capacity))
+    }
+
+    /// Returns a buffer to the pool.
+    pub fn give(mut buffer: Vec<u8>) {
+        if buffer.capacity() == 0 || buffer.capacity() > MAX_POOLED_BUFFER_CAPACITY {
+            return;
+        }
+        buffer.clear();
+        POOL.lock(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.capacity() == 0 {
+                pool.reserve_exact(MAX_POOLED_BUFFERS);
+            }
+            if pool.len() < MAX_POOLED_BUFFERS {
+                pool.push(buffer);
+            }
+        });
+    }
+}
+
+fn fits(buffer_capacity: usize, capacity: usize) -> bool {
+    buffer_capacity >= capacity && (buffer_capacity <= SMALL_BUFFER_CAPACITY || buffer_capacity / 4 <= capacity)
+}
@@ -1,19 +1,26 @@
-// use alloc::vec::Vec;
-//
-// struct ByteBuf(Vec<u8>);
-//
-// impl embedded_io_async::Write for ByteBuf {
-//     async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
-//         self.0.extend_from_slice(buf);
-//         Ok(buf.len())
-//     }
-//
-//     async fn flush(&mut self) -> Result<(), Self::Error> {
-//         unimplemented!()
-//     }
-//
-//     async fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
-//         self.write(buf).await?;
-//         Ok(())
-//     }
-// }
\ No newline at end of file
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+/// In-memory writer, for encoding data that isn't a whole packet.
+#[derive(Debug, Default)]
+pub struct ByteBuf(pub Vec<u8>);
+
+impl embedded_io_async::ErrorType for ByteBuf {
+    type Error = Infallible;
+}
+
+impl embedded_io_async::Write for ByteBuf {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.write(buf).await?;
+        Ok(())
+    }
+}
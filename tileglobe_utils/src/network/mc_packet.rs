@@ -1,4 +1,4 @@
-use crate::network::{EIOError, WriteVarInt};
+use crate::network::{BufferPool, EIOError, WriteVarInt};
 use alloc::vec::Vec;
 use core::convert::Infallible;
 use defmt_or_log::debug;
@@ -27,10 +27,16 @@ impl embedded_io_async::Write for MCPacketBuffer {
     }
 }
 
+impl Drop for MCPacketBuffer {
+    fn drop(&mut self) {
+        BufferPool::give(core::mem::take(&mut self.buffer));
+    }
+}
+
 impl MCPacketBuffer {
     pub async fn with_capacity(packet_type: i32, capacity: usize) -> Self {
         let mut pkt = Self {
-            buffer: Vec::with_capacity(capacity),
+            buffer: BufferPool::take(capacity),
         };
         pkt.write_varint::<i32>(packet_type).await.unwrap();
         pkt
@@ -39,6 +45,68 @@ impl MCPacketBuffer {
     pub async fn new(packet_type: i32) -> Self {
         Self::with_capacity(packet_type, 64).await
     }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// Length-prefixed packet frames collected in memory, so that they can be written out with a single
+/// socket write.
+pub struct MCPacketBatch {
+    buffer: Vec<u8>,
+}
+
+impl embedded_io_async::ErrorType for MCPacketBatch {
+    type Error = Infallible;
+}
+
+impl embedded_io_async::Write for MCPacketBatch {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.write(buf).await?;
+        Ok(())
+    }
+}
+
+impl Drop for MCPacketBatch {
+    fn drop(&mut self) {
+        BufferPool::give(core::mem::take(&mut self.buffer));
+    }
+}
+
+impl MCPacketBatch {
+    pub fn new() -> Self {
+        Self {
+            buffer: BufferPool::take(1024),
+        }
+    }
+
+    pub async fn push(&mut self, pkt: &MCPacketBuffer) {
+        self.buffer.reserve(5 + pkt.len());
+        self.write_varint::<u32>(pkt.len() as u32).await.unwrap();
+        self.buffer.extend_from_slice(&pkt.buffer);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
 }
 
 async fn write_all_with_log<TX: embedded_io_async::Write>(tx: &mut TX, buf: &[u8]) -> Result<(), TX::Error> {
@@ -64,6 +132,14 @@ pub trait WriteMCPacket: embedded_io_async::Write {
         write_all_with_log(&mut self, &*pkt.buffer).await?;
         Ok(())
     }
+
+    async fn write_mc_packet_batch(
+        mut self: &mut Self,
+        batch: &MCPacketBatch,
+    ) -> Result<(), EIOError<Self::Error>> {
+        write_all_with_log(&mut self, &*batch.buffer).await?;
+        Ok(())
+    }
 }
 
 impl<T: embedded_io_async::Write> WriteMCPacket for T {}
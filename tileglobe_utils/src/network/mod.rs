@@ -1,4 +1,5 @@
 mod mc_packet;
+mod buffer_pool;
 mod primitives;
 mod string;
 mod uuid;
@@ -11,12 +12,14 @@ mod bool;
 
 pub use error_wrappers::*;
 pub use mc_packet::*;
+pub use buffer_pool::*;
 pub use primitives::*;
 pub use string::*;
 pub use uuid::*;
 pub use varint::*;
 pub use enums::*;
 pub use bitbuf::*;
+pub use bytebuf::*;
 pub use block_pos::*;
 pub use bool::*;
 
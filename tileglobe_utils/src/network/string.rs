@@ -1,5 +1,6 @@
 use crate::network::{EIOError, EIOReadExactError, ReadVarInt, ReadVarIntError, WriteVarInt};
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::{FromUtf8Error, String};
 use core::error::Error;
 
@@ -44,3 +45,39 @@ pub trait WriteUTF8: embedded_io_async::Write {
 }
 
 impl<T: embedded_io_async::Write> WriteUTF8 for T {}
+
+#[allow(async_fn_in_trait)]
+pub trait WriteTextComponent: embedded_io_async::Write {
+    /// Plain text component, as a network NBT string tag.
+    /// Note that NBT uses modified UTF-8, which differs for NUL and characters outside the BMP.
+    /// Text longer than the 65535 bytes a string tag holds is cut at the last character fitting.
+    async fn write_text_component(&mut self, text: &str) -> Result<(), EIOError<Self::Error>> {
+        let mut len = text.len().min(u16::MAX as usize);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        let data = &text.as_bytes()[..len];
+        self.write_all(&[0x08]).await?; // TAG_String
+        self.write_all(&(data.len() as u16).to_be_bytes()).await?;
+        self.write_all(data).await?;
+        Ok(())
+    }
+
+    /// Plain text component, as a JSON string, the format used during login.
+    async fn write_json_text_component(mut self: &mut Self, text: &str) -> Result<(), EIOError<Self::Error>> {
+        let mut json = String::with_capacity(text.len() + 12);
+        json.push_str(r#"{"text":""#);
+        for char in text.chars() {
+            match char {
+                '"' => json.push_str(r#"\""#),
+                '\\' => json.push_str(r"\\"),
+                '\u{0}'..='\u{1F}' => json.push_str(&format!(r"\u{:04x}", char as u32)),
+                _ => json.push(char),
+            }
+        }
+        json.push_str(r#""}"#);
+        self.write_utf8(&json).await
+    }
+}
+
+impl<T: embedded_io_async::Write> WriteTextComponent for T {}
@@ -51,6 +51,8 @@ impl PartialOrd for BlockPos {
 #[derive(
     Copy,
     Clone,
+    PartialEq,
+    Eq,
     derive_more::Deref,
     derive_more::DerefMut,
     derive_more::From,
@@ -68,7 +70,16 @@ impl ChunkPos {
 }
 
 #[derive(
-    Copy, Clone, derive_more::From, derive_more::Into, derive_more::Debug, derive_more::Display,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::From,
+    derive_more::Into,
+    derive_more::Debug,
+    derive_more::Display,
 )]
 #[debug("ChunkLocalPos({}, {}, {})", self.x(), self.y(), self.z())]
 #[display("[{}, {}, {}]", self.x(), self.y(), self.z())]
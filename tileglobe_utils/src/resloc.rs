@@ -3,6 +3,7 @@
 use crate::MINECRAFT;
 use alloc::borrow::{Cow, ToOwned};
 use alloc::string::String;
+use alloc::vec::Vec;
 use core::fmt::{Display, Formatter};
 use core::marker::PhantomData;
 use serde::de::{Error, Visitor};
@@ -180,4 +181,12 @@ impl ResLoc<'static> {
     pub fn de_owned<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         Ok(ResLoc::deserialize(deserializer)?.into_owned())
     }
+
+    pub fn de_owned_option<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Self>, D::Error> {
+        Ok(Option::<ResLoc>::deserialize(deserializer)?.map(ResLoc::into_owned))
+    }
+
+    pub fn de_owned_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Self>, D::Error> {
+        Ok(Vec::<ResLoc>::deserialize(deserializer)?.into_iter().map(ResLoc::into_owned).collect())
+    }
 }